@@ -0,0 +1,294 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::tasks::{AsyncComputeTaskPool, Task};
+use chess::{ChessMove, PieceColour, Position, Score, SearchInfo, SearchLimits, UciEngine};
+use futures_lite::future;
+
+use crate::board::{
+    play_move, ChessGame, DrawReason, GameStatus, MoveMadeEvent, MoveStack, NewGameEvent, Player,
+    PlayerTurn, Players, Pockets, StartingPosition, Taken,
+};
+use crate::chess_clock::ChessClock;
+use crate::pieces::Piece;
+use crate::states::{can_step_through_moves, AppState};
+
+/// How many moves the computer expects to still have to make when sharing out its time on the
+/// clock
+const EXPECTED_MOVES: u32 = 30;
+
+pub struct AiPlugin;
+
+impl Plugin for AiPlugin {
+    fn build(&self, app: &mut App) {
+        app // new line
+            .init_resource::<AiSettings>()
+            .init_resource::<Thinking>()
+            .add_system(start_thinking.in_set(OnUpdate(AppState::InGame)))
+            .add_system(answer_draw_offer.in_set(OnUpdate(AppState::InGame)))
+            .add_system(play_best_move)
+            .add_system(analyse.run_if(can_step_through_moves))
+            .add_system(start_engine_game);
+    }
+}
+
+/// How long the computer thinks about each move, and the UCI engine that finds its moves instead
+/// of the built-in search if one was given
+#[derive(Resource)]
+pub struct AiSettings {
+    pub limits: SearchLimits,
+    pub engine: Option<Arc<Mutex<UciEngine>>>,
+}
+
+impl Default for AiSettings {
+    fn default() -> Self {
+        AiSettings {
+            limits: SearchLimits {
+                time: Some(Duration::from_secs(1)),
+                ..SearchLimits::default()
+            },
+            engine: None,
+        }
+    }
+}
+
+/// The search for the computer's next move, running in the background so the board keeps
+/// rendering
+#[derive(Resource, Default)]
+struct Thinking(Option<Search>);
+
+struct Search {
+    task: Task<Option<ChessMove>>,
+    stop: Arc<AtomicBool>,
+    /// The key of the position being searched, so the result is only used if the game has not
+    /// moved on
+    key: u64,
+}
+
+/// Starts searching for a move when it is the computer's turn
+///
+/// The computer waits while there are moves that have been taken back, so they can be stepped
+/// through with U and R rather than being replaced by its own moves. When playing with a clock it
+/// thinks for less time if it would otherwise run short
+#[allow(clippy::too_many_arguments)]
+fn start_thinking(
+    mut thinking: ResMut<Thinking>,
+    settings: Res<AiSettings>,
+    clock: Res<ChessClock>,
+    players: Res<Players>,
+    turn: Res<PlayerTurn>,
+    game: Res<ChessGame>,
+    starting_position: Res<StartingPosition>,
+    stack: Res<MoveStack>,
+) {
+    let position = game.0.position();
+    if thinking.0.is_some()
+        || players.of(turn.0) != Player::Computer
+        || position.side_to_move() != turn.0
+        || !stack.redo.is_empty()
+    {
+        return;
+    }
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let key = position.key();
+    let mut limits = settings.limits;
+    if let Some(clock) = &clock.0 {
+        let share = clock.remaining(turn.0) / EXPECTED_MOVES;
+        limits.time = Some(limits.time.map_or(share, |time| time.min(share)));
+    }
+    let task_stop = stop.clone();
+    let task = match &settings.engine {
+        // the engine is sent the moves that have been made on the board
+        Some(engine) => {
+            let engine = engine.clone();
+            let starting_position = starting_position.0.clone();
+            let moves = stack.moves();
+            AsyncComputeTaskPool::get().spawn(async move {
+                engine
+                    .lock()
+                    .unwrap()
+                    .best_move(&starting_position, &moves, limits, &task_stop, |_| {})
+                    .map_err(|err| println!("The engine was unable to move: {err}"))
+                    .ok()
+            })
+        }
+        None => {
+            let game = game.0.clone();
+            AsyncComputeTaskPool::get().spawn(async move {
+                game.search(limits, &task_stop, |_| {})
+                    .map(|info| info.best_move())
+            })
+        }
+    };
+
+    thinking.0 = Some(Search { task, stop, key });
+}
+
+/// Plays the move found by the search through the same path as a move picked on the board
+///
+/// The search is abandoned if the game moves on while it is running, from a move being taken
+/// back, a new game being started or the game being left
+fn play_best_move(
+    mut commands: Commands,
+    mut thinking: ResMut<Thinking>,
+    state: Res<State<AppState>>,
+    game: Res<ChessGame>,
+    pieces: Query<(Entity, &Piece), Without<Taken>>,
+    pockets: Res<Pockets>,
+    mut move_made_event: EventWriter<MoveMadeEvent>,
+) {
+    let Some(search) = &thinking.0 else {
+        return;
+    };
+
+    if state.0 != AppState::InGame || game.0.position().key() != search.key {
+        search.stop.store(true, Ordering::Relaxed);
+        thinking.0 = None;
+        return;
+    }
+    if !search.task.is_finished() {
+        return;
+    }
+
+    let search = thinking.0.take().unwrap();
+    if let Some(chess_move) = future::block_on(search.task) {
+        play_move(
+            &mut commands,
+            game.0.position(),
+            &pieces,
+            &pockets,
+            &chess_move,
+            &mut move_made_event,
+        );
+    }
+}
+
+/// Accepts a draw offered to the computer unless it thinks it is ahead
+fn answer_draw_offer(
+    mut game: ResMut<ChessGame>,
+    players: Res<Players>,
+    mut game_status: ResMut<GameStatus>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let Some(offered_by) = game.0.draw_offer() else {
+        return;
+    };
+    let colour = offered_by.opponent();
+    if players.of(colour) != Player::Computer {
+        return;
+    }
+
+    let position = game.0.position();
+    let evaluation = if position.side_to_move() == colour {
+        position.evaluate()
+    } else {
+        -position.evaluate()
+    };
+
+    if evaluation <= 0 && game.0.accept_draw(colour).is_ok() {
+        *game_status = GameStatus::Draw(DrawReason::Agreement);
+        next_state.set(AppState::GameOver);
+    } else if game.0.decline_draw(colour).is_ok() {
+        println!("The computer declines the draw");
+        game_status.set_changed();
+    }
+}
+
+/// Prints the evaluation and best line of the position on the board when A is pressed, found by
+/// the engine if there is one
+fn analyse(
+    keys: Res<Input<KeyCode>>,
+    settings: Res<AiSettings>,
+    game: Res<ChessGame>,
+    starting_position: Res<StartingPosition>,
+    stack: Res<MoveStack>,
+) {
+    if !keys.just_pressed(KeyCode::A) {
+        return;
+    }
+
+    let limits = settings.limits;
+    let position = game.0.position().clone();
+    let task = match &settings.engine {
+        Some(engine) => {
+            let engine = engine.clone();
+            let starting_position = starting_position.0.clone();
+            let moves = stack.moves();
+            AsyncComputeTaskPool::get().spawn(async move {
+                let mut last_info = None;
+                let result = engine.lock().unwrap().best_move(
+                    &starting_position,
+                    &moves,
+                    limits,
+                    &AtomicBool::new(false),
+                    |info| last_info = Some(info.clone()),
+                );
+                match (result, last_info) {
+                    (Ok(_), Some(info)) => println!("{}", describe(&position, &info)),
+                    (Ok(chess_move), None) => {
+                        println!("Best move: {}", position.to_san(&chess_move))
+                    }
+                    (Err(err), _) => println!("The engine was unable to analyse: {err}"),
+                }
+            })
+        }
+        None => {
+            let game = game.0.clone();
+            AsyncComputeTaskPool::get().spawn(async move {
+                match game.search(limits, &AtomicBool::new(false), |_| {}) {
+                    Some(info) => println!("{}", describe(&position, &info)),
+                    None => println!("There are no moves to analyse"),
+                }
+            })
+        }
+    };
+    task.detach();
+}
+
+/// Describes a search from White's point of view with its line in SAN, e.g.
+/// "Depth 6, +0.35: 1. e4 e5 2. Nf3"
+fn describe(position: &Position, info: &SearchInfo) -> String {
+    let sign = match position.side_to_move() {
+        PieceColour::White => 1,
+        PieceColour::Black => -1,
+    };
+    let score = match info.score {
+        Score::Centipawns(centipawns) => format!("{:+.2}", f64::from(sign * centipawns) / 100.0),
+        Score::Mate(moves) => format!("#{}", sign * moves),
+    };
+
+    let mut position = position.clone();
+    let mut line = Vec::new();
+    for chess_move in &info.pv {
+        let move_number = position.fullmove_number();
+        match position.side_to_move() {
+            PieceColour::White => line.push(format!("{move_number}.")),
+            PieceColour::Black if line.is_empty() => line.push(format!("{move_number}...")),
+            PieceColour::Black => {}
+        }
+        line.push(position.to_san(chess_move));
+        position.make_move(*chess_move);
+    }
+    format!("Depth {}, {score}: {}", info.depth, line.join(" "))
+}
+
+/// Tells the engine, if there is one, when a new game is started
+fn start_engine_game(settings: Res<AiSettings>, mut events: EventReader<NewGameEvent>) {
+    if events.iter().count() == 0 {
+        return;
+    }
+    let Some(engine) = settings.engine.clone() else {
+        return;
+    };
+
+    AsyncComputeTaskPool::get()
+        .spawn(async move {
+            if let Err(err) = engine.lock().unwrap().new_game() {
+                println!("Unable to start a new game with the engine: {err}");
+            }
+        })
+        .detach();
+}
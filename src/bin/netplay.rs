@@ -0,0 +1,143 @@
+//! Plays a game over the network from the terminal, without the 3d board
+//!
+//! `netplay host <address> [white|black]` waits for the other player to connect, playing White
+//! unless told otherwise, `netplay connect <address>` joins a game being hosted and
+//! `netplay watch <address>` follows it as a spectator. Moves are typed in UCI notation, and
+//! `resign`, `offer`, `accept`, `decline` and `claim` resign, offer, accept or decline a draw or
+//! claim one. `show` prints the game so far as a UCI `position` command and `quit` leaves.
+//! Everything that happens on the connection is printed a line at a time, which makes it easy to
+//! drive two of these from a script
+
+use std::env;
+use std::io::{stdin, BufRead};
+use std::process;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+use chess::{position_command, Game, NetworkPeer, NetworkRole, PieceColour, PlayerAction};
+
+/// How often the connection is checked while waiting for input
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut peer = match start(&args) {
+        Ok(peer) => peer,
+        Err(err) => {
+            eprintln!("{err}");
+            process::exit(1);
+        }
+    };
+
+    // stdin is read on another thread so that moves from the other player are printed as they
+    // arrive
+    let (sender, lines) = mpsc::channel();
+    thread::spawn(move || {
+        for line in stdin().lock().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    loop {
+        for event in peer.poll() {
+            println!("{event}");
+        }
+        match lines.recv_timeout(POLL_INTERVAL) {
+            Ok(line) => {
+                if !handle(&mut peer, line.trim()) {
+                    break;
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+}
+
+fn start(args: &[String]) -> Result<NetworkPeer, String> {
+    match args {
+        [command, address, rest @ ..] if command == "host" && rest.len() <= 1 => {
+            let colour = match rest.first().map(String::as_str) {
+                None | Some("white") => PieceColour::White,
+                Some("black") => PieceColour::Black,
+                Some(colour) => return Err(format!("invalid colour '{colour}'")),
+            };
+            let peer = NetworkPeer::host(address, colour, Game::default())?;
+            if let Some(local_addr) = peer.local_addr() {
+                println!("listening on {local_addr}");
+            }
+            Ok(peer)
+        }
+        [command, address] if command == "connect" => {
+            let peer = NetworkPeer::connect(address)?;
+            if let Some(colour) = peer.colour() {
+                println!("connected as {colour}");
+            }
+            Ok(peer)
+        }
+        [command, address] if command == "watch" => {
+            let peer = NetworkPeer::watch(address)?;
+            println!("watching");
+            Ok(peer)
+        }
+        _ => Err(
+            "usage: netplay host <address> [white|black] | netplay connect <address> | \
+             netplay watch <address>"
+                .to_string(),
+        ),
+    }
+}
+
+/// Carries out a line typed by the player, returning false once they want to leave
+fn handle(peer: &mut NetworkPeer, line: &str) -> bool {
+    match line {
+        "quit" => return false,
+        "show" => {
+            let moves: Vec<_> = peer.game().moves().copied().collect();
+            println!(
+                "{}",
+                position_command(peer.game().starting_position(), &moves)
+            );
+        }
+        "" => {}
+        "resign" | "offer" | "accept" | "decline" | "claim" => {
+            let Some(colour) = peer.colour() else {
+                println!("error spectators can only watch");
+                return true;
+            };
+            let action = match line {
+                "resign" => PlayerAction::Resign(colour),
+                "offer" => PlayerAction::OfferDraw(colour),
+                "accept" => PlayerAction::AcceptDraw(colour),
+                "decline" => PlayerAction::DeclineDraw(colour),
+                _ => PlayerAction::ClaimDraw,
+            };
+            if let Err(err) = peer.act(action) {
+                println!("error {err}");
+            }
+        }
+        _ if peer.role() == NetworkRole::Spectator => println!("error spectators cannot move"),
+        // the turn is checked first, as the opponent's moves would otherwise be reported as
+        // illegal
+        _ if Some(peer.game().position().side_to_move()) != peer.colour() => {
+            println!("error it is not your turn");
+        }
+        uci => {
+            let result = peer
+                .game()
+                .position()
+                .parse_uci(uci)
+                .and_then(|chess_move| peer.play(chess_move));
+            if let Err(err) = result {
+                println!("error {err}");
+            }
+        }
+    }
+    true
+}
@@ -0,0 +1,283 @@
+//! Runs the rules and the built-in search as a UCI engine, for chess GUIs and tournament managers
+//!
+//! Commands are read from stdin and answered on stdout. Searches run on their own thread, so
+//! `stop` and `isready` are answered while the engine is thinking
+
+use std::io::{stdin, BufRead};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use chess::{
+    divide, parse_position_command, variant_by_name, ChessMove, Game, PieceColour, Position, Score,
+    SearchLimits, Standard, Variant, VARIANTS,
+};
+
+/// How many moves are assumed to be left when sharing out the time on the clock
+const EXPECTED_MOVES: u32 = 30;
+const DEFAULT_MOVE_OVERHEAD: u64 = 30;
+
+fn main() {
+    let mut engine = Engine::default();
+    for line in stdin().lock().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if !engine.handle(&line) {
+            break;
+        }
+    }
+    engine.stop();
+}
+
+struct Engine {
+    game: Game,
+    /// The variant set with UCI_Variant, which new games are played under
+    variant: &'static dyn Variant,
+    /// Time kept back from each move for the delay in sending it to the GUI
+    move_overhead: Duration,
+    /// Whether UCI_Chess960 is set, when castling is always written as the King taking its own
+    /// rook, even from the standard starting position
+    chess960: bool,
+    search: Option<(JoinHandle<()>, Arc<AtomicBool>)>,
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Engine {
+            game: Game::default(),
+            variant: &Standard,
+            move_overhead: Duration::from_millis(DEFAULT_MOVE_OVERHEAD),
+            chess960: false,
+            search: None,
+        }
+    }
+}
+
+impl Engine {
+    /// Carries out a command, returning false once the engine should quit
+    fn handle(&mut self, line: &str) -> bool {
+        let mut words = line.split_whitespace();
+        let result = match words.next() {
+            Some("uci") => {
+                println!("id name bevy-chess");
+                println!("id author the bevy-chess authors");
+                println!(
+                    "option name Move Overhead type spin default {DEFAULT_MOVE_OVERHEAD} min 0 max 5000"
+                );
+                let variants: String = VARIANTS
+                    .iter()
+                    .map(|variant| format!(" var {}", variant.uci_name()))
+                    .collect();
+                println!(
+                    "option name UCI_Variant type combo default {}{variants}",
+                    Standard.uci_name()
+                );
+                println!("option name UCI_Chess960 type check default false");
+                println!("uciok");
+                Ok(())
+            }
+            Some("isready") => {
+                println!("readyok");
+                Ok(())
+            }
+            Some("ucinewgame") => {
+                self.stop();
+                self.game = Game::new(Position::starting(self.variant));
+                Ok(())
+            }
+            Some("setoption") => self.set_option(words),
+            Some("position") => {
+                self.stop();
+                parse_position_command(line, self.variant).map(|game| self.game = game)
+            }
+            Some("go") => {
+                self.stop();
+                self.go(words)
+            }
+            Some("perft") => self.perft(words),
+            Some("stop") => {
+                self.stop();
+                Ok(())
+            }
+            Some("quit") => return false,
+            // blank lines and commands this engine does not use, such as "debug" and
+            // "ponderhit", are ignored
+            _ => Ok(()),
+        };
+
+        if let Err(err) = result {
+            println!("info string {err}");
+        }
+        true
+    }
+
+    /// Handles `setoption name <name> value <value>`, where the name may contain spaces
+    fn set_option<'a>(&mut self, words: impl Iterator<Item = &'a str>) -> Result<(), String> {
+        let words: Vec<_> = words.collect();
+        let value_index = words.iter().position(|&word| word == "value");
+        let name = words[..value_index.unwrap_or(words.len())]
+            .iter()
+            .skip_while(|&&word| word == "name")
+            .copied()
+            .collect::<Vec<_>>()
+            .join(" ");
+        let value = value_index.map(|index| words[index + 1..].join(" "));
+
+        match (name.to_lowercase().as_str(), value) {
+            ("move overhead", Some(value)) => {
+                let milliseconds = value
+                    .parse()
+                    .map_err(|_| format!("invalid Move Overhead '{value}'"))?;
+                self.move_overhead = Duration::from_millis(milliseconds);
+                Ok(())
+            }
+            ("uci_variant", Some(value)) => {
+                self.variant =
+                    variant_by_name(&value).ok_or_else(|| format!("unknown variant '{value}'"))?;
+                self.stop();
+                self.game = Game::new(Position::starting(self.variant));
+                Ok(())
+            }
+            ("uci_chess960", Some(value)) => {
+                self.chess960 = value
+                    .parse()
+                    .map_err(|_| format!("invalid UCI_Chess960 '{value}'"))?;
+                Ok(())
+            }
+            _ => Err(format!("unknown option '{name}'")),
+        }
+    }
+
+    /// Starts searching the current position on another thread, which prints an `info` line as
+    /// each depth is completed and `bestmove` at the end
+    fn go<'a>(&mut self, mut words: impl Iterator<Item = &'a str>) -> Result<(), String> {
+        let mut limits = SearchLimits::default();
+        let mut clock = [None, None];
+        let mut increment = [Duration::ZERO, Duration::ZERO];
+        let mut moves_to_go = None;
+
+        while let Some(word) = words.next() {
+            let mut value = || {
+                words
+                    .next()
+                    .and_then(|value| value.parse::<u64>().ok())
+                    .ok_or_else(|| format!("expected a number after '{word}'"))
+            };
+            match word {
+                "perft" => return self.perft(words),
+                "depth" => limits.depth = Some(value()? as u32),
+                "nodes" => limits.nodes = Some(value()?),
+                "movetime" => limits.time = Some(Duration::from_millis(value()?)),
+                "wtime" => clock[0] = Some(Duration::from_millis(value()?)),
+                "btime" => clock[1] = Some(Duration::from_millis(value()?)),
+                "winc" => increment[0] = Duration::from_millis(value()?),
+                "binc" => increment[1] = Duration::from_millis(value()?),
+                "movestogo" => moves_to_go = Some(value()? as u32),
+                _ => {}
+            }
+        }
+
+        let side = match self.game.position().side_to_move() {
+            PieceColour::White => 0,
+            PieceColour::Black => 1,
+        };
+        if limits.time.is_none() {
+            if let Some(remaining) = clock[side] {
+                limits.time = Some(self.time_for_move(remaining, increment[side], moves_to_go));
+            }
+        }
+
+        let game = self.game.clone();
+        let chess960 = self.chess960;
+        let stop = Arc::new(AtomicBool::new(false));
+        let search_stop = stop.clone();
+        let handle = thread::spawn(move || {
+            let result = game.search(limits, &search_stop, |info| {
+                let score = match info.score {
+                    Score::Centipawns(centipawns) => format!("cp {centipawns}"),
+                    Score::Mate(moves) => format!("mate {moves}"),
+                };
+                let milliseconds = info.time.as_millis().max(1);
+                let pv = write_moves(game.position(), &info.pv, chess960);
+                println!(
+                    "info depth {} score {score} nodes {} nps {} time {} pv {}",
+                    info.depth,
+                    info.nodes,
+                    u128::from(info.nodes) * 1000 / milliseconds,
+                    info.time.as_millis(),
+                    pv.join(" ")
+                );
+            });
+
+            match result {
+                Some(info) => println!(
+                    "bestmove {}",
+                    write_moves(game.position(), &[info.best_move()], chess960)[0]
+                ),
+                // the game is over, but a GUI always expects an answer
+                None => println!("bestmove 0000"),
+            }
+        });
+
+        self.search = Some((handle, stop));
+        Ok(())
+    }
+
+    /// Shares out the time left on the clock, keeping back the move overhead so the engine never
+    /// loses on time
+    fn time_for_move(
+        &self,
+        remaining: Duration,
+        increment: Duration,
+        moves_to_go: Option<u32>,
+    ) -> Duration {
+        let moves = moves_to_go.unwrap_or(EXPECTED_MOVES).max(1);
+        let available = remaining.saturating_sub(self.move_overhead);
+        (remaining / moves + increment * 3 / 4).min(available / 2)
+    }
+
+    /// Prints the number of leaf nodes after each legal move `depth` moves deep, then the total
+    fn perft<'a>(&self, mut words: impl Iterator<Item = &'a str>) -> Result<(), String> {
+        let depth = words
+            .next()
+            .and_then(|depth| depth.parse().ok())
+            .ok_or("expected a depth for perft")?;
+
+        let mut position = self.game.position().clone();
+        let mut total = 0;
+        for (chess_move, nodes) in divide(&mut position, depth) {
+            println!("{chess_move}: {nodes}");
+            total += nodes;
+        }
+        println!();
+        println!("Nodes searched: {total}");
+        Ok(())
+    }
+
+    /// Stops any search that is running, waiting for it to print its best move
+    fn stop(&mut self) {
+        if let Some((handle, stop)) = self.search.take() {
+            stop.store(true, Ordering::Relaxed);
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Writes `moves`, played in turn from `position`, in UCI, with castling written as the King
+/// taking its own rook when `chess960` is set
+fn write_moves(position: &Position, moves: &[ChessMove], chess960: bool) -> Vec<String> {
+    let mut position = position.clone();
+    moves
+        .iter()
+        .map(|&chess_move| {
+            let written = match position.castling_rook(&chess_move) {
+                Some((rook_from, _)) if chess960 => format!("{}{rook_from}", chess_move.from),
+                _ => chess_move.to_string(),
+            };
+            position.make_move(chess_move);
+            written
+        })
+        .collect()
+}
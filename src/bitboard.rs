@@ -0,0 +1,232 @@
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, Not};
+
+use crate::{PieceColour, Square};
+
+/// A set of squares, with one bit per square. Bit 0 is a1, bit 7 is h1 and bit 63 is h8
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct Bitboard(pub u64);
+
+impl Bitboard {
+    pub const EMPTY: Bitboard = Bitboard(0);
+
+    pub fn from_square(square: Square) -> Bitboard {
+        Bitboard(1 << square.index())
+    }
+
+    pub fn contains(&self, square: Square) -> bool {
+        self.0 & (1 << square.index()) != 0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn count(&self) -> u32 {
+        self.0.count_ones()
+    }
+
+    /// Returns the square of the lowest set bit, if any
+    pub fn first(&self) -> Option<Square> {
+        (!self.is_empty()).then(|| Square::from_index(self.0.trailing_zeros() as u8))
+    }
+
+    pub fn squares(&self) -> BitboardSquares {
+        BitboardSquares(self.0)
+    }
+}
+
+/// Iterates over the squares in a `Bitboard` from a1 to h8
+pub struct BitboardSquares(u64);
+
+impl Iterator for BitboardSquares {
+    type Item = Square;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0 == 0 {
+            return None;
+        }
+
+        let index = self.0.trailing_zeros();
+        self.0 &= self.0 - 1;
+        Some(Square::from_index(index as u8))
+    }
+}
+
+impl BitAnd for Bitboard {
+    type Output = Bitboard;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        Bitboard(self.0 & rhs.0)
+    }
+}
+
+impl BitOr for Bitboard {
+    type Output = Bitboard;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Bitboard(self.0 | rhs.0)
+    }
+}
+
+impl Not for Bitboard {
+    type Output = Bitboard;
+
+    fn not(self) -> Self::Output {
+        Bitboard(!self.0)
+    }
+}
+
+impl BitAndAssign for Bitboard {
+    fn bitand_assign(&mut self, rhs: Self) {
+        self.0 &= rhs.0
+    }
+}
+
+impl BitOrAssign for Bitboard {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0
+    }
+}
+
+const KNIGHT_OFFSETS: [(i8, i8); 8] = [
+    (1, 2),
+    (-1, 2),
+    (1, -2),
+    (-1, -2),
+    (2, 1),
+    (-2, 1),
+    (2, -1),
+    (-2, -1),
+];
+const KING_OFFSETS: [(i8, i8); 8] = [
+    (0, 1),
+    (0, -1),
+    (1, 1),
+    (1, -1),
+    (-1, 1),
+    (-1, -1),
+    (1, 0),
+    (-1, 0),
+];
+
+/// The directions a sliding piece can move in as (rank, file) offsets. The first four increase the
+/// square index and the last four decrease it, which determines which end of a ray is nearest
+const DIRECTIONS: [(i8, i8); 8] = [
+    (0, 1),
+    (1, 0),
+    (1, 1),
+    (1, -1),
+    (0, -1),
+    (-1, 0),
+    (-1, -1),
+    (-1, 1),
+];
+const ROOK_DIRECTIONS: [usize; 4] = [0, 1, 4, 5];
+const BISHOP_DIRECTIONS: [usize; 4] = [2, 3, 6, 7];
+
+static KNIGHT_ATTACKS: [u64; 64] = step_attacks(&KNIGHT_OFFSETS);
+static KING_ATTACKS: [u64; 64] = step_attacks(&KING_OFFSETS);
+static WHITE_PAWN_ATTACKS: [u64; 64] = step_attacks(&[(1, 1), (1, -1)]);
+static BLACK_PAWN_ATTACKS: [u64; 64] = step_attacks(&[(-1, 1), (-1, -1)]);
+static RAYS: [[u64; 64]; 8] = rays();
+
+/// Builds the table of squares reachable with a single step of each offset from every square
+const fn step_attacks(offsets: &[(i8, i8)]) -> [u64; 64] {
+    let mut attacks = [0; 64];
+    let mut index = 0;
+    while index < 64 {
+        let (rank, file) = ((index / 8) as i8, (index % 8) as i8);
+        let mut i = 0;
+        while i < offsets.len() {
+            let (target_rank, target_file) = (rank + offsets[i].0, file + offsets[i].1);
+            if target_rank >= 0 && target_rank < 8 && target_file >= 0 && target_file < 8 {
+                attacks[index] |= 1 << (target_rank * 8 + target_file);
+            }
+            i += 1;
+        }
+        index += 1;
+    }
+    attacks
+}
+
+/// Builds the table of every square along each direction from every square, up to the board edge
+const fn rays() -> [[u64; 64]; 8] {
+    let mut rays = [[0; 64]; 8];
+    let mut direction = 0;
+    while direction < 8 {
+        let (rank_step, file_step) = DIRECTIONS[direction];
+        let mut index = 0;
+        while index < 64 {
+            let (mut rank, mut file) = ((index / 8) as i8, (index % 8) as i8);
+            loop {
+                rank += rank_step;
+                file += file_step;
+                if rank < 0 || rank >= 8 || file < 0 || file >= 8 {
+                    break;
+                }
+                rays[direction][index] |= 1 << (rank * 8 + file);
+            }
+            index += 1;
+        }
+        direction += 1;
+    }
+    rays
+}
+
+pub fn knight_attacks(square: Square) -> Bitboard {
+    Bitboard(KNIGHT_ATTACKS[square.index()])
+}
+
+pub fn king_attacks(square: Square) -> Bitboard {
+    Bitboard(KING_ATTACKS[square.index()])
+}
+
+/// The squares a pawn of `colour` on `square` attacks, which are not the squares it moves to
+pub fn pawn_attacks(colour: PieceColour, square: Square) -> Bitboard {
+    match colour {
+        PieceColour::White => Bitboard(WHITE_PAWN_ATTACKS[square.index()]),
+        PieceColour::Black => Bitboard(BLACK_PAWN_ATTACKS[square.index()]),
+    }
+}
+
+pub fn bishop_attacks(square: Square, occupied: Bitboard) -> Bitboard {
+    sliding_attacks(square, occupied, &BISHOP_DIRECTIONS)
+}
+
+pub fn rook_attacks(square: Square, occupied: Bitboard) -> Bitboard {
+    sliding_attacks(square, occupied, &ROOK_DIRECTIONS)
+}
+
+pub fn queen_attacks(square: Square, occupied: Bitboard) -> Bitboard {
+    bishop_attacks(square, occupied) | rook_attacks(square, occupied)
+}
+
+/// Classical ray attacks: each ray is followed until the first occupied square, which is included
+/// so that captures are generated, then everything beyond that blocker is removed
+fn sliding_attacks(square: Square, occupied: Bitboard, directions: &[usize]) -> Bitboard {
+    let mut attacks = 0;
+    for &direction in directions {
+        let ray = RAYS[direction][square.index()];
+        let blockers = ray & occupied.0;
+        attacks |= if blockers == 0 {
+            ray
+        } else if direction < 4 {
+            ray ^ RAYS[direction][blockers.trailing_zeros() as usize]
+        } else {
+            ray ^ RAYS[direction][63 - blockers.leading_zeros() as usize]
+        };
+    }
+    Bitboard(attacks)
+}
+
+/// The squares strictly between two squares on the same rank, file or diagonal
+///
+/// Returns an empty set if the squares are not aligned
+pub fn between(from: Square, to: Square) -> Bitboard {
+    RAYS.iter()
+        .find(|ray| ray[from.index()] & (1 << to.index()) != 0)
+        .map_or(Bitboard::EMPTY, |ray| {
+            Bitboard(ray[from.index()] & !ray[to.index()] & !(1 << to.index()))
+        })
+}
@@ -1,11 +1,19 @@
 use bevy::prelude::*;
 
-pub use components::{Promote, Square, Taken};
+pub use chess::{DeadPosition, DrawReason, Square};
+pub use components::{Promote, Taken};
 pub use events::{
-    MoveMadeEvent, MoveType, PromotionOutcome, ResetSelectedEvent, SelectPromotionOutcome,
+    GameAction, GameActionTaken, MoveMadeEvent, NewGameEvent, PromotionOutcome, ResetSelectedEvent,
+    SelectPromotionOutcome,
 };
-pub use resources::{DrawReason, GameStatus, MoveHistory, PlayerTurn};
-use resources::{Graveyard, MoveStack, SquareMaterials};
+use resources::SquareMaterials;
+pub use resources::{
+    ChessGame, GameStatus, Graveyard, ImportedGame, MoveHistory, MoveStack, PendingPromotion,
+    Player, PlayerNames, PlayerTurn, Players, Pockets, StartingPosition,
+};
+pub use systems::{play_move, put_back_move, show_game_action};
+
+use crate::states::{can_step_through_moves, AppState};
 
 mod components;
 mod events;
@@ -14,51 +22,67 @@ mod systems;
 
 pub struct BoardPlugin;
 
-pub const A_FILE: i8 = 0;
-pub const B_FILE: i8 = 1;
-pub const C_FILE: i8 = 2;
-pub const D_FILE: i8 = 3;
-pub const E_FILE: i8 = 4;
-pub const F_FILE: i8 = 5;
-pub const G_FILE: i8 = 6;
-pub const H_FILE: i8 = 7;
-
-pub const RANK_1: i8 = 0;
-pub const RANK_2: i8 = 1;
-pub const RANK_3: i8 = 2;
-pub const RANK_4: i8 = 3;
-pub const RANK_5: i8 = 4;
-pub const RANK_6: i8 = 5;
-pub const RANK_7: i8 = 6;
-pub const RANK_8: i8 = 7;
-
 impl Plugin for BoardPlugin {
     fn build(&self, app: &mut App) {
         app // new line
             // .init_resource::<SelectedSquare>()
             // .init_resource::<SelectedPiece>()
             .init_resource::<PlayerTurn>()
+            .init_resource::<Players>()
+            .init_resource::<PlayerNames>()
             .init_resource::<SquareMaterials>()
             .init_resource::<Graveyard>()
+            .init_resource::<Pockets>()
             .init_resource::<MoveStack>()
             .init_resource::<MoveHistory>()
             .init_resource::<GameStatus>()
+            .init_resource::<StartingPosition>()
+            .init_resource::<ChessGame>()
+            .init_resource::<PendingPromotion>()
+            .init_resource::<ImportedGame>()
             .add_event::<ResetSelectedEvent>()
+            .add_event::<NewGameEvent>()
+            .add_event::<GameAction>()
+            .add_event::<GameActionTaken>()
             .add_event::<MoveMadeEvent>()
             .add_event::<SelectPromotionOutcome>()
             .add_event::<PromotionOutcome>()
             .add_startup_system(systems::create_board)
-            .add_system(systems::select_square)
-            .add_system(systems::select_piece)
-            .add_system(systems::move_piece)
+            .add_startup_system(systems::load_starting_position)
+            .add_systems(
+                (
+                    systems::select_square,
+                    systems::select_piece,
+                    systems::select_pocket,
+                    systems::move_piece,
+                    systems::claim_draw,
+                )
+                    .in_set(OnUpdate(AppState::InGame))
+                    .distributive_run_if(systems::human_to_move),
+            )
+            .add_system(
+                systems::toggle_review
+                    .run_if(in_state(AppState::GameOver).or_else(in_state(AppState::Review))),
+            )
+            .add_systems(
+                (systems::undo_move, systems::redo_move)
+                    .distributive_run_if(can_step_through_moves),
+            )
+            .add_system(systems::apply_game_action.in_set(OnUpdate(AppState::InGame)))
+            .add_system(systems::request_new_game.run_if(not(in_state(AppState::MainMenu))))
             .add_system(systems::make_move)
             .add_system(systems::remove_taken_pieces)
+            .add_system(systems::explode_pieces)
             .add_system(systems::reset_selected)
             .add_system(systems::colour_moves)
+            .add_system(systems::update_pocket_slots)
             .add_system(systems::push_move)
-            .add_system(systems::select_promotion)
             .add_system(systems::promote_piece)
             .add_system(systems::update_move_history)
-            .add_system(systems::update_status);
+            .add_system(systems::update_status)
+            .add_system(systems::print_fen)
+            .add_system(systems::save_pgn)
+            .add_system(systems::start_new_game)
+            .add_system(systems::replay_imported_game);
     }
 }
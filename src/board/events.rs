@@ -1,17 +1,42 @@
 use bevy::prelude::Entity;
+use chess::{ChessMove, PlayerAction};
 
-use crate::board::components::Square;
+use crate::board::Square;
 use crate::pieces::{Piece, PieceType};
 
 pub struct ResetSelectedEvent;
 
+/// Sent to clear the board and start again from the `StartingPosition`
+pub struct NewGameEvent;
+
+/// Sent when a player ends or offers to end the game other than by moving
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub enum GameAction {
+    Resign,
+    OfferDraw,
+    AcceptDraw,
+    DeclineDraw,
+    /// Claims a draw by threefold repetition or the fifty-move rule
+    ClaimDraw,
+}
+
+/// Sent once a `GameAction` has been carried out in the game, so that it can be passed on to the
+/// other player in a game over the network
+#[derive(Clone, Copy)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct GameActionTaken(pub PlayerAction);
+
 #[derive(Clone, Copy)]
 #[cfg_attr(debug_assertions, derive(Debug))]
 pub struct MoveMadeEvent {
     pub piece: Piece,
     pub origin: Square,
+    /// The destination of the move as the rules engine writes it, which is the rook's square when
+    /// castling in Chess960
     pub destination: Square,
     pub move_type: MoveType,
+    pub promotion: Option<PieceType>,
 }
 
 #[derive(Clone, Copy)]
@@ -21,6 +46,8 @@ pub enum MoveType {
     Take(Entity),
     TakeEnPassant(Entity),
     Castle,
+    /// A piece dropped from the pocket, which is both the origin and destination of the move
+    Drop(Entity),
 }
 
 impl MoveMadeEvent {
@@ -46,6 +73,7 @@ impl MoveMadeEvent {
             destination,
             origin,
             move_type,
+            promotion: None,
         }
     }
 
@@ -55,14 +83,46 @@ impl MoveMadeEvent {
             destination,
             origin,
             move_type: MoveType::Castle,
+            promotion: None,
+        }
+    }
+
+    /// Dropping `piece` from the pocket onto the square given by its `pos`
+    pub fn drop(piece: Piece, entity: Entity) -> MoveMadeEvent {
+        MoveMadeEvent {
+            piece,
+            destination: piece.pos,
+            origin: piece.pos,
+            move_type: MoveType::Drop(entity),
+            promotion: None,
+        }
+    }
+
+    /// The move as understood by the rules engine
+    pub fn chess_move(&self) -> ChessMove {
+        if self.dropped_piece().is_some() {
+            return ChessMove::new_drop(self.piece.piece_type, self.destination);
+        }
+        ChessMove {
+            promotion: self.promotion,
+            ..ChessMove::new(self.origin, self.destination)
         }
     }
 
-    pub fn is_take(&self) -> bool {
-        matches!(
-            self.move_type,
-            MoveType::Take(_) | MoveType::TakeEnPassant(_)
-        )
+    /// Returns the entity of the piece taken by this move, if any
+    pub fn taken_piece(&self) -> Option<Entity> {
+        match self.move_type {
+            MoveType::Take(entity) | MoveType::TakeEnPassant(entity) => Some(entity),
+            MoveType::Move | MoveType::Castle | MoveType::Drop(_) => None,
+        }
+    }
+
+    /// Returns the entity of the piece dropped from the pocket by this move, if any
+    pub fn dropped_piece(&self) -> Option<Entity> {
+        match self.move_type {
+            MoveType::Drop(entity) => Some(entity),
+            _ => None,
+        }
     }
 }
 
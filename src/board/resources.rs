@@ -1,13 +1,49 @@
 use bevy::prelude::*;
+use chess::{ChessMove, Clock, DrawReason, Game, Position, Status};
 
 use crate::board::MoveMadeEvent;
-use crate::pieces::{Piece, PieceColour};
+use crate::pieces::{Piece, PieceColour, PieceType};
 
 #[derive(Resource, Default)]
 pub struct MoveStack {
-    pub stack: Vec<(MoveMadeEvent, Vec<Piece>)>,
+    /// The moves made, each with the pieces on the board before it, in variants where captures
+    /// explode the pieces it blew up with the capturing piece first, and the clock as it was before
+    /// it if that is known
+    #[allow(clippy::type_complexity)]
+    pub stack: Vec<(MoveMadeEvent, Vec<Piece>, Vec<Entity>, Option<Clock>)>,
+    /// Moves that have been taken back, with the most recently taken back last, each with the clock
+    /// as it was when it was taken back if that is known
+    pub redo: Vec<(MoveMadeEvent, Option<Clock>)>,
 }
 
+impl MoveStack {
+    /// The moves made on the board, in the order they were made
+    pub fn moves(&self) -> Vec<ChessMove> {
+        self.stack
+            .iter()
+            .map(|(move_made, _, _, _)| move_made.chess_move())
+            .collect()
+    }
+}
+
+/// The position the game was started from
+#[derive(Resource, Default)]
+pub struct StartingPosition(pub Position);
+
+/// The rules engine's view of the game, which is the source of truth for move legality and the
+/// status of the game
+#[derive(Resource, Default)]
+pub struct ChessGame(pub Game);
+
+/// A pawn move onto the last rank waiting for the player to choose a piece to promote to
+#[derive(Resource, Default)]
+pub struct PendingPromotion(pub Option<MoveMadeEvent>);
+
+/// The moves of a game loaded from PGN, waiting for the pieces to be on the board before they are
+/// lined up to be replayed
+#[derive(Resource, Default)]
+pub struct ImportedGame(pub Option<Vec<ChessMove>>);
+
 #[derive(Resource, Default)]
 pub struct MoveHistory(pub Vec<String>);
 
@@ -59,6 +95,14 @@ impl Graveyard {
         }
     }
 
+    /// Takes back the last grave given out by `next` when a taken piece returns to the board
+    pub fn previous(&mut self, colour: PieceColour) {
+        match colour {
+            PieceColour::White => self.previous_white(),
+            PieceColour::Black => self.previous_black(),
+        }
+    }
+
     fn next_white(&mut self) -> Vec3 {
         let current = self.white;
         self.white = if current.z >= 7.0 {
@@ -78,6 +122,164 @@ impl Graveyard {
         };
         current
     }
+
+    fn previous_white(&mut self) {
+        let current = self.white;
+        self.white = if current.z <= 0.0 {
+            Vec3::new(current.x + 1.0, current.y, 7.0)
+        } else {
+            Vec3::new(current.x, current.y, current.z - 1.0)
+        };
+    }
+
+    fn previous_black(&mut self) {
+        let current = self.black;
+        self.black = if current.z <= 0.0 {
+            Vec3::new(current.x - 1.0, current.y, 7.0)
+        } else {
+            Vec3::new(current.x, current.y, current.z - 1.0)
+        };
+    }
+}
+
+/// The pieces each player holds in hand in variants such as Crazyhouse, which wait in rows beside
+/// the board in place of the graveyard
+///
+/// Each type of piece has its own row, with the piece most recently added to a row being the first
+/// to be dropped and the furthest from the board
+#[derive(Resource, Clone, Default)]
+pub struct Pockets([[Vec<Entity>; 5]; 2]);
+
+impl Pockets {
+    /// The types of piece that can be held, in the order of the rows along the side of the board
+    pub const PIECE_TYPES: [PieceType; 5] = [
+        PieceType::Queen,
+        PieceType::Rook,
+        PieceType::Bishop,
+        PieceType::Knight,
+        PieceType::Pawn,
+    ];
+
+    /// The piece `taken` becomes in the pocket of `taker`: one of their own, and a pawn again if it
+    /// had been promoted
+    pub fn held_as(position: &Position, taken: Piece, taker: PieceColour) -> Piece {
+        let piece_type = if position.is_promoted(taken.pos) {
+            PieceType::Pawn
+        } else {
+            taken.piece_type
+        };
+        Piece {
+            colour: taker,
+            piece_type,
+            ..taken
+        }
+    }
+
+    /// Adds `entity` to the pocket of `colour`, returning where it waits to be dropped
+    pub fn push(&mut self, colour: PieceColour, piece_type: PieceType, entity: Entity) -> Vec3 {
+        let row = self.row_mut(colour, piece_type);
+        row.push(entity);
+        let place = row.len() - 1;
+        Pockets::translation(colour, piece_type, place)
+    }
+
+    /// The piece of `piece_type` that `colour` would drop next, if it holds one
+    pub fn last(&self, colour: PieceColour, piece_type: PieceType) -> Option<Entity> {
+        self.0[colour_index(colour)][row_index(piece_type)]
+            .last()
+            .copied()
+    }
+
+    /// Takes `entity` out of the pocket it is in, when it is dropped or a capture is taken back
+    pub fn remove(&mut self, entity: Entity) {
+        for row in self.0.iter_mut().flatten() {
+            row.retain(|&other| other != entity);
+        }
+    }
+
+    /// Where the piece `place` pieces out from the board in the row of `piece_type` waits, with
+    /// place 0 being the pocket's square that is picked to drop the piece
+    pub fn translation(colour: PieceColour, piece_type: PieceType, place: usize) -> Vec3 {
+        let file = 1.5 + row_index(piece_type) as f32;
+        match colour {
+            PieceColour::White => Vec3::new(-1.0 - place as f32, 0.0, file),
+            PieceColour::Black => Vec3::new(8.0 + place as f32, 0.0, file),
+        }
+    }
+
+    fn row_mut(&mut self, colour: PieceColour, piece_type: PieceType) -> &mut Vec<Entity> {
+        &mut self.0[colour_index(colour)][row_index(piece_type)]
+    }
+}
+
+fn colour_index(colour: PieceColour) -> usize {
+    match colour {
+        PieceColour::White => 0,
+        PieceColour::Black => 1,
+    }
+}
+
+fn row_index(piece_type: PieceType) -> usize {
+    Pockets::PIECE_TYPES
+        .iter()
+        .position(|&other| other == piece_type)
+        .expect("Kings are never held")
+}
+
+/// Who chooses the moves for a side
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub enum Player {
+    /// Moves are made by picking the pieces on the board
+    #[default]
+    Human,
+    /// Moves are found by the built-in search
+    Computer,
+    /// Moves are made by the other player in a game played over the network
+    Remote,
+}
+
+/// The player of each side
+#[derive(Resource, Default)]
+pub struct Players {
+    pub white: Player,
+    pub black: Player,
+}
+
+impl Players {
+    pub fn of(&self, colour: PieceColour) -> Player {
+        match colour {
+            PieceColour::White => self.white,
+            PieceColour::Black => self.black,
+        }
+    }
+
+    /// The side a player at the board acts for when resigning or offering a draw: the only
+    /// human player when playing the computer or over the network, or otherwise the side to move
+    pub fn acting_for(&self, turn: PieceColour) -> PieceColour {
+        match (self.white, self.black) {
+            (Player::Human, Player::Computer | Player::Remote) => PieceColour::White,
+            (Player::Computer | Player::Remote, Player::Human) => PieceColour::Black,
+            _ => turn,
+        }
+    }
+}
+
+/// The names of the players, recorded when the game is saved
+#[derive(Resource)]
+pub struct PlayerNames {
+    pub white: String,
+    pub black: String,
+}
+
+impl Default for PlayerNames {
+    fn default() -> Self {
+        // PGN's placeholder for an unknown name
+        PlayerNames {
+            white: "?".to_string(),
+            black: "?".to_string(),
+        }
+    }
 }
 
 #[derive(Resource)]
@@ -97,25 +299,40 @@ pub enum GameStatus {
     OnGoing,
     Check,
     Checkmate,
+    /// The player to move ran out of time, so the player in `PlayerTurn` wins
+    OutOfTime,
+    /// The player of the given colour resigned
+    Resigned(PieceColour),
+    /// The player of the given colour won by a rule of the variant being played
+    VariantWin(PieceColour),
     Draw(DrawReason),
 }
 
-/// The various different rules that can lead to a draw. Fivefold Repetition and DeadPosition are not
-/// yet checked. A full implementation of DeadPosition is probably beyond the scope of this project
-/// but the intent is to capture simple material based dead positions, but not capture more complex
-/// board state scenarios where in theory sufficient material exits for a mate but it is impossible
-/// to actually achieve mate.
-#[cfg_attr(debug_assertions, derive(Debug))]
-pub enum DrawReason {
-    Stalemate,
-    // FivefoldRepetition,
-    FiftyMoveRule,
-    // DeadPosition,
+impl GameStatus {
+    /// Returns true once the game has finished, however it ended
+    pub fn is_over(&self) -> bool {
+        matches!(
+            self,
+            GameStatus::Checkmate
+                | GameStatus::OutOfTime
+                | GameStatus::Resigned(_)
+                | GameStatus::VariantWin(_)
+                | GameStatus::Draw(_)
+        )
+    }
 }
 
-impl PlayerTurn {
-    pub fn change(&mut self) {
-        self.0 = self.0.opponent()
+impl From<Status> for GameStatus {
+    fn from(status: Status) -> Self {
+        match status {
+            Status::OnGoing => GameStatus::OnGoing,
+            Status::Check => GameStatus::Check,
+            Status::Checkmate => GameStatus::Checkmate,
+            Status::OutOfTime => GameStatus::OutOfTime,
+            Status::Resigned(colour) => GameStatus::Resigned(colour),
+            Status::VariantWin(colour) => GameStatus::VariantWin(colour),
+            Status::Draw(reason) => GameStatus::Draw(reason),
+        }
     }
 }
 
@@ -1,18 +1,34 @@
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+
 use bevy::prelude::*;
 use bevy_mod_picking::{Highlighting, PickableBundle, PickingEvent, Selection, SelectionEvent};
+use chess::{Game, PgnTags, PlayerAction};
 
-pub use movement::{colour_moves, make_move, move_piece, push_move, remove_taken_pieces};
+pub use history::{redo_move, replay_imported_game, undo_move};
+pub use movement::{
+    colour_moves, explode_pieces, make_move, move_piece, play_move, push_move, put_back_move,
+    remove_taken_pieces,
+};
+pub use pockets::{select_pocket, update_pocket_slots};
 
-use crate::board;
-use crate::board::components::{Move, Selected, Square, Taken};
+use crate::board::components::{Selected, Taken};
 use crate::board::events::ResetSelectedEvent;
-use crate::board::resources::{DrawReason, MoveHistory, MoveStack, PlayerTurn, SquareMaterials};
+use crate::board::resources::{
+    ChessGame, Graveyard, MoveHistory, MoveStack, PendingPromotion, Player, PlayerNames,
+    PlayerTurn, Players, SquareMaterials, StartingPosition,
+};
+use crate::board::systems::movement::moves_of;
 use crate::board::{
-    GameStatus, MoveMadeEvent, MoveType, Promote, PromotionOutcome, SelectPromotionOutcome,
+    GameAction, GameActionTaken, GameStatus, MoveMadeEvent, NewGameEvent, Promote,
+    PromotionOutcome, Square,
 };
-use crate::pieces::{Piece, PieceColour, PieceType};
+use crate::pieces::{Piece, PieceColour};
+use crate::states::AppState;
 
+mod history;
 mod movement;
+mod pockets;
 
 pub fn create_board(
     mut commands: Commands,
@@ -24,8 +40,8 @@ pub fn create_board(
         subdivisions: 0,
     }));
 
-    for rank in board::RANK_1..=board::RANK_8 {
-        for file in board::A_FILE..=board::H_FILE {
+    for rank in chess::RANK_1..=chess::RANK_8 {
+        for file in chess::A_FILE..=chess::H_FILE {
             let square = Square { rank, file };
             let initial_material = if square.is_white() {
                 square_materials.white_colour.clone()
@@ -56,6 +72,205 @@ pub fn create_board(
     }
 }
 
+/// Starts the game from the `StartingPosition`
+pub fn load_starting_position(
+    starting_position: Res<StartingPosition>,
+    mut game: ResMut<ChessGame>,
+    mut turn: ResMut<PlayerTurn>,
+) {
+    game.0 = chess::Game::new(starting_position.0.clone());
+    turn.0 = starting_position.0.side_to_move();
+}
+
+/// Starts a new game when N is pressed
+pub fn request_new_game(keys: Res<Input<KeyCode>>, mut new_game_event: EventWriter<NewGameEvent>) {
+    if keys.just_pressed(KeyCode::N) {
+        new_game_event.send(NewGameEvent);
+    }
+}
+
+/// Restarts the game from the `StartingPosition`, clearing everything recorded about the last game
+///
+/// The turn and status are updated from the new game by `update_status`, as the move stack changes
+pub fn start_new_game(
+    mut commands: Commands,
+    mut events: EventReader<NewGameEvent>,
+    starting_position: Res<StartingPosition>,
+    mut game: ResMut<ChessGame>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut reset_selected_event: EventWriter<ResetSelectedEvent>,
+) {
+    if events.iter().count() == 0 {
+        return;
+    }
+
+    next_state.set(AppState::InGame);
+    game.0 = chess::Game::new(starting_position.0.clone());
+    commands.insert_resource(MoveStack::default());
+    commands.insert_resource(MoveHistory::default());
+    commands.insert_resource(GameStatus::default());
+    commands.insert_resource(Graveyard::default());
+    commands.insert_resource(PendingPromotion::default());
+    reset_selected_event.send(ResetSelectedEvent);
+}
+
+/// Switches between reviewing the moves of a finished game and the game itself when V is pressed
+///
+/// Leaving the review continues the game from the position being reviewed
+pub fn toggle_review(
+    keys: Res<Input<KeyCode>>,
+    state: Res<State<AppState>>,
+    game: Res<ChessGame>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if keys.just_pressed(KeyCode::V) {
+        next_state.set(match state.0 {
+            AppState::Review => AppState::playing(game.0.status().is_over()),
+            _ => AppState::Review,
+        });
+    }
+}
+
+/// Prints the FEN of the current position when F is pressed
+pub fn print_fen(keys: Res<Input<KeyCode>>, game: Res<ChessGame>) {
+    if keys.just_pressed(KeyCode::F) {
+        println!("{}", game.0.position().to_fen());
+    }
+}
+
+/// Saves the game as PGN to a file in the working directory when S is pressed
+pub fn save_pgn(keys: Res<Input<KeyCode>>, game: Res<ChessGame>, names: Res<PlayerNames>) {
+    if !keys.just_pressed(KeyCode::S) {
+        return;
+    }
+
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let tags = PgnTags {
+        event: "Casual game".to_string(),
+        site: "bevy-chess".to_string(),
+        date: pgn_date(seconds),
+        white: names.white.clone(),
+        black: names.black.clone(),
+        ..PgnTags::default()
+    };
+
+    let path = format!("game-{seconds}.pgn");
+    match fs::write(&path, game.0.to_pgn(&tags)) {
+        Ok(()) => println!("Saved the game to {path}"),
+        Err(err) => println!("Unable to save the game to {path}: {err}"),
+    }
+}
+
+/// Formats the UTC date `seconds` after the Unix epoch as a PGN date, e.g. "2023.04.01"
+fn pgn_date(seconds: u64) -> String {
+    // converts days since the epoch to a date in the proleptic Gregorian calendar, counting years
+    // from March so the leap day falls at the end of the year
+    let days = (seconds / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{year:04}.{month:02}.{day:02}")
+}
+
+/// Claims a draw for the player to move when D is pressed, if they are entitled to one
+pub fn claim_draw(keys: Res<Input<KeyCode>>, mut game_action: EventWriter<GameAction>) {
+    if keys.just_pressed(KeyCode::D) {
+        game_action.send(GameAction::ClaimDraw);
+    }
+}
+
+/// Carries out resignations, draw offers and their answers, and draw claims
+///
+/// Draws are claimed by the player to move, while the other actions are taken for the player
+/// given by `Players::acting_for`
+pub fn apply_game_action(
+    mut events: EventReader<GameAction>,
+    mut actions_taken: EventWriter<GameActionTaken>,
+    mut game: ResMut<ChessGame>,
+    players: Res<Players>,
+    mut turn: ResMut<PlayerTurn>,
+    mut game_status: ResMut<GameStatus>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    for &action in events.iter() {
+        let colour = players.acting_for(turn.0);
+        let player_action = match action {
+            GameAction::Resign => PlayerAction::Resign(colour),
+            GameAction::OfferDraw => PlayerAction::OfferDraw(colour),
+            GameAction::AcceptDraw => PlayerAction::AcceptDraw(colour),
+            GameAction::DeclineDraw => PlayerAction::DeclineDraw(colour),
+            GameAction::ClaimDraw => PlayerAction::ClaimDraw,
+        };
+        let result = match action {
+            // both sides are played over the network when watching a game
+            _ if players.white == Player::Remote && players.black == Player::Remote => {
+                Err("spectators can only watch".to_string())
+            }
+            // the other player's actions arrive over the network instead
+            _ if players.of(colour) == Player::Remote => {
+                Err(format!("{colour} is played by the other player"))
+            }
+            GameAction::ClaimDraw if players.of(turn.0) != Player::Human => {
+                Err("only the player to move can claim a draw".to_string())
+            }
+            _ => game.0.act(player_action),
+        };
+
+        match result {
+            Ok(()) => {
+                actions_taken.send(GameActionTaken(player_action));
+                show_game_action(&game.0, &mut turn, &mut game_status, &mut next_state);
+            }
+            Err(err) => println!("Unable to {}: {err}", action_text(action)),
+        }
+    }
+}
+
+/// Shows the end of the game, or the offer of a draw to answer, after a player has resigned,
+/// offered or answered a draw or claimed one
+pub fn show_game_action(
+    game: &Game,
+    turn: &mut PlayerTurn,
+    game_status: &mut ResMut<GameStatus>,
+    next_state: &mut NextState<AppState>,
+) {
+    let status = game.status();
+    if status.is_over() {
+        // claims keep the turn with the player who claimed, as for a threefold repetition, while
+        // otherwise the turn passes to the player who did not lose
+        if let chess::Status::Resigned(colour) = status {
+            turn.0 = colour.opponent();
+        }
+        **game_status = status.into();
+        next_state.set(AppState::GameOver);
+    } else {
+        // the status text shows whether there is an offer of a draw to answer
+        game_status.set_changed();
+    }
+}
+
+fn action_text(action: GameAction) -> &'static str {
+    match action {
+        GameAction::Resign => "resign",
+        GameAction::OfferDraw => "offer a draw",
+        GameAction::AcceptDraw => "accept a draw",
+        GameAction::DeclineDraw => "decline a draw",
+        GameAction::ClaimDraw => "claim a draw",
+    }
+}
+
 /// Consumes events from Bevy_Mod_Picking and adds the `Selected` marker component when an element
 /// is selected, and removes it when it is deselected
 pub fn select_square(mut commands: Commands, mut events: EventReader<PickingEvent>) {
@@ -73,14 +288,48 @@ pub fn select_square(mut commands: Commands, mut events: EventReader<PickingEven
     }
 }
 
+/// Run condition for the systems that let a player pick the pieces to move
+pub fn human_to_move(turn: Res<PlayerTurn>, players: Res<Players>) -> bool {
+    players.of(turn.0) == Player::Human
+}
+
 /// If a selected square contains a piece then give that piece the `Selected` marker trait also
+///
+/// A square the selected piece can move to is left to `move_piece`, as in Chess960 the King
+/// castles by moving onto its own rook, which would otherwise be selected in its place. Likewise
+/// for a square the piece picked from the pocket can be dropped on, while picking any other square
+/// puts the piece back
+#[allow(clippy::type_complexity)]
 pub fn select_piece(
     mut commands: Commands,
     turn: Res<PlayerTurn>,
+    game: Res<ChessGame>,
     squares: Query<(&Square, &Selected)>,
     pieces: Query<(Entity, &Piece, Option<&Selected>), Without<Taken>>,
+    pocket: Query<(Entity, &Piece), (With<Taken>, With<Selected>)>,
 ) {
+    let position = game.0.position();
     for (square, _) in squares.iter() {
+        let is_move = pieces.iter().any(|(_, piece, selected)| {
+            selected.is_some()
+                && position
+                    .legal_moves_from(piece.pos)
+                    .iter()
+                    .any(|chess_move| chess_move.to == *square)
+        });
+        let is_drop = pocket.iter().any(|(_, piece)| {
+            moves_of(position, piece, true)
+                .iter()
+                .any(|chess_move| chess_move.to == *square)
+        });
+        if is_move || is_drop {
+            continue;
+        }
+
+        for (entity, _) in pocket.iter() {
+            commands.entity(entity).remove::<Selected>();
+        }
+
         for (entity, piece, selected) in pieces.iter() {
             if piece.pos.eq(square) && piece.colour == turn.0 && selected.is_none() {
                 commands.entity(entity).insert(Selected);
@@ -91,14 +340,15 @@ pub fn select_piece(
     }
 }
 
+/// Clears the selection of every square, pocket and piece
 pub fn reset_selected(
     mut commands: Commands,
     mut event_reader: EventReader<ResetSelectedEvent>,
-    mut selected_squares: Query<(Entity, &Square, &Selected, &mut Selection)>,
+    mut selected_squares: Query<(Entity, &mut Selection), With<Selected>>,
     selected_pieces: Query<(Entity, &Piece, &Selected)>,
 ) {
     for _ in event_reader.iter() {
-        for (entity, _, _, mut selection) in selected_squares.iter_mut() {
+        for (entity, mut selection) in selected_squares.iter_mut() {
             selection.set_selected(false);
             commands.entity(entity).remove::<Selected>();
         }
@@ -109,23 +359,12 @@ pub fn reset_selected(
     }
 }
 
-pub fn select_promotion(
-    mut event_writer: EventWriter<SelectPromotionOutcome>,
-    pieces: Query<(Entity, &Piece, &Move), Without<Taken>>,
-) {
-    for (entity, piece, movement) in pieces.iter() {
-        if piece.piece_type == PieceType::Pawn
-            && (movement.square.rank == board::RANK_1 || movement.square.rank == board::RANK_8)
-        {
-            event_writer.send(SelectPromotionOutcome { entity });
-        }
-    }
-}
-
+/// Completes a move onto the last rank once the piece to promote to has been chosen
 pub fn promote_piece(
     mut commands: Commands,
-    mut move_history: ResMut<MoveHistory>,
+    mut pending_promotion: ResMut<PendingPromotion>,
     mut event_reader: EventReader<PromotionOutcome>,
+    mut move_made_event: EventWriter<MoveMadeEvent>,
 ) {
     for event in event_reader.iter() {
         let promote = Promote {
@@ -133,219 +372,76 @@ pub fn promote_piece(
         };
 
         commands.entity(event.entity).insert(promote);
-        move_history
-            .0
-            .last_mut()
-            .unwrap()
-            .push_str(&format!("={}", event.piece_type.notation_letter()));
+
+        if let Some(mut move_made) = pending_promotion.0.take() {
+            move_made.promotion = Some(event.piece_type);
+            move_made_event.send(move_made);
+        }
     }
 }
 
-pub fn update_move_history(
-    mut move_number: Local<u32>,
-    move_stack: Res<MoveStack>,
-    mut move_history: ResMut<MoveHistory>,
-    game_status: Res<GameStatus>,
-    pieces: Query<&Piece, Without<Taken>>,
-) {
-    if move_stack.stack.is_empty() || !game_status.is_changed() {
+/// Writes out the moves of the game in SAN whenever the game changes, with an entry for each
+/// move number
+///
+/// The history is written from the start each time, so it stays in step as moves are taken back
+/// or a saved game is loaded
+pub fn update_move_history(game: Res<ChessGame>, mut move_history: ResMut<MoveHistory>) {
+    if !game.is_changed() {
         return;
     }
 
-    let pieces_vec: Vec<_> = pieces.iter().copied().collect();
-    let (last_move, _) = move_stack.stack.last().unwrap();
-    let (moving_piece, destination) = (last_move.piece, last_move.destination);
-
-    if moving_piece.colour == PieceColour::White {
-        *move_number += 1;
-        let move_annotation = generate_move_annotation(
-            &format!("{}. ", *move_number),
-            last_move,
-            &moving_piece,
-            &pieces_vec,
-            &destination,
-            game_status.as_ref(),
-        );
-        move_history.0.push(move_annotation);
-    } else {
-        let current = move_history.0.last_mut().unwrap();
-        *current = generate_move_annotation(
-            current,
-            last_move,
-            &moving_piece,
-            &pieces_vec,
-            &destination,
-            game_status.as_ref(),
-        );
+    let mut position = game.0.starting_position().clone();
+    let mut history: Vec<String> = Vec::new();
+    for chess_move in game.0.moves() {
+        let move_number = position.fullmove_number();
+        let san = position.to_san(chess_move);
+
+        if position.side_to_move() == PieceColour::White {
+            history.push(format!("{move_number}. {san}"));
+        } else {
+            // a game started from a position with black to move has no entry for white's move
+            if history.is_empty() {
+                history.push(format!("{move_number}..."));
+            }
+            let current = history.last_mut().unwrap();
+            current.push(' ');
+            current.push_str(&san);
+        }
+        position.make_move(*chess_move);
+    }
+
+    if move_history.0 != history {
+        move_history.0 = history;
     }
 }
 
 pub fn update_status(
-    mut last_action: Local<i32>,
     move_stack: Res<MoveStack>,
+    game: Res<ChessGame>,
+    state: Res<State<AppState>>,
+    mut next_state: ResMut<NextState<AppState>>,
     mut turn: ResMut<PlayerTurn>,
     mut game_status: ResMut<GameStatus>,
-    pieces: Query<(&Piece, Option<&Move>), Without<Taken>>,
 ) {
-    if move_stack.stack.is_empty() || !move_stack.is_changed() {
+    if !move_stack.is_changed() {
         return;
     }
 
-    let pieces_vec: Vec<_> = pieces
-        .iter()
-        .map(|(piece, move_opt)| match move_opt {
-            None => *piece,
-            Some(movement) => {
-                let mut piece = *piece;
-                piece.pos = movement.square;
-                piece.has_moved = true;
-                piece
-            }
-        })
-        .collect();
-
-    let (last_move, _) = move_stack.stack.last().unwrap();
-
-    if last_move.piece.piece_type == PieceType::Pawn || last_move.is_take() {
-        *last_action = 0
-    } else {
-        *last_action += 1
-    }
-
-    let has_moves = player_has_moves(turn.0.opponent(), &pieces_vec, &pieces_vec, last_move);
-    let check = is_in_check(turn.0.opponent(), &pieces_vec, &pieces_vec, last_move);
-
-    *game_status = if check && !has_moves {
-        GameStatus::Checkmate
-    } else if *last_action == 50 {
-        GameStatus::Draw(DrawReason::FiftyMoveRule)
-    } else if check & has_moves {
-        turn.change();
-        GameStatus::Check
-    } else if !check && !has_moves {
-        GameStatus::Draw(DrawReason::Stalemate)
+    let status = game.0.status();
+    let side_to_move = game.0.position().side_to_move();
+    // the turn only passes to the next player if the game continues
+    turn.0 = if status.is_over() {
+        side_to_move.opponent()
     } else {
-        // TODO other draw conditions
-        turn.change();
-        GameStatus::OnGoing
-    };
-}
-
-fn generate_move_annotation(
-    prefix: &str,
-    event: &MoveMadeEvent,
-    // last_move: Option<(Piece, Square, Square)>,
-    moving_piece: &Piece,
-    pieces: &[Piece],
-    destination: &Square,
-    status: &GameStatus,
-) -> String {
-    let disambiguation = disambiguate_piece(event, moving_piece, pieces, destination);
-
-    let status = match status {
-        GameStatus::Check => "!",
-        GameStatus::Checkmate => "#",
-        _ => "",
+        side_to_move
     };
+    *game_status = status.into();
 
-    match event.move_type {
-        MoveType::Take(_) | MoveType::TakeEnPassant(_) => {
-            let piece_letter = if moving_piece.piece_type == PieceType::Pawn {
-                moving_piece.pos.file_annotation()
-            } else {
-                moving_piece.piece_type.notation_letter()
-            };
-            format!(
-                "{prefix} {piece_letter}{disambiguation}x{destination}{status}",
-                // piece.piece_type.notation_letter()
-            )
-        }
-        MoveType::Castle => {
-            if destination.file == board::G_FILE {
-                format!("{prefix} 0-0{status}")
-            } else {
-                format!("{prefix} 0-0-0{status}")
-            }
-        }
-        MoveType::Move => {
-            format!(
-                "{prefix} {}{disambiguation}{destination}{status}",
-                moving_piece.piece_type.notation_letter()
-            )
-        }
+    // reviewing the game steps through the moves without playing them
+    if matches!(
+        state.0,
+        AppState::InGame | AppState::PromotionPending | AppState::GameOver
+    ) {
+        next_state.set(AppState::playing(status.is_over()));
     }
 }
-
-fn disambiguate_piece(
-    last_move: &MoveMadeEvent,
-    moving_piece: &Piece,
-    pieces: &[Piece],
-    destination: &Square,
-) -> String {
-    let ambiguous_pieces: Vec<_> = pieces
-        .iter()
-        .filter(|piece| {
-            piece.colour == moving_piece.colour
-                && piece.piece_type == moving_piece.piece_type
-                && piece
-                    .legal_moves(pieces, Some(last_move))
-                    .contains(destination)
-                && piece.pos != moving_piece.pos
-        })
-        .collect();
-
-    let file_ambiguous = ambiguous_pieces
-        .iter()
-        .any(|piece| piece.pos.file == moving_piece.pos.file);
-    let rank_ambiguous = ambiguous_pieces
-        .iter()
-        .any(|piece| piece.pos.rank == moving_piece.pos.rank);
-
-    if file_ambiguous {
-        if rank_ambiguous {
-            moving_piece.pos.to_string()
-        } else {
-            moving_piece.pos.rank_annotation()
-        }
-    } else if !ambiguous_pieces.is_empty() {
-        moving_piece.pos.file_annotation()
-    } else {
-        String::new()
-    }
-}
-
-fn is_in_check(
-    player_colour: PieceColour,
-    pieces: &[Piece],
-    pieces_vec: &[Piece],
-    last_move: &MoveMadeEvent,
-) -> bool {
-    let own_king = pieces_vec
-        .iter()
-        .find(|piece| piece.colour == player_colour && piece.piece_type == PieceType::King)
-        .unwrap();
-
-    // FIXME a pinned piece still gives check even though it's not considered a legal move
-    pieces
-        .iter()
-        .filter(|piece| piece.colour == player_colour.opponent())
-        .any(|piece| {
-            piece
-                .legal_moves(pieces_vec, Some(last_move))
-                .contains(&own_king.pos)
-        })
-}
-
-fn player_has_moves(
-    player_colour: PieceColour,
-    pieces: &[Piece],
-    pieces_vec: &[Piece],
-    last_move: &MoveMadeEvent,
-) -> bool {
-    pieces
-        .iter()
-        .filter(|piece| piece.colour == player_colour)
-        .flat_map(|piece| piece.legal_moves(pieces_vec, Some(last_move)))
-        .count()
-        > 0
-}
@@ -0,0 +1,245 @@
+use bevy::prelude::*;
+
+use crate::board::components::{Move, Taken};
+use crate::board::resources::{ChessGame, Graveyard, ImportedGame, MoveStack, Pockets};
+use crate::board::systems::movement::find_piece_entity;
+use crate::board::{MoveMadeEvent, Promote, ResetSelectedEvent};
+use crate::chess_clock::ChessClock;
+use crate::pieces::{Piece, PieceType};
+use crate::states::AppState;
+
+/// Takes back the last move when U is pressed, returning any taken piece from the graveyard, or
+/// in variants with pockets from the pocket of the player who took it
+///
+/// In variants where captures explode the pieces blown up by the move come back from the graveyard
+/// too, including the piece that made it. The clock is put back to how it was before the move
+#[allow(clippy::too_many_arguments)]
+pub fn undo_move(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    mut stack: ResMut<MoveStack>,
+    mut game: ResMut<ChessGame>,
+    mut clock: ResMut<ChessClock>,
+    mut graveyard: ResMut<Graveyard>,
+    mut pockets: ResMut<Pockets>,
+    pieces: Query<(Entity, &Piece), Without<Taken>>,
+    taken_pieces: Query<&Piece, With<Taken>>,
+    mut reset_selected_event: EventWriter<ResetSelectedEvent>,
+) {
+    if !keys.just_pressed(KeyCode::U) {
+        return;
+    }
+    let Some((move_made, _, exploded, before)) = stack.stack.pop() else {
+        return;
+    };
+
+    game.0.unmake_move();
+    let after = clock.0.clone();
+    if let (Some(clock), Some(before)) = (&mut clock.0, before) {
+        *clock = before;
+    }
+    let chess_move = move_made.chess_move();
+
+    if let Some(entity) = move_made.dropped_piece() {
+        let piece = move_made.piece;
+        commands.entity(entity).insert(Taken {
+            grave: pockets.push(piece.colour, piece.piece_type, entity),
+        });
+        stack.redo.push((move_made, after));
+        reset_selected_event.send(ResetSelectedEvent);
+        return;
+    }
+
+    for &entity in &exploded {
+        let piece = taken_pieces.get(entity).expect("exploded pieces are taken");
+        graveyard.previous(piece.colour.opponent());
+        commands.entity(entity).remove::<Taken>();
+    }
+
+    // a piece that blew itself up is the first of the pieces that exploded
+    let piece_entity = exploded
+        .first()
+        .copied()
+        .or_else(|| find_piece_entity(&pieces, game.0.position().destination(&chess_move)))
+        .expect("the moved piece is on its destination");
+    commands.entity(piece_entity).insert(Move {
+        square: move_made.origin,
+    });
+    if move_made.promotion.is_some() {
+        commands.entity(piece_entity).insert(Promote {
+            to: PieceType::Pawn,
+        });
+    }
+
+    if let Some((rook_origin, rook_destination)) = game.0.position().castling_rook(&chess_move) {
+        let rook_entity = find_piece_entity(&pieces, rook_destination).unwrap();
+        commands.entity(rook_entity).insert(Move {
+            square: rook_origin,
+        });
+    }
+
+    if let Some(entity) = move_made.taken_piece() {
+        commands.entity(entity).remove::<Taken>();
+        if game.0.position().variant().has_pockets() {
+            // the piece goes back to the side it was taken from, as it was before
+            let square = game.0.position().captured_square(&chess_move).unwrap();
+            let piece = *game.0.position().piece_at(square).unwrap();
+            pockets.remove(entity);
+            commands.entity(entity).insert((
+                piece,
+                Promote {
+                    to: piece.piece_type,
+                },
+            ));
+        } else {
+            graveyard.previous(move_made.piece.colour);
+        }
+    }
+
+    stack.redo.push((move_made, after));
+    reset_selected_event.send(ResetSelectedEvent);
+}
+
+/// Makes the last move that was taken back again when R is pressed
+///
+/// The pieces are moved here, then the move is recorded by `push_move` like any other move
+pub fn redo_move(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    stack: Res<MoveStack>,
+    game: Res<ChessGame>,
+    pieces: Query<(Entity, &Piece), Without<Taken>>,
+    mut move_made_event: EventWriter<MoveMadeEvent>,
+    mut reset_selected_event: EventWriter<ResetSelectedEvent>,
+) {
+    if !keys.just_pressed(KeyCode::R) {
+        return;
+    }
+    let Some((move_made, _)) = stack.redo.last() else {
+        return;
+    };
+    // a dropped piece is taken from the pocket when the move is recorded
+    if move_made.dropped_piece().is_some() {
+        move_made_event.send(*move_made);
+        reset_selected_event.send(ResetSelectedEvent);
+        return;
+    }
+
+    let chess_move = move_made.chess_move();
+    let piece_entity =
+        find_piece_entity(&pieces, move_made.origin).expect("the piece to move is on its origin");
+    commands.entity(piece_entity).insert(Move {
+        square: game.0.position().destination(&chess_move),
+    });
+    if let Some(piece_type) = move_made.promotion {
+        commands
+            .entity(piece_entity)
+            .insert(Promote { to: piece_type });
+    }
+
+    if let Some((rook_origin, rook_destination)) = game.0.position().castling_rook(&chess_move) {
+        let rook_entity = find_piece_entity(&pieces, rook_origin).unwrap();
+        commands.entity(rook_entity).insert(Move {
+            square: rook_destination,
+        });
+    }
+
+    move_made_event.send(*move_made);
+    reset_selected_event.send(ResetSelectedEvent);
+}
+
+/// Lines up the moves of a game loaded from PGN to be made again, so the game can be stepped
+/// through from the start with R and U
+///
+/// The pieces taken and dropped by each move are found by following every piece through the
+/// game, as none of them have moved yet
+pub fn replay_imported_game(
+    mut imported_game: ResMut<ImportedGame>,
+    mut stack: ResMut<MoveStack>,
+    mut next_state: ResMut<NextState<AppState>>,
+    game: Res<ChessGame>,
+    pockets: Res<Pockets>,
+    pieces: Query<(Entity, &Piece), Without<Taken>>,
+) {
+    // the pieces are spawned on startup, so are only on the board from the first update
+    if imported_game.0.is_none() || pieces.is_empty() {
+        return;
+    }
+    let moves = imported_game.0.take().unwrap();
+
+    let mut entities = [None; 64];
+    for (entity, piece) in pieces.iter() {
+        entities[piece.pos.index()] = Some(entity);
+    }
+
+    let mut position = game.0.position().clone();
+    let mut pockets = pockets.clone();
+    let mut moves_made = Vec::new();
+    for chess_move in moves {
+        if let Some(piece_type) = chess_move.drop {
+            let colour = position.side_to_move();
+            let entity = pockets
+                .last(colour, piece_type)
+                .expect("imported moves are legal");
+            pockets.remove(entity);
+            entities[chess_move.to.index()] = Some(entity);
+            let piece = Piece {
+                colour,
+                piece_type,
+                pos: chess_move.to,
+            };
+            position.make_move(chess_move);
+            moves_made.push(MoveMadeEvent::drop(piece, entity));
+            continue;
+        }
+
+        let piece = *position
+            .piece_at(chess_move.from)
+            .expect("imported moves are legal");
+        let piece_entity = entities[chess_move.from.index()].take();
+
+        let move_made =
+            if let Some((rook_origin, rook_destination)) = position.castling_rook(&chess_move) {
+                entities[rook_destination.index()] = entities[rook_origin.index()].take();
+                MoveMadeEvent::castling(piece, chess_move.from, chess_move.to)
+            } else {
+                let taken_piece = position
+                    .captured_square(&chess_move)
+                    .and_then(|square| entities[square.index()].take());
+                if let Some(entity) = taken_piece.filter(|_| position.variant().has_pockets()) {
+                    let square = position.captured_square(&chess_move).unwrap();
+                    let taken = *position.piece_at(square).unwrap();
+                    let held = Pockets::held_as(&position, taken, piece.colour);
+                    pockets.push(held.colour, held.piece_type, entity);
+                }
+                MoveMadeEvent {
+                    promotion: chess_move.promotion,
+                    ..MoveMadeEvent::not_castling(
+                        piece,
+                        chess_move.from,
+                        chess_move.to,
+                        taken_piece,
+                        position.is_en_passant(&chess_move),
+                    )
+                }
+            };
+
+        entities[position.destination(&chess_move).index()] = piece_entity;
+        // a capture that explodes blows up the capturing piece and the pieces around it
+        if position.variant().explodes() && position.captured_square(&chess_move).is_some() {
+            let exploded = position.exploded_squares(&chess_move);
+            for square in exploded.squares().chain([chess_move.to]) {
+                entities[square.index()] = None;
+            }
+        }
+        position.make_move(chess_move);
+        moves_made.push(move_made);
+    }
+
+    stack.redo = moves_made
+        .into_iter()
+        .rev()
+        .map(|move_made| (move_made, None))
+        .collect();
+    next_state.set(AppState::Review);
+}
@@ -1,38 +1,232 @@
-use bevy::app::AppExit;
 use bevy::asset::Handle;
 use bevy::pbr::StandardMaterial;
 use bevy::prelude::*;
 use bevy::utils::HashSet;
 use bevy_mod_picking::{Hover, Selection};
+use chess::{ChessMove, Position};
 
-use crate::board;
-use crate::board::components::{Move, Selected};
-use crate::board::resources::{Graveyard, MoveStack, SquareMaterials};
-use crate::board::{MoveMadeEvent, ResetSelectedEvent, Square, Taken};
-use crate::pieces::{Piece, PieceType};
+use crate::board::components::{Exploded, Move, Selected};
+use crate::board::resources::{
+    ChessGame, Graveyard, MoveStack, PendingPromotion, Pockets, SquareMaterials,
+};
+use crate::board::{
+    MoveMadeEvent, Promote, ResetSelectedEvent, SelectPromotionOutcome, Square, Taken,
+};
+use crate::chess_clock::ChessClock;
+use crate::pieces::{Piece, PieceColour, PieceType};
+use crate::states::AppState;
 
+/// How long a piece blown up by a capture takes to disappear from the board, in seconds
+const EXPLOSION_TIME: f32 = 0.5;
+
+/// Records each move made and applies it to the game, sending any taken piece to the graveyard
+///
+/// In variants with pockets a taken piece changes sides and goes to the pocket of the player who
+/// took it instead, as a pawn if it had been promoted, and a piece dropped from the pocket is put
+/// on its square. In variants where captures explode the capturing piece and the pieces around it
+/// are blown up into the graveyard too
+///
+/// The clock of the player who moved is pressed, unless the move is one that was taken back being
+/// made again, which puts the clock back to how it was when it was taken back
+#[allow(clippy::too_many_arguments)]
 pub fn push_move(
+    mut commands: Commands,
     mut stack: ResMut<MoveStack>,
+    mut game: ResMut<ChessGame>,
+    mut clock: ResMut<ChessClock>,
+    mut graveyard: ResMut<Graveyard>,
+    mut pockets: ResMut<Pockets>,
     mut move_events: EventReader<MoveMadeEvent>,
-    query: Query<&Piece, Without<Taken>>,
+    query: Query<(Entity, &Piece), Without<Taken>>,
 ) {
     for move_event in move_events.iter() {
-        let pieces: Vec<_> = query.iter().cloned().collect();
-        stack.stack.push((*move_event, pieces));
+        let chess_move = move_event.chess_move();
+        let pieces: Vec<_> = query.iter().map(|(_, piece)| *piece).collect();
+        let exploded = exploded_pieces(game.0.position(), move_event, &query);
+        let has_pockets = game.0.position().variant().has_pockets();
+        let taken = move_event.taken_piece().map(|entity| {
+            let (_, piece) = query.get(entity).expect("the taken piece is on the board");
+            let held = Pockets::held_as(game.0.position(), *piece, move_event.piece.colour);
+            (entity, held)
+        });
+        // a move sent in the same frame as the game ended, by resigning or agreeing a draw, is
+        // too late to be made
+        if let Err(err) = game.0.make_move(chess_move) {
+            println!("Unable to make {chess_move}: {err}");
+            put_back_move(&mut commands, game.0.position(), move_event, &query);
+            continue;
+        }
+
+        // making the move that was taken back keeps the rest of the moves available to redo
+        let before = clock.0.clone();
+        if stack
+            .redo
+            .last()
+            .map(|(move_made, _)| move_made.chess_move())
+            == Some(chess_move)
+        {
+            let (_, after) = stack.redo.pop().unwrap();
+            if let (Some(clock), Some(after)) = (&mut clock.0, after) {
+                *clock = after;
+            }
+        } else {
+            stack.redo.clear();
+            if let Some(clock) = &mut clock.0 {
+                clock.press(move_event.piece.colour);
+            }
+        }
+        stack.stack.push((
+            *move_event,
+            pieces,
+            exploded.iter().map(|(entity, _)| *entity).collect(),
+            before,
+        ));
+
+        if let Some((entity, held)) = taken {
+            let grave = if has_pockets {
+                commands.entity(entity).insert((
+                    held,
+                    Promote {
+                        to: held.piece_type,
+                    },
+                ));
+                pockets.push(held.colour, held.piece_type, entity)
+            } else {
+                graveyard.next(move_event.piece.colour)
+            };
+            commands.entity(entity).insert(Taken { grave });
+        }
+
+        for (entity, colour) in exploded {
+            commands.entity(entity).insert((
+                Taken {
+                    grave: graveyard.next(colour.opponent()),
+                },
+                Exploded {
+                    square: move_event.destination,
+                    time: 0.0,
+                },
+            ));
+        }
+
+        if let Some(entity) = move_event.dropped_piece() {
+            pockets.remove(entity);
+            commands
+                .entity(entity)
+                .remove::<Taken>()
+                .insert(move_event.piece);
+        }
     }
 }
 
-pub fn remove_taken_pieces(
-    mut exit_event: EventWriter<AppExit>,
+/// Moves the pieces of a move that could not be made back to where they were in `position`
+///
+/// The pieces may not have been moved to their destinations or promoted yet, so are found either
+/// way. A piece dropped from the pocket is only taken out of it once the move is made, so stays
+/// where it is
+pub fn put_back_move(
+    commands: &mut Commands,
+    position: &Position,
+    move_made: &MoveMadeEvent,
+    pieces: &Query<(Entity, &Piece), Without<Taken>>,
+) {
+    if move_made.dropped_piece().is_some() {
+        return;
+    }
+
+    let chess_move = move_made.chess_move();
+    let colour = move_made.piece.colour;
+    let mut moved = vec![(
+        move_made.origin,
+        position.destination(&chess_move),
+        move_made.piece.piece_type,
+    )];
+    if let Some((rook_origin, rook_destination)) = position.castling_rook(&chess_move) {
+        moved.push((rook_origin, rook_destination, PieceType::Rook));
+    }
+    for (origin, destination, piece_type) in moved {
+        let found = pieces.iter().find(|(_, piece)| {
+            piece.colour == colour
+                && (piece.piece_type == piece_type || Some(piece.piece_type) == move_made.promotion)
+                && (piece.pos == destination || piece.pos == origin)
+        });
+        if let Some((entity, _)) = found {
+            commands.entity(entity).insert(Move { square: origin });
+            if move_made.promotion.is_some() {
+                commands.entity(entity).insert(Promote {
+                    to: PieceType::Pawn,
+                });
+            }
+        }
+    }
+}
+
+/// The entity and colour of each piece blown up by `move_made`, with the capturing piece first
+///
+/// The capturing piece may not have been moved to its destination yet, so is found on either
+fn exploded_pieces(
+    position: &Position,
+    move_made: &MoveMadeEvent,
+    pieces: &Query<(Entity, &Piece), Without<Taken>>,
+) -> Vec<(Entity, PieceColour)> {
+    if !position.variant().explodes() || move_made.taken_piece().is_none() {
+        return Vec::new();
+    }
+
+    let squares = position.exploded_squares(&move_made.chess_move());
+    let capturer = pieces.iter().find(|(_, piece)| {
+        piece.colour == move_made.piece.colour
+            && (piece.pos == move_made.origin || piece.pos == move_made.destination)
+    });
+    capturer
+        .into_iter()
+        .chain(
+            pieces
+                .iter()
+                .filter(|(_, piece)| squares.contains(piece.pos)),
+        )
+        .map(|(entity, piece)| (entity, piece.colour))
+        .collect()
+}
+
+/// Throws each piece blown up by a capture into the air from the square of the capture, shrinking
+/// it away before it appears in its grave
+///
+/// A piece put back on the board before it has finished is returned to its full size
+pub fn explode_pieces(
+    mut commands: Commands,
     time: Res<Time>,
-    mut query: Query<(&Piece, &Taken, &mut Transform)>,
+    mut query: Query<(Entity, &mut Exploded, &mut Transform, Option<&Taken>)>,
 ) {
-    for (piece, taken, mut transform) in query.iter_mut() {
-        if piece.piece_type == PieceType::King {
-            println!("{} won! Thanks for playing!", piece.colour);
-            exit_event.send(AppExit);
+    for (entity, mut exploded, mut transform, taken) in query.iter_mut() {
+        exploded.time += time.delta_seconds();
+        let progress = (exploded.time / EXPLOSION_TIME).min(1.0);
+
+        match taken {
+            Some(_) if progress < 1.0 => {
+                let square = exploded.square;
+                transform.translation =
+                    Vec3::new(square.rank as f32, progress * 2.0, square.file as f32);
+                transform.scale = Vec3::splat(1.0 - progress);
+            }
+            Some(taken) => {
+                transform.translation = taken.grave;
+                transform.scale = Vec3::ONE;
+                commands.entity(entity).remove::<Exploded>();
+            }
+            None => {
+                transform.scale = Vec3::ONE;
+                commands.entity(entity).remove::<Exploded>();
+            }
         }
+    }
+}
 
+pub fn remove_taken_pieces(
+    time: Res<Time>,
+    mut query: Query<(&Taken, &mut Transform), Without<Exploded>>,
+) {
+    for (taken, mut transform) in query.iter_mut() {
         let direction = taken.grave - transform.translation;
 
         if direction.length() > 0.1 {
@@ -43,18 +237,15 @@ pub fn remove_taken_pieces(
 
 pub fn colour_moves(
     materials: Res<SquareMaterials>,
-    move_stack: Res<MoveStack>,
-    selected_piece: Query<(&Piece, &Selected)>,
-    pieces: Query<&Piece, Without<Taken>>,
+    game: Res<ChessGame>,
+    selected_piece: Query<(&Piece, Option<&Taken>), With<Selected>>,
     mut squares: Query<(&Square, &mut Handle<StandardMaterial>, &Selection, &Hover)>,
 ) {
-    let moves = if let Ok((piece, _)) = selected_piece.get_single() {
-        // let piece = pieces.get(piece_entity).expect("unable to retrieve entity");
-        let pieces_vec: Vec<_> = pieces.iter().copied().collect();
-
-        let last_move = move_stack.stack.last().map(|(move_event, _)| move_event);
-
-        piece.legal_moves(&pieces_vec, last_move)
+    let moves: HashSet<_> = if let Ok((piece, in_pocket)) = selected_piece.get_single() {
+        moves_of(game.0.position(), piece, in_pocket.is_some())
+            .into_iter()
+            .flat_map(|chess_move| [chess_move.to, game.0.position().destination(&chess_move)])
+            .collect()
     } else {
         HashSet::new()
     };
@@ -82,145 +273,161 @@ pub fn make_move(
 ) {
     for (entity, mut piece, movement) in pieces.iter_mut() {
         piece.pos = movement.square;
-        piece.has_moved = true;
 
         commands.entity(entity).remove::<Move>();
     }
 }
 
+/// The legal moves of `piece`, which are its drops if it is in the pocket
+pub(super) fn moves_of(position: &Position, piece: &Piece, in_pocket: bool) -> Vec<ChessMove> {
+    if in_pocket {
+        position
+            .legal_moves()
+            .into_iter()
+            .filter(|chess_move| chess_move.drop == Some(piece.piece_type))
+            .collect()
+    } else {
+        position.legal_moves_from(piece.pos)
+    }
+}
+
+/// Moves the selected piece to the selected square, or drops it there if it was picked from the
+/// pocket
 #[allow(clippy::too_many_arguments)]
 pub fn move_piece(
     mut commands: Commands,
-    mut graveyard: ResMut<Graveyard>,
-    move_stack: Res<MoveStack>,
+    mut pending_promotion: ResMut<PendingPromotion>,
+    mut next_state: ResMut<NextState<AppState>>,
+    game: Res<ChessGame>,
+    pockets: Res<Pockets>,
     selected_square: Query<(&Square, &Selected)>,
-    selected_piece: Query<(Entity, &Piece, &Selected)>,
+    selected_piece: Query<(Entity, &Piece, Option<&Taken>), With<Selected>>,
     pieces: Query<(Entity, &Piece), Without<Taken>>,
     mut reset_selected_event: EventWriter<ResetSelectedEvent>,
     mut move_made_event: EventWriter<MoveMadeEvent>,
+    mut select_promotion_event: EventWriter<SelectPromotionOutcome>,
 ) {
-    let Ok((destination, _)) = selected_square.get_single() else { return; };
-    let Ok((piece_entity, moving_piece, _)) = selected_piece.get_single() else { return };
-    if moving_piece.pos.eq(destination) {
+    let Ok((destination, _)) = selected_square.get_single() else {
+        return;
+    };
+    let Ok((piece_entity, moving_piece, in_pocket)) = selected_piece.get_single() else {
+        return;
+    };
+    if in_pocket.is_none() && moving_piece.pos.eq(destination) {
         return;
     }
 
-    let pieces_vec: Vec<_> = pieces.iter().map(|(_, piece)| *piece).collect();
+    let position = game.0.position();
 
-    let last_move = move_stack.stack.last().map(|(event, _)| event);
-
-    if moving_piece
-        .legal_moves(&pieces_vec, last_move)
-        .contains(destination)
+    // promotions are listed once per piece type, the choice is made after the pawn has moved.
+    // Castling in Chess960 is written as the King taking its rook, so picking the square the King
+    // finishes on castles too, unless the King can move there anyway
+    let moves = moves_of(position, moving_piece, in_pocket.is_some());
+    if let Some(chess_move) = moves
+        .iter()
+        .find(|chess_move| chess_move.to == *destination)
+        .or_else(|| {
+            moves
+                .iter()
+                .find(|chess_move| position.destination(chess_move) == *destination)
+        })
+        .copied()
     {
-        let (taken_piece, en_passant) =
-            try_get_taken_piece(&pieces, destination, piece_entity, last_move);
+        let move_made = start_move(&mut commands, position, &pieces, &pockets, &chess_move);
 
-        if let Some(entity) = taken_piece {
-            commands.entity(entity).insert(Taken {
-                grave: graveyard.next(moving_piece.colour),
+        if chess_move.promotion.is_some() {
+            pending_promotion.0 = Some(move_made);
+            next_state.set(AppState::PromotionPending);
+            select_promotion_event.send(SelectPromotionOutcome {
+                entity: piece_entity,
             });
-        }
-
-        commands.entity(piece_entity).insert(Move {
-            square: *destination,
-        });
-
-        // if castling the rook needs to move too
-        if moving_piece.piece_type == PieceType::King
-            && (moving_piece.pos.file - destination.file).abs() == 2
-        {
-            move_castling_rook(&mut commands, &pieces, destination, moving_piece);
-            move_made_event.send(MoveMadeEvent::castling(
-                *moving_piece,
-                moving_piece.pos,
-                *destination,
-            ));
         } else {
-            move_made_event.send(MoveMadeEvent::not_castling(
-                *moving_piece,
-                moving_piece.pos,
-                *destination,
-                taken_piece,
-                en_passant,
-            ));
+            move_made_event.send(move_made);
         }
     }
 
     reset_selected_event.send(ResetSelectedEvent);
 }
 
-fn try_get_taken_piece(
+/// Makes `chess_move`, which must be legal, as if a player had picked it on the board, without
+/// asking for the piece to promote to as the move already says
+pub fn play_move(
+    commands: &mut Commands,
+    position: &Position,
     pieces: &Query<(Entity, &Piece), Without<Taken>>,
-    square: &Square,
-    piece_entity: Entity,
-    last_move: Option<&MoveMadeEvent>,
-) -> (Option<Entity>, bool) {
-    let (taken_piece, en_passant) = {
-        let taken_piece = pieces
-            .iter()
-            .find(|(_, taken_piece)| taken_piece.pos == *square)
-            .map(|(entity, _)| entity);
-
-        if taken_piece.is_none() {
-            let taken_piece = get_en_passant_piece(pieces, square, piece_entity, last_move);
-            (taken_piece, taken_piece.is_some())
-        } else {
-            (taken_piece, false)
-        }
-    };
-    (taken_piece, en_passant)
+    pockets: &Pockets,
+    chess_move: &ChessMove,
+    move_made_event: &mut EventWriter<MoveMadeEvent>,
+) {
+    let mut move_made = start_move(commands, position, pieces, pockets, chess_move);
+    if let Some(piece_type) = chess_move.promotion {
+        let piece_entity = find_piece_entity(pieces, chess_move.from).unwrap();
+        commands
+            .entity(piece_entity)
+            .insert(Promote { to: piece_type });
+        move_made.promotion = Some(piece_type);
+    }
+    move_made_event.send(move_made);
 }
 
-fn move_castling_rook(
+/// Moves the piece, and the rook when castling, returning the event that records the move once
+/// any promotion has been chosen
+///
+/// A piece dropped from the pocket is left where it is until the move is recorded
+fn start_move(
     commands: &mut Commands,
+    position: &Position,
     pieces: &Query<(Entity, &Piece), Without<Taken>>,
-    square: &Square,
-    moving_piece: &Piece,
-) {
-    let rook_dest_file = if square.file == board::G_FILE {
-        board::F_FILE
-    } else {
-        board::D_FILE
-    };
-    let rook_dest_square = Square {
-        rank: square.rank,
-        file: rook_dest_file,
-    };
+    pockets: &Pockets,
+    chess_move: &ChessMove,
+) -> MoveMadeEvent {
+    if let Some(piece_type) = chess_move.drop {
+        let colour = position.side_to_move();
+        let entity = pockets.last(colour, piece_type).expect("no piece to drop");
+        let piece = Piece {
+            colour,
+            piece_type,
+            pos: chess_move.to,
+        };
+        return MoveMadeEvent::drop(piece, entity);
+    }
 
-    let (rook_entity, _) = pieces
-        .iter()
-        .find(|(_, piece)| {
-            piece.piece_type == PieceType::Rook
-                && !piece.has_moved
-                && piece.colour == moving_piece.colour
-                && (piece.pos.file - square.file).abs() < 3
-        })
-        .unwrap();
+    let piece_entity = find_piece_entity(pieces, chess_move.from).expect("no piece to move");
+    let piece = *position
+        .piece_at(chess_move.from)
+        .expect("no piece to move");
+    let taken_piece = position
+        .captured_square(chess_move)
+        .and_then(|square| find_piece_entity(pieces, square));
 
-    commands.entity(rook_entity).insert(Move {
-        square: rook_dest_square,
+    commands.entity(piece_entity).insert(Move {
+        square: position.destination(chess_move),
     });
+
+    // if castling the rook needs to move too
+    if let Some((rook_origin, rook_destination)) = position.castling_rook(chess_move) {
+        let rook_entity = find_piece_entity(pieces, rook_origin).unwrap();
+        commands.entity(rook_entity).insert(Move {
+            square: rook_destination,
+        });
+        MoveMadeEvent::castling(piece, chess_move.from, chess_move.to)
+    } else {
+        MoveMadeEvent::not_castling(
+            piece,
+            chess_move.from,
+            chess_move.to,
+            taken_piece,
+            position.is_en_passant(chess_move),
+        )
+    }
 }
 
-fn get_en_passant_piece(
+pub(super) fn find_piece_entity(
     pieces: &Query<(Entity, &Piece), Without<Taken>>,
-    square: &Square,
-    piece_entity: Entity,
-    last_move: Option<&MoveMadeEvent>,
+    square: Square,
 ) -> Option<Entity> {
-    if pieces
-        .get(piece_entity)
-        .unwrap()
-        .1
-        .may_take_en_passant(square, last_move)
-    {
-        let last_move_event = last_move?;
-        pieces
-            .iter()
-            .find(|(_, piece)| piece.pos == last_move_event.destination)
-            .map(|(entity, _)| entity)
-    } else {
-        None
-    }
+    pieces
+        .iter()
+        .find(|(_, piece)| piece.pos == square)
+        .map(|(entity, _)| entity)
 }
@@ -0,0 +1,90 @@
+use bevy::prelude::*;
+use bevy_mod_picking::{Highlighting, PickableBundle};
+
+use crate::board::components::{PocketSlot, Selected};
+use crate::board::resources::{ChessGame, PlayerTurn, Pockets, SquareMaterials};
+use crate::pieces::{Piece, PieceColour};
+
+/// Puts a square beside the board for each type of piece a player can hold when the game is played
+/// with pockets, and takes them away again when it is not
+///
+/// The squares are where the graveyard would be, nearest the board in each row of the pockets
+pub fn update_pocket_slots(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    square_materials: Res<SquareMaterials>,
+    game: Res<ChessGame>,
+    slots: Query<Entity, With<PocketSlot>>,
+) {
+    if !game.is_changed() {
+        return;
+    }
+
+    let has_pockets = game.0.position().variant().has_pockets();
+    if !has_pockets {
+        for entity in slots.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    }
+    if !slots.is_empty() {
+        return;
+    }
+
+    let mesh = meshes.add(Mesh::from(shape::Plane {
+        size: 1.0,
+        subdivisions: 0,
+    }));
+    for colour in [PieceColour::White, PieceColour::Black] {
+        let initial_material = match colour {
+            PieceColour::White => square_materials.white_colour.clone(),
+            PieceColour::Black => square_materials.black_colour.clone(),
+        };
+        for piece_type in Pockets::PIECE_TYPES {
+            commands.spawn((
+                PbrBundle {
+                    mesh: mesh.clone(),
+                    material: initial_material.clone(),
+                    transform: Transform::from_translation(Pockets::translation(
+                        colour, piece_type, 0,
+                    )),
+                    ..Default::default()
+                },
+                PickableBundle::default(),
+                Highlighting {
+                    initial: initial_material.clone(),
+                    hovered: Some(square_materials.hover_colour.clone()),
+                    pressed: None,
+                    selected: Some(square_materials.selected_colour.clone()),
+                },
+                PocketSlot { colour, piece_type },
+            ));
+        }
+    }
+}
+
+/// Selects the piece that will be dropped when a player picks one of the rows of their pocket
+/// holding a piece, in place of any piece selected on the board
+pub fn select_pocket(
+    mut commands: Commands,
+    turn: Res<PlayerTurn>,
+    pockets: Res<Pockets>,
+    slots: Query<&PocketSlot, With<Selected>>,
+    selected_pieces: Query<Entity, (With<Piece>, With<Selected>)>,
+) {
+    for slot in slots.iter() {
+        let Some(entity) = pockets
+            .last(slot.colour, slot.piece_type)
+            .filter(|_| slot.colour == turn.0)
+        else {
+            continue;
+        };
+
+        for selected in selected_pieces.iter() {
+            if selected != entity {
+                commands.entity(selected).remove::<Selected>();
+            }
+        }
+        commands.entity(entity).insert(Selected);
+    }
+}
@@ -0,0 +1,106 @@
+use crate::{CastlingRights, Piece, PieceColour, PieceType, Position, Square};
+use crate::{RANK_1, RANK_2, RANK_7, RANK_8};
+
+/// The number of Chess960 starting positions
+pub const CHESS960_POSITIONS: u16 = 960;
+
+/// The files the two Knights are placed on among the five squares left once the Bishops and Queen
+/// have been placed, for each of the ten ways of placing them
+const KNIGHT_PLACEMENTS: [(usize, usize); 10] = [
+    (0, 1),
+    (0, 2),
+    (0, 3),
+    (0, 4),
+    (1, 2),
+    (1, 3),
+    (1, 4),
+    (2, 3),
+    (2, 4),
+    (3, 4),
+];
+
+impl Position {
+    /// The Chess960 starting position numbered `index`, from 0 to 959
+    ///
+    /// Positions are numbered as Scharnagl's scheme, so 518 is the standard starting position.
+    /// Both sides have the same pieces on their back rank, with the Bishops on squares of opposite
+    /// colours and the King somewhere between the rooks
+    pub fn chess960(index: u16) -> Result<Position, String> {
+        if index >= CHESS960_POSITIONS {
+            return Err(format!(
+                "there is no Chess960 position {index}, they are numbered 0 to {}",
+                CHESS960_POSITIONS - 1
+            ));
+        }
+
+        let back_rank = back_rank(index);
+        let mut position = Position::empty(PieceColour::White);
+        for (file, piece_type) in (0..).zip(back_rank) {
+            for (colour, rank, pawn_rank) in [
+                (PieceColour::White, RANK_1, RANK_2),
+                (PieceColour::Black, RANK_8, RANK_7),
+            ] {
+                position.put_piece(Piece {
+                    colour,
+                    piece_type,
+                    pos: Square::new(rank, file),
+                });
+                position.put_piece(Piece {
+                    colour,
+                    piece_type: PieceType::Pawn,
+                    pos: Square::new(pawn_rank, file),
+                });
+            }
+        }
+
+        let mut rooks = (0..)
+            .zip(back_rank)
+            .filter_map(|(file, piece_type)| (piece_type == PieceType::Rook).then_some(file));
+        let queenside = rooks.next().expect("there are two rooks");
+        let kingside = rooks.next().expect("there are two rooks");
+        position.castling_files = [(kingside, queenside); 2];
+        position.castling = CastlingRights {
+            white_kingside: true,
+            white_queenside: true,
+            black_kingside: true,
+            black_queenside: true,
+        };
+        position.key = position.calculate_key();
+        Ok(position)
+    }
+}
+
+/// The pieces on the back rank of Chess960 position `index`, from the a-file to the h-file
+///
+/// The index is taken apart digit by digit, placing the light-squared Bishop, the dark-squared
+/// Bishop, the Queen and the Knights in turn, then filling the three squares left with a rook, the
+/// King and the other rook
+fn back_rank(index: u16) -> [PieceType; 8] {
+    let mut squares = [None; 8];
+    let index = index as usize;
+
+    squares[2 * (index % 4) + 1] = Some(PieceType::Bishop);
+    let index = index / 4;
+    squares[2 * (index % 4)] = Some(PieceType::Bishop);
+    let index = index / 4;
+    place(&mut squares, index % 6, PieceType::Queen);
+    let (first, second) = KNIGHT_PLACEMENTS[index / 6];
+    // the second Knight is placed first so that placing it does not move the first one's square
+    place(&mut squares, second, PieceType::Knight);
+    place(&mut squares, first, PieceType::Knight);
+    for piece_type in [PieceType::Rook, PieceType::King, PieceType::Rook] {
+        place(&mut squares, 0, piece_type);
+    }
+
+    squares.map(|piece_type| piece_type.expect("every square is filled"))
+}
+
+/// Puts `piece_type` on the empty square numbered `n`, counting only the empty squares
+fn place(squares: &mut [Option<PieceType>; 8], n: usize, piece_type: PieceType) {
+    let square = squares
+        .iter_mut()
+        .filter(|square| square.is_none())
+        .nth(n)
+        .expect("there are enough empty squares");
+    *square = Some(piece_type);
+}
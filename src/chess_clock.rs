@@ -0,0 +1,78 @@
+use bevy::prelude::*;
+use chess::Clock;
+
+use crate::board::{
+    put_back_move, ChessGame, GameStatus, NewGameEvent, PendingPromotion, PlayerTurn, Taken,
+};
+use crate::pieces::Piece;
+use crate::states::AppState;
+
+pub struct ChessClockPlugin;
+
+impl Plugin for ChessClockPlugin {
+    fn build(&self, app: &mut App) {
+        app // new line
+            .init_resource::<ChessClock>()
+            .add_system(
+                run_clock.run_if(
+                    in_state(AppState::InGame).or_else(in_state(AppState::PromotionPending)),
+                ),
+            )
+            .add_system(reset_clock);
+    }
+}
+
+/// The clock for the game, if it is being played with a time control
+///
+/// The clock runs for the player in `PlayerTurn` while the game is being played, including while a
+/// promotion is being chosen, and is paused in the menus and once the game is over. It is pressed
+/// as each move is recorded by `push_move`, and taking back a move puts it back to how it was
+/// before the move
+#[derive(Resource, Default)]
+pub struct ChessClock(pub Option<Clock>);
+
+/// Counts down the time of the player to move, ending the game when it runs out
+///
+/// A pawn waiting for the piece to promote to when time runs out is put back, as its move was
+/// never finished
+#[allow(clippy::too_many_arguments)]
+fn run_clock(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut clock: ResMut<ChessClock>,
+    mut game: ResMut<ChessGame>,
+    mut turn: ResMut<PlayerTurn>,
+    mut game_status: ResMut<GameStatus>,
+    mut pending_promotion: ResMut<PendingPromotion>,
+    mut next_state: ResMut<NextState<AppState>>,
+    pieces: Query<(Entity, &Piece), Without<Taken>>,
+) {
+    let Some(clock) = &mut clock.0 else {
+        return;
+    };
+
+    clock.tick(turn.0, time.delta());
+    if !clock.is_flagged(turn.0) || game.0.position().side_to_move() != turn.0 {
+        return;
+    }
+
+    if let Ok(status) = game.0.run_out_of_time() {
+        if let Some(move_made) = pending_promotion.0.take() {
+            put_back_move(&mut commands, game.0.position(), &move_made, &pieces);
+        }
+        // as with checkmate, the turn passes to the player who did not lose
+        turn.0 = turn.0.opponent();
+        *game_status = status.into();
+        next_state.set(AppState::GameOver);
+    }
+}
+
+/// Puts the clock back to the start when a new game is started
+fn reset_clock(mut clock: ResMut<ChessClock>, mut events: EventReader<NewGameEvent>) {
+    if events.iter().count() == 0 {
+        return;
+    }
+    if let Some(clock) = &mut clock.0 {
+        clock.reset();
+    }
+}
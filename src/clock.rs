@@ -0,0 +1,183 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::PieceColour;
+
+/// How much time each player has for the game, and how much more they are given as they move
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub enum TimeControl {
+    /// The whole game must be played in `time`
+    SuddenDeath { time: Duration },
+    /// `increment` is added to the clock after every move
+    Fischer { time: Duration, increment: Duration },
+    /// The time spent on each move is given back after the move, up to `delay`
+    Bronstein { time: Duration, delay: Duration },
+    /// The clock only starts counting down once `delay` has passed on each move
+    SimpleDelay { time: Duration, delay: Duration },
+}
+
+impl TimeControl {
+    /// The time each player starts the game with
+    pub fn time(&self) -> Duration {
+        match *self {
+            TimeControl::SuddenDeath { time }
+            | TimeControl::Fischer { time, .. }
+            | TimeControl::Bronstein { time, .. }
+            | TimeControl::SimpleDelay { time, .. } => time,
+        }
+    }
+}
+
+impl FromStr for TimeControl {
+    type Err = String;
+
+    /// Reads a time control written as minutes for the game followed by seconds for each move,
+    /// e.g. "5" for sudden death, "3+2" for an increment, "5d3" for a simple delay or "5b3" for a
+    /// Bronstein delay
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid time control '{s}'");
+        let seconds = |value: &str, scale: f64| {
+            value
+                .parse::<f64>()
+                .ok()
+                .filter(|value| value.is_finite() && *value >= 0.0)
+                .map(|value| Duration::from_secs_f64(value * scale))
+                .ok_or_else(invalid)
+        };
+
+        let (minutes, bonus) = match s.find(['+', 'd', 'b']) {
+            Some(index) => (&s[..index], Some((&s[index..index + 1], &s[index + 1..]))),
+            None => (s, None),
+        };
+        let time = seconds(minutes, 60.0)?;
+        if time.is_zero() {
+            return Err(invalid());
+        }
+
+        Ok(match bonus {
+            None => TimeControl::SuddenDeath { time },
+            Some(("+", increment)) => TimeControl::Fischer {
+                time,
+                increment: seconds(increment, 1.0)?,
+            },
+            Some(("d", delay)) => TimeControl::SimpleDelay {
+                time,
+                delay: seconds(delay, 1.0)?,
+            },
+            Some((_, delay)) => TimeControl::Bronstein {
+                time,
+                delay: seconds(delay, 1.0)?,
+            },
+        })
+    }
+}
+
+/// A chess clock, counting down the time left for each player under a `TimeControl`
+///
+/// The clock does not measure time itself: whoever owns it reports the time that passes with
+/// `tick`, and presses it with `press` once a move has been made
+#[derive(Clone)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct Clock {
+    time_control: TimeControl,
+    white: Duration,
+    black: Duration,
+    /// How long has been spent on the move being made, which the delays depend on
+    spent: Duration,
+}
+
+impl Clock {
+    pub fn new(time_control: TimeControl) -> Clock {
+        Clock {
+            time_control,
+            white: time_control.time(),
+            black: time_control.time(),
+            spent: Duration::ZERO,
+        }
+    }
+
+    /// Continues a clock that had `white` and `black` left, with `spent` already spent on the move
+    /// being made
+    pub fn resume(
+        time_control: TimeControl,
+        white: Duration,
+        black: Duration,
+        spent: Duration,
+    ) -> Clock {
+        Clock {
+            time_control,
+            white,
+            black,
+            spent,
+        }
+    }
+
+    pub fn time_control(&self) -> TimeControl {
+        self.time_control
+    }
+
+    /// Puts the clock back to the start of a game
+    pub fn reset(&mut self) {
+        *self = Clock::new(self.time_control);
+    }
+
+    /// The time `colour` has left
+    pub fn remaining(&self, colour: PieceColour) -> Duration {
+        match colour {
+            PieceColour::White => self.white,
+            PieceColour::Black => self.black,
+        }
+    }
+
+    /// How long has been spent on the move being made
+    pub fn spent(&self) -> Duration {
+        self.spent
+    }
+
+    /// Returns true once `colour` has run out of time
+    pub fn is_flagged(&self, colour: PieceColour) -> bool {
+        self.remaining(colour).is_zero()
+    }
+
+    /// Counts `elapsed` against `colour`, who is thinking about their move
+    pub fn tick(&mut self, colour: PieceColour, elapsed: Duration) {
+        let charged = match self.time_control {
+            TimeControl::SimpleDelay { delay, .. } => {
+                let before = self.spent.saturating_sub(delay);
+                let after = (self.spent + elapsed).saturating_sub(delay);
+                after - before
+            }
+            _ => elapsed,
+        };
+        self.spent += elapsed;
+
+        let remaining = self.remaining_mut(colour);
+        *remaining = remaining.saturating_sub(charged);
+    }
+
+    /// Stops the clock of `colour` once they have moved, adding any time they are due for the
+    /// move
+    ///
+    /// A player who has already run out of time is not given any more
+    pub fn press(&mut self, colour: PieceColour) {
+        let bonus = match self.time_control {
+            TimeControl::Fischer { increment, .. } => increment,
+            TimeControl::Bronstein { delay, .. } => self.spent.min(delay),
+            TimeControl::SuddenDeath { .. } | TimeControl::SimpleDelay { .. } => Duration::ZERO,
+        };
+        self.spent = Duration::ZERO;
+
+        let remaining = self.remaining_mut(colour);
+        if !remaining.is_zero() {
+            *remaining += bonus;
+        }
+    }
+
+    fn remaining_mut(&mut self, colour: PieceColour) -> &mut Duration {
+        match colour {
+            PieceColour::White => &mut self.white,
+            PieceColour::Black => &mut self.black,
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use crate::bitboard::{king_attacks, pawn_attacks};
+use crate::{Bitboard, PieceColour, PieceType, Position};
+
+/// The positions in which neither player can checkmate by any series of legal moves
+///
+/// Only positions that can be recognised from the material on the board, or from a simple locked
+/// pawn structure, are detected
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub enum DeadPosition {
+    KingVsKing,
+    KingAndBishopVsKing,
+    KingAndKnightVsKing,
+    /// Any number of Bishops, all on squares of the same colour
+    SameColouredBishops,
+    /// Only Kings and Pawns remain, no Pawn can move and neither King can reach a Pawn it could take
+    BlockedPawns,
+}
+
+impl Position {
+    /// Returns the reason the position is dead, if it is recognised as one
+    pub fn dead_position(&self) -> Option<DeadPosition> {
+        let kings = self.piece_types[PieceType::King.index()];
+        let pawns = self.piece_types[PieceType::Pawn.index()];
+        let knights = self.piece_types[PieceType::Knight.index()];
+        let bishops = self.piece_types[PieceType::Bishop.index()];
+        let others = self.occupied() & !kings;
+
+        if others.is_empty() {
+            Some(DeadPosition::KingVsKing)
+        } else if others == knights && knights.count() == 1 {
+            Some(DeadPosition::KingAndKnightVsKing)
+        } else if others == bishops && bishops.count() == 1 {
+            Some(DeadPosition::KingAndBishopVsKing)
+        } else if others == bishops
+            && (bishops.squares().all(|square| square.is_white())
+                || bishops.squares().all(|square| !square.is_white()))
+        {
+            Some(DeadPosition::SameColouredBishops)
+        } else if others == pawns && self.is_blocked() {
+            Some(DeadPosition::BlockedPawns)
+        } else {
+            None
+        }
+    }
+
+    /// Returns false if `colour` could never checkmate their opponent, which decides whether
+    /// running out of time loses or draws
+    ///
+    /// A lone King can never checkmate. Anything more is treated as enough unless the position is
+    /// dead, as even a single minor piece can mate when the opposing pieces block their own King
+    pub fn can_checkmate(&self, colour: PieceColour) -> bool {
+        self.occupied_by(colour) != self.pieces_of(colour, PieceType::King)
+            && self.dead_position().is_none()
+    }
+
+    /// Checks a position with only Kings and Pawns for a locked pawn structure
+    ///
+    /// Every Pawn must be blocked by the Pawn in front of it with nothing to take, and each King
+    /// must be unable to reach a square next to an opposing Pawn that is not defended by another
+    /// Pawn. The Kings can only ever walk around their own side of the structure, so the position
+    /// never changes in any way that matters
+    fn is_blocked(&self) -> bool {
+        let pawns = self.piece_types[PieceType::Pawn.index()];
+        let colours = [PieceColour::White, PieceColour::Black];
+
+        let pawns_locked = colours.iter().all(|&colour| {
+            let opponent = self.occupied_by(colour.opponent());
+            self.pieces_of(colour, PieceType::Pawn)
+                .squares()
+                .all(|square| {
+                    let blocked = square
+                        .try_add((colour.pawn_movement_direction(), 0))
+                        .map_or(true, |front| pawns.contains(front));
+                    blocked && (pawn_attacks(colour, square) & opponent).is_empty()
+                })
+        });
+
+        pawns_locked
+            && colours
+                .iter()
+                .all(|&colour| !self.king_can_reach_pawn(colour))
+    }
+
+    /// Returns true if the King of `colour` can walk next to an opposing Pawn that is not defended,
+    /// without ever stepping onto a Pawn or a square attacked by an opposing Pawn
+    fn king_can_reach_pawn(&self, colour: PieceColour) -> bool {
+        let opponent = colour.opponent();
+        let pawns = self.piece_types[PieceType::Pawn.index()];
+        let opposing_pawns = self.pieces_of(opponent, PieceType::Pawn);
+
+        let mut defended = Bitboard::EMPTY;
+        for square in opposing_pawns.squares() {
+            defended |= pawn_attacks(opponent, square);
+        }
+        let undefended = opposing_pawns & !defended;
+        let allowed = !pawns & !defended;
+
+        let mut reachable = self.pieces_of(colour, PieceType::King);
+        loop {
+            let mut next = reachable;
+            for square in reachable.squares() {
+                if !(king_attacks(square) & undefended).is_empty() {
+                    return true;
+                }
+                next |= king_attacks(square) & allowed;
+            }
+
+            if next == reachable {
+                return false;
+            }
+            reachable = next;
+        }
+    }
+}
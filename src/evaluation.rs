@@ -0,0 +1,181 @@
+use crate::{PieceColour, PieceType, Position};
+
+/// The most a game phase can be, reached when every Knight, Bishop, Rook and Queen is on the
+/// board
+const OPENING_PHASE: i32 = 24;
+
+/// Bonuses for each piece on each square, in centipawns, from White's point of view with a8 first
+/// so they read like the board
+///
+/// These are the tables from Tomasz Michniewski's Simplified Evaluation Function
+#[rustfmt::skip]
+const PAWN_TABLE: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+    50, 50, 50, 50, 50, 50, 50, 50,
+    10, 10, 20, 30, 30, 20, 10, 10,
+     5,  5, 10, 25, 25, 10,  5,  5,
+     0,  0,  0, 20, 20,  0,  0,  0,
+     5, -5,-10,  0,  0,-10, -5,  5,
+     5, 10, 10,-20,-20, 10, 10,  5,
+     0,  0,  0,  0,  0,  0,  0,  0,
+];
+
+#[rustfmt::skip]
+const KNIGHT_TABLE: [i32; 64] = [
+    -50,-40,-30,-30,-30,-30,-40,-50,
+    -40,-20,  0,  0,  0,  0,-20,-40,
+    -30,  0, 10, 15, 15, 10,  0,-30,
+    -30,  5, 15, 20, 20, 15,  5,-30,
+    -30,  0, 15, 20, 20, 15,  0,-30,
+    -30,  5, 10, 15, 15, 10,  5,-30,
+    -40,-20,  0,  5,  5,  0,-20,-40,
+    -50,-40,-30,-30,-30,-30,-40,-50,
+];
+
+#[rustfmt::skip]
+const BISHOP_TABLE: [i32; 64] = [
+    -20,-10,-10,-10,-10,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5, 10, 10,  5,  0,-10,
+    -10,  5,  5, 10, 10,  5,  5,-10,
+    -10,  0, 10, 10, 10, 10,  0,-10,
+    -10, 10, 10, 10, 10, 10, 10,-10,
+    -10,  5,  0,  0,  0,  0,  5,-10,
+    -20,-10,-10,-10,-10,-10,-10,-20,
+];
+
+#[rustfmt::skip]
+const ROOK_TABLE: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+     5, 10, 10, 10, 10, 10, 10,  5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+     0,  0,  0,  5,  5,  0,  0,  0,
+];
+
+#[rustfmt::skip]
+const QUEEN_TABLE: [i32; 64] = [
+    -20,-10,-10, -5, -5,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5,  5,  5,  5,  0,-10,
+     -5,  0,  5,  5,  5,  5,  0, -5,
+      0,  0,  5,  5,  5,  5,  0, -5,
+    -10,  5,  5,  5,  5,  5,  0,-10,
+    -10,  0,  5,  0,  0,  0,  0,-10,
+    -20,-10,-10, -5, -5,-10,-10,-20,
+];
+
+/// The King should stay sheltered while there are pieces to attack it
+#[rustfmt::skip]
+const KING_OPENING_TABLE: [i32; 64] = [
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -20,-30,-30,-40,-40,-30,-30,-20,
+    -10,-20,-20,-20,-20,-20,-20,-10,
+     20, 20,  0,  0,  0,  0, 20, 20,
+     20, 30, 10,  0,  0, 10, 30, 20,
+];
+
+/// The King should head for the centre once the pieces have been traded off
+#[rustfmt::skip]
+const KING_ENDGAME_TABLE: [i32; 64] = [
+    -50,-40,-30,-20,-20,-30,-40,-50,
+    -30,-20,-10,  0,  0,-10,-20,-30,
+    -30,-10, 20, 30, 30, 20,-10,-30,
+    -30,-10, 30, 40, 40, 30,-10,-30,
+    -30,-10, 30, 40, 40, 30,-10,-30,
+    -30,-10, 20, 30, 30, 20,-10,-30,
+    -30,-30,  0,  0,  0,  0,-30,-30,
+    -50,-30,-30,-30,-30,-30,-30,-50,
+];
+
+impl PieceType {
+    /// The value of the piece in centipawns, with the King given no value as it is never taken
+    pub fn value(&self) -> i32 {
+        match self {
+            PieceType::Pawn => 100,
+            PieceType::Knight => 320,
+            PieceType::Bishop => 330,
+            PieceType::Rook => 500,
+            PieceType::Queen => 900,
+            PieceType::King => 0,
+        }
+    }
+
+    /// How much the piece counts towards the game still being in the opening
+    fn phase(&self) -> i32 {
+        match self {
+            PieceType::Knight | PieceType::Bishop => 1,
+            PieceType::Rook => 2,
+            PieceType::Queen => 4,
+            PieceType::Pawn | PieceType::King => 0,
+        }
+    }
+}
+
+impl Position {
+    /// Estimates how good the position is for the side to move, in centipawns, from the material
+    /// on the board and in hand and where each piece stands
+    ///
+    /// The King's position is scored by blending its opening and endgame tables according to how
+    /// many pieces are left
+    pub fn evaluate(&self) -> i32 {
+        let mut score = 0;
+        let mut phase = 0;
+        let mut king_opening = 0;
+        let mut king_endgame = 0;
+
+        for piece in self.pieces() {
+            // the tables are laid out from White's side with a8 first
+            let rank = match piece.colour {
+                PieceColour::White => 7 - piece.pos.rank,
+                PieceColour::Black => piece.pos.rank,
+            };
+            let index = (rank * 8 + piece.pos.file) as usize;
+            let sign = if piece.colour == self.side_to_move {
+                1
+            } else {
+                -1
+            };
+
+            let square_bonus = match piece.piece_type {
+                PieceType::Pawn => PAWN_TABLE[index],
+                PieceType::Knight => KNIGHT_TABLE[index],
+                PieceType::Bishop => BISHOP_TABLE[index],
+                PieceType::Rook => ROOK_TABLE[index],
+                PieceType::Queen => QUEEN_TABLE[index],
+                PieceType::King => {
+                    king_opening += sign * KING_OPENING_TABLE[index];
+                    king_endgame += sign * KING_ENDGAME_TABLE[index];
+                    0
+                }
+            };
+            score += sign * (piece.piece_type.value() + square_bonus);
+            phase += piece.piece_type.phase();
+        }
+
+        for (colour, pocket) in [PieceColour::White, PieceColour::Black]
+            .into_iter()
+            .zip(self.pockets)
+        {
+            let sign = if colour == self.side_to_move { 1 } else { -1 };
+            for piece_type in [
+                PieceType::Pawn,
+                PieceType::Knight,
+                PieceType::Bishop,
+                PieceType::Rook,
+                PieceType::Queen,
+            ] {
+                score += sign * piece_type.value() * pocket[piece_type.index()] as i32;
+            }
+        }
+
+        let phase = phase.min(OPENING_PHASE);
+        score + (king_opening * phase + king_endgame * (OPENING_PHASE - phase)) / OPENING_PHASE
+    }
+}
@@ -0,0 +1,502 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::variant::{Standard, Variant};
+use crate::{Bitboard, Piece, PieceColour, PieceType, Position, Square};
+use crate::{A_FILE, H_FILE, RANK_1, RANK_3, RANK_6, RANK_8};
+
+/// The standard starting position
+pub const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+/// The order the pieces in each pocket are written in
+const POCKET_ORDER: [PieceType; 5] = [
+    PieceType::Queen,
+    PieceType::Rook,
+    PieceType::Bishop,
+    PieceType::Knight,
+    PieceType::Pawn,
+];
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct CastlingRights {
+    pub white_kingside: bool,
+    pub white_queenside: bool,
+    pub black_kingside: bool,
+    pub black_queenside: bool,
+}
+
+impl CastlingRights {
+    /// Returns the (kingside, queenside) castling rights for `colour`
+    pub fn for_colour(&self, colour: PieceColour) -> (bool, bool) {
+        match colour {
+            PieceColour::White => (self.white_kingside, self.white_queenside),
+            PieceColour::Black => (self.black_kingside, self.black_queenside),
+        }
+    }
+
+    /// Takes away the kingside or queenside castling right of `colour`
+    pub(crate) fn remove(&mut self, colour: PieceColour, kingside: bool) {
+        *self.right_mut(colour, kingside) = false;
+    }
+
+    fn right_mut(&mut self, colour: PieceColour, kingside: bool) -> &mut bool {
+        match (colour, kingside) {
+            (PieceColour::White, true) => &mut self.white_kingside,
+            (PieceColour::White, false) => &mut self.white_queenside,
+            (PieceColour::Black, true) => &mut self.black_kingside,
+            (PieceColour::Black, false) => &mut self.black_queenside,
+        }
+    }
+}
+
+impl FromStr for CastlingRights {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rights = CastlingRights::default();
+        if s == "-" {
+            return Ok(rights);
+        }
+
+        for c in s.chars() {
+            match c {
+                'K' => rights.white_kingside = true,
+                'Q' => rights.white_queenside = true,
+                'k' => rights.black_kingside = true,
+                'q' => rights.black_queenside = true,
+                _ => return Err(format!("invalid castling right '{c}'")),
+            }
+        }
+        Ok(rights)
+    }
+}
+
+impl Display for CastlingRights {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let rights: String = [
+            (self.white_kingside, 'K'),
+            (self.white_queenside, 'Q'),
+            (self.black_kingside, 'k'),
+            (self.black_queenside, 'q'),
+        ]
+        .into_iter()
+        .filter_map(|(right, c)| right.then_some(c))
+        .collect();
+
+        if rights.is_empty() {
+            write!(f, "-")
+        } else {
+            write!(f, "{rights}")
+        }
+    }
+}
+
+impl Position {
+    /// Returns the position in Forsyth-Edwards Notation
+    ///
+    /// Castling rights are written as in X-FEN, which is the same as standard FEN except when
+    /// castling in Chess960 with a rook that is not the outermost one on its side of the King.
+    /// Once either side has given check in a variant that counts checks, the checks given by White
+    /// and Black follow as an extra field, e.g. "+2+1". In variants with pockets the pieces in hand
+    /// follow the placement in brackets, e.g. "[QPn]", and promoted pieces are marked with a '~'
+    pub fn to_fen(&self) -> String {
+        let active_colour = match self.side_to_move {
+            PieceColour::White => "w",
+            PieceColour::Black => "b",
+        };
+        let en_passant = self
+            .en_passant
+            .map_or_else(|| "-".to_string(), |square| square.to_string());
+
+        let mut fen = format!(
+            "{}{} {active_colour} {} {en_passant} {} {}",
+            self.placement(),
+            self.pockets_field(),
+            self.castling_field(),
+            self.halfmove_clock,
+            self.fullmove_number
+        );
+        if self.checks != [0; 2] {
+            fen.push_str(&format!(" +{}+{}", self.checks[0], self.checks[1]));
+        }
+        fen
+    }
+
+    fn placement(&self) -> String {
+        (RANK_1..=RANK_8)
+            .rev()
+            .map(|rank| {
+                let mut rank_str = String::new();
+                let mut empty = 0;
+                for file in A_FILE..=H_FILE {
+                    match self.piece_at(Square { rank, file }) {
+                        Some(piece) => {
+                            if empty > 0 {
+                                rank_str.push_str(&empty.to_string());
+                                empty = 0;
+                            }
+                            rank_str.push(piece.fen_letter());
+                            if self.promoted.contains(piece.pos) {
+                                rank_str.push('~');
+                            }
+                        }
+                        None => empty += 1,
+                    }
+                }
+                if empty > 0 {
+                    rank_str.push_str(&empty.to_string());
+                }
+                rank_str
+            })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// The pieces in each pocket, White's first and from the Queen down, which is left out unless
+    /// the variant has pockets or a piece is in one
+    fn pockets_field(&self) -> String {
+        if !self.variant.has_pockets() && self.pockets == [[0; 6]; 2] {
+            return String::new();
+        }
+
+        let mut field = String::from("[");
+        for colour in [PieceColour::White, PieceColour::Black] {
+            for piece_type in POCKET_ORDER {
+                let letter = match colour {
+                    PieceColour::White => piece_type.fen_letter(),
+                    PieceColour::Black => piece_type.fen_letter().to_ascii_lowercase(),
+                };
+                for _ in 0..self.pocket(colour, piece_type) {
+                    field.push(letter);
+                }
+            }
+        }
+        field.push(']');
+        field
+    }
+
+    fn castling_field(&self) -> String {
+        if !self.is_chess960() {
+            return self.castling.to_string();
+        }
+
+        let mut field = String::new();
+        for (colour, rank) in [(PieceColour::White, RANK_1), (PieceColour::Black, RANK_8)] {
+            let (kingside, queenside) = self.castling.for_colour(colour);
+            let (kingside_file, queenside_file) = self.castling_files(colour);
+            let rooks: Vec<_> = self
+                .pieces_of(colour, PieceType::Rook)
+                .squares()
+                .filter(|square| square.rank == rank)
+                .map(|square| square.file)
+                .collect();
+
+            let sides = [
+                (kingside, kingside_file, 'K', rooks.iter().max()),
+                (queenside, queenside_file, 'Q', rooks.iter().min()),
+            ];
+            for (has_right, file, letter, outermost) in sides {
+                if !has_right {
+                    continue;
+                }
+                // an inner rook is named by its file, as the letter would mean the outer one
+                let letter = if outermost.is_some_and(|&outermost| outermost != file) {
+                    (b'A' + file as u8) as char
+                } else {
+                    letter
+                };
+                field.push(match colour {
+                    PieceColour::White => letter,
+                    PieceColour::Black => letter.to_ascii_lowercase(),
+                });
+            }
+        }
+
+        field
+    }
+
+    /// Sets the castling rights from the castling field of a FEN, in which each right is either a
+    /// letter from "KQkq", for the outermost rook on that side of the King as in X-FEN, or the
+    /// file of the rook as in Shredder-FEN
+    fn parse_castling(&mut self, field: &str) -> Result<(), String> {
+        if field == "-" {
+            return Ok(());
+        }
+
+        for c in field.chars() {
+            let (colour, rank) = if c.is_ascii_uppercase() {
+                (PieceColour::White, RANK_1)
+            } else {
+                (PieceColour::Black, RANK_8)
+            };
+            let king_file = self
+                .pieces_of(colour, PieceType::King)
+                .squares()
+                .find(|square| square.rank == rank)
+                .map(|square| square.file)
+                .ok_or_else(|| format!("no King to castle with for '{c}'"))?;
+            let mut rooks = self
+                .pieces_of(colour, PieceType::Rook)
+                .squares()
+                .filter(|square| square.rank == rank)
+                .map(|square| square.file);
+            let no_rook = || format!("no rook to castle with for '{c}'");
+
+            let (kingside, file) = match c.to_ascii_uppercase() {
+                'K' => (
+                    true,
+                    rooks
+                        .filter(|&file| file > king_file)
+                        .max()
+                        .ok_or_else(no_rook)?,
+                ),
+                'Q' => (
+                    false,
+                    rooks
+                        .filter(|&file| file < king_file)
+                        .min()
+                        .ok_or_else(no_rook)?,
+                ),
+                letter @ 'A'..='H' => {
+                    let file = (letter as u8 - b'A') as i8;
+                    if file == king_file {
+                        return Err(format!("invalid castling right '{c}'"));
+                    }
+                    if !rooks.any(|rook| rook == file) {
+                        return Err(no_rook());
+                    }
+                    (file > king_file, file)
+                }
+                _ => return Err(format!("invalid castling right '{c}'")),
+            };
+
+            *self.castling.right_mut(colour, kingside) = true;
+            let files = &mut self.castling_files[colour.index()];
+            if kingside {
+                files.0 = file;
+            } else {
+                files.1 = file;
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the pieces could have been reached in a game under the rules of the variant: each
+    /// side has exactly one King unless the variant treats it as an ordinary piece, there are no
+    /// pawns on the first or eighth rank, and an en passant target is just behind a pawn that
+    /// could have moved two squares past it
+    fn check_pieces(&self) -> Result<(), String> {
+        if self.variant.needs_kings() {
+            for colour in [PieceColour::White, PieceColour::Black] {
+                let kings = self.pieces_of(colour, PieceType::King).count();
+                if kings != 1 {
+                    return Err(format!("expected one {colour} King but found {kings}"));
+                }
+            }
+        }
+
+        if let Some(pawn) = self.pieces().find(|piece| {
+            piece.piece_type == PieceType::Pawn && [RANK_1, RANK_8].contains(&piece.pos.rank)
+        }) {
+            return Err(format!("invalid pawn on {}", pawn.pos));
+        }
+
+        if let Some(target) = self.en_passant {
+            let direction = self.side_to_move.pawn_movement_direction();
+            let moved_pawn = self.piece_at(target + (-direction, 0));
+            let has_moved_pawn = moved_pawn.is_some_and(|pawn| {
+                pawn.piece_type == PieceType::Pawn && pawn.colour != self.side_to_move
+            });
+            let passed_squares = [target, target + (direction, 0)];
+            if !has_moved_pawn
+                || passed_squares
+                    .iter()
+                    .any(|&square| self.piece_at(square).is_some())
+            {
+                return Err(format!("invalid en passant target '{target}'"));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads the checks given by White and Black from the field Three-check adds to the end of the
+/// FEN, e.g. "+2+1"
+fn parse_checks(field: &str) -> Result<[u8; 2], String> {
+    let invalid = || format!("invalid checks '{field}'");
+    let (white, black) = field
+        .strip_prefix('+')
+        .and_then(|checks| checks.split_once('+'))
+        .ok_or_else(invalid)?;
+    Ok([
+        white.parse().map_err(|_| invalid())?,
+        black.parse().map_err(|_| invalid())?,
+    ])
+}
+
+/// Reads the pieces in each pocket from the letters between the brackets that follow the
+/// placement, e.g. "QPn" for a White Queen and pawn and a Black Knight
+fn parse_pockets(field: &str) -> Result<[[u8; 6]; 2], String> {
+    let mut pockets = [[0u8; 6]; 2];
+    for c in field.chars() {
+        let colour = if c.is_ascii_uppercase() {
+            PieceColour::White
+        } else {
+            PieceColour::Black
+        };
+        let piece_type = PieceType::from_fen_letter(c.to_ascii_uppercase())
+            .filter(|&piece_type| piece_type != PieceType::King)
+            .ok_or_else(|| format!("invalid piece in pocket '{c}'"))?;
+        let count = &mut pockets[colour.index()][piece_type.index()];
+        *count = count
+            .checked_add(1)
+            .ok_or_else(|| format!("too many '{c}' in pocket"))?;
+    }
+    Ok(pockets)
+}
+
+/// Reads the pieces on the board, with a '~' after any piece that was promoted, returning them
+/// with the squares of the promoted pieces
+fn parse_placement(placement: &str) -> Result<(Vec<Piece>, Bitboard), String> {
+    let ranks: Vec<_> = placement.split('/').collect();
+    if ranks.len() != 8 {
+        return Err(format!("expected 8 ranks but found {}", ranks.len()));
+    }
+
+    let mut pieces = Vec::new();
+    let mut promoted = Bitboard::EMPTY;
+    // FEN lists ranks from the eighth down to the first
+    for (rank, rank_str) in (RANK_1..=RANK_8).rev().zip(ranks) {
+        let mut file = A_FILE;
+        let mut last_piece = None;
+        for c in rank_str.chars() {
+            if c == '~' {
+                let square = last_piece
+                    .take()
+                    .ok_or_else(|| format!("'~' does not follow a piece in rank {}", rank + 1))?;
+                promoted |= Bitboard::from_square(square);
+                continue;
+            }
+            last_piece = None;
+            if let Some(empty) = c.to_digit(10) {
+                if empty == 0 || file + empty as i8 > H_FILE + 1 {
+                    return Err(format!("invalid empty squares '{c}' in rank {}", rank + 1));
+                }
+                file += empty as i8;
+                continue;
+            }
+
+            let pos = Square { rank, file };
+            if !pos.is_valid() {
+                return Err(format!("too many squares in rank {}", rank + 1));
+            }
+
+            let colour = if c.is_ascii_uppercase() {
+                PieceColour::White
+            } else {
+                PieceColour::Black
+            };
+            let piece_type = PieceType::from_fen_letter(c.to_ascii_uppercase())
+                .ok_or_else(|| format!("invalid piece '{c}'"))?;
+
+            pieces.push(Piece {
+                colour,
+                piece_type,
+                pos,
+            });
+            last_piece = Some(pos);
+            file += 1;
+        }
+
+        if file != H_FILE + 1 {
+            return Err(format!("rank {} does not have 8 squares", rank + 1));
+        }
+    }
+
+    Ok((pieces, promoted))
+}
+
+impl Position {
+    /// Reads a position from a FEN, to be played under the rules of `variant`, rejecting any
+    /// position that could not be reached in a game of the variant
+    pub fn from_fen(fen: &str, variant: &'static dyn Variant) -> Result<Position, String> {
+        let mut fields: Vec<_> = fen.split_whitespace().collect();
+        let checks = if fields.len() == 7 {
+            parse_checks(fields.remove(6))?
+        } else {
+            [0; 2]
+        };
+        let [placement, active_colour, castling, en_passant, halfmove_clock, fullmove_number] =
+            fields[..]
+        else {
+            return Err(format!("expected 6 fields but found {}", fields.len()));
+        };
+
+        let (placement, pockets) = match placement.split_once('[') {
+            Some((placement, pockets)) => {
+                let pockets = pockets
+                    .strip_suffix(']')
+                    .ok_or_else(|| format!("invalid pockets '[{pockets}'"))?;
+                (placement, parse_pockets(pockets)?)
+            }
+            None => (placement, [[0; 6]; 2]),
+        };
+        let (pieces, promoted) = parse_placement(placement)?;
+
+        let side_to_move = match active_colour {
+            "w" => PieceColour::White,
+            "b" => PieceColour::Black,
+            _ => return Err(format!("invalid active colour '{active_colour}'")),
+        };
+
+        let en_passant = match en_passant {
+            "-" => None,
+            square => {
+                let square: Square = square.parse()?;
+                let expected_rank = match side_to_move {
+                    PieceColour::White => RANK_6,
+                    PieceColour::Black => RANK_3,
+                };
+                if square.rank != expected_rank {
+                    return Err(format!("invalid en passant target '{square}'"));
+                }
+                Some(square)
+            }
+        };
+
+        let halfmove_clock = halfmove_clock
+            .parse()
+            .map_err(|_| format!("invalid halfmove clock '{halfmove_clock}'"))?;
+        let fullmove_number = fullmove_number
+            .parse()
+            .map_err(|_| format!("invalid fullmove number '{fullmove_number}'"))?;
+
+        let mut position = Position {
+            en_passant,
+            halfmove_clock,
+            fullmove_number,
+            checks,
+            pockets,
+            promoted,
+            ..Position::empty(side_to_move)
+        };
+        for piece in pieces {
+            position.put_piece(piece);
+        }
+        position.parse_castling(castling)?;
+        position.set_variant(variant);
+        position.check_pieces()?;
+        Ok(position)
+    }
+}
+
+/// Reads a position from a FEN to be played under the standard rules
+impl FromStr for Position {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Position::from_fen(s, &Standard)
+    }
+}
@@ -0,0 +1,316 @@
+use crate::{ChessMove, DeadPosition, PieceColour, Position, Undo};
+
+/// The state of a game from the perspective of the side to move
+#[derive(Clone, Copy, PartialEq)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub enum Status {
+    OnGoing,
+    Check,
+    Checkmate,
+    /// The side to move ran out of time and their opponent could still checkmate them
+    OutOfTime,
+    /// The player of the given colour resigned
+    Resigned(PieceColour),
+    /// The player of the given colour won by a rule of the variant other than checkmate, such as
+    /// bringing their King to the centre in King of the Hill
+    VariantWin(PieceColour),
+    Draw(DrawReason),
+}
+
+impl Status {
+    /// Returns true if the game has finished in checkmate, on time, by resignation, by the rules
+    /// of the variant or in a draw
+    pub fn is_over(&self) -> bool {
+        matches!(
+            self,
+            Status::Checkmate
+                | Status::OutOfTime
+                | Status::Resigned(_)
+                | Status::VariantWin(_)
+                | Status::Draw(_)
+        )
+    }
+}
+
+/// The various different rules that can lead to a draw. A full implementation of DeadPosition is
+/// probably beyond the scope of this project so only simple material based dead positions and
+/// locked pawn structures are captured, not more complex board state scenarios where in theory
+/// sufficient material exits for a mate but it is impossible to actually achieve mate.
+#[derive(Clone, Copy, PartialEq)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub enum DrawReason {
+    Stalemate,
+    /// Claimed by a player when the same position has occurred three times
+    ThreefoldRepetition,
+    FivefoldRepetition,
+    /// Claimed by a player when fifty moves have been made by each side without a capture or a
+    /// pawn move
+    FiftyMoveRule,
+    SeventyFiveMoveRule,
+    DeadPosition(DeadPosition),
+    /// The side to move ran out of time, but their opponent could never checkmate them
+    OutOfTimeWithInsufficientMaterial,
+    /// One player offered a draw and the other accepted
+    Agreement,
+    /// A draw particular to the variant being played, such as both Kings reaching the eighth rank
+    /// in Racing Kings
+    VariantRule,
+}
+
+/// Something a player does to end the game, or to offer to end it, other than moving
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub enum PlayerAction {
+    Resign(PieceColour),
+    OfferDraw(PieceColour),
+    AcceptDraw(PieceColour),
+    DeclineDraw(PieceColour),
+    /// Claims a draw by threefold repetition or the fifty-move rule for the side to move
+    ClaimDraw,
+}
+
+/// A game played from a starting position, keeping every move so that they can be taken back
+#[derive(Clone)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct Game {
+    starting_position: Position,
+    position: Position,
+    moves: Vec<(ChessMove, Undo)>,
+    /// The key of the starting position followed by the key after each move
+    keys: Vec<u64>,
+    /// How the game ended if it was not decided on the board, by a claimed draw or running out
+    /// of time
+    ending: Option<Status>,
+    /// The player whose offer of a draw is waiting for an answer
+    draw_offer: Option<PieceColour>,
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Game::new(Position::default())
+    }
+}
+
+impl Game {
+    pub fn new(starting_position: Position) -> Game {
+        Game {
+            keys: vec![starting_position.key()],
+            position: starting_position.clone(),
+            starting_position,
+            moves: Vec::new(),
+            ending: None,
+            draw_offer: None,
+        }
+    }
+
+    pub fn starting_position(&self) -> &Position {
+        &self.starting_position
+    }
+
+    /// The current position
+    pub fn position(&self) -> &Position {
+        &self.position
+    }
+
+    /// The position before the last move was made, if any move has been made
+    pub fn previous_position(&self) -> Option<Position> {
+        let (last_move, undo) = self.moves.last()?;
+        let mut position = self.position.clone();
+        position.unmake_move(*last_move, *undo);
+        Some(position)
+    }
+
+    /// The key of the starting position followed by the key after each move
+    pub(crate) fn keys(&self) -> &[u64] {
+        &self.keys
+    }
+
+    /// Every move made since the starting position
+    pub fn moves(&self) -> impl Iterator<Item = &ChessMove> {
+        self.moves.iter().map(|(chess_move, _)| chess_move)
+    }
+
+    pub fn last_move(&self) -> Option<&ChessMove> {
+        self.moves.last().map(|(chess_move, _)| chess_move)
+    }
+
+    /// The moves that can be made now, of which there are none once the game is over
+    pub fn legal_moves(&self) -> Vec<ChessMove> {
+        if self.status().is_over() {
+            return Vec::new();
+        }
+
+        self.position.legal_moves()
+    }
+
+    /// Makes `chess_move` if the game is still going and it is legal in the current position
+    pub fn make_move(&mut self, chess_move: ChessMove) -> Result<(), String> {
+        if self.status().is_over() {
+            return Err("the game is already over".to_string());
+        }
+        if !self.position.is_legal(&chess_move) {
+            return Err(format!("{chess_move} is not a legal move"));
+        }
+
+        // moving instead of answering an offer of a draw declines it
+        if self.draw_offer == Some(self.position.side_to_move().opponent()) {
+            self.draw_offer = None;
+        }
+
+        let undo = self.position.make_move(chess_move);
+        self.moves.push((chess_move, undo));
+        self.keys.push(self.position.key());
+        Ok(())
+    }
+
+    /// Takes back the last move, returning it if there was one
+    pub fn unmake_move(&mut self) -> Option<ChessMove> {
+        let (chess_move, undo) = self.moves.pop()?;
+        self.position.unmake_move(chess_move, undo);
+        self.keys.pop();
+        self.ending = None;
+        self.draw_offer = None;
+        Some(chess_move)
+    }
+
+    /// The number of times the current position has occurred, including now
+    ///
+    /// Only positions since the last capture or pawn move are compared, as no earlier position
+    /// can occur again
+    pub fn repetitions(&self) -> usize {
+        let current = self.position.key();
+        self.keys
+            .iter()
+            .rev()
+            .take(self.position.halfmove_clock() as usize + 1)
+            .filter(|&&key| key == current)
+            .count()
+    }
+
+    /// Returns the draw the side to move is entitled to claim, if any
+    pub fn claimable_draw(&self) -> Option<DrawReason> {
+        if self.repetitions() >= 3 {
+            Some(DrawReason::ThreefoldRepetition)
+        } else if self.position.halfmove_clock() >= 100 {
+            Some(DrawReason::FiftyMoveRule)
+        } else {
+            None
+        }
+    }
+
+    /// Ends the game as a draw if the side to move is entitled to claim one
+    pub fn claim_draw(&mut self) -> Result<DrawReason, String> {
+        if self.status().is_over() {
+            return Err("the game is already over".to_string());
+        }
+
+        let reason = self
+            .claimable_draw()
+            .ok_or_else(|| "there is no draw to claim".to_string())?;
+        self.ending = Some(Status::Draw(reason));
+        Ok(reason)
+    }
+
+    /// The player who has offered a draw, if the offer has not yet been answered
+    pub fn draw_offer(&self) -> Option<PieceColour> {
+        self.draw_offer
+    }
+
+    /// Offers a draw on behalf of `colour`, which stands until their opponent accepts or declines
+    /// it or makes a move
+    pub fn offer_draw(&mut self, colour: PieceColour) -> Result<(), String> {
+        if self.status().is_over() {
+            return Err("the game is already over".to_string());
+        }
+        match self.draw_offer {
+            Some(offered_by) if offered_by == colour => {
+                Err(format!("{colour} has already offered a draw"))
+            }
+            Some(offered_by) => Err(format!("{offered_by} has already offered a draw")),
+            None => {
+                self.draw_offer = Some(colour);
+                Ok(())
+            }
+        }
+    }
+
+    /// Accepts the draw offered to `colour`, ending the game as a draw by agreement
+    pub fn accept_draw(&mut self, colour: PieceColour) -> Result<(), String> {
+        self.answer_draw_offer(colour)?;
+        self.ending = Some(Status::Draw(DrawReason::Agreement));
+        Ok(())
+    }
+
+    /// Declines the draw offered to `colour`, so the game continues
+    pub fn decline_draw(&mut self, colour: PieceColour) -> Result<(), String> {
+        self.answer_draw_offer(colour)
+    }
+
+    fn answer_draw_offer(&mut self, colour: PieceColour) -> Result<(), String> {
+        if self.status().is_over() {
+            return Err("the game is already over".to_string());
+        }
+        if self.draw_offer != Some(colour.opponent()) {
+            return Err(format!("{} has not offered a draw", colour.opponent()));
+        }
+        self.draw_offer = None;
+        Ok(())
+    }
+
+    /// Ends the game with `colour` resigning, which their opponent wins
+    pub fn resign(&mut self, colour: PieceColour) -> Result<(), String> {
+        if self.status().is_over() {
+            return Err("the game is already over".to_string());
+        }
+        self.ending = Some(Status::Resigned(colour));
+        self.draw_offer = None;
+        Ok(())
+    }
+
+    /// Carries out `action`, as long as the player taking it is allowed to
+    pub fn act(&mut self, action: PlayerAction) -> Result<(), String> {
+        match action {
+            PlayerAction::Resign(colour) => self.resign(colour),
+            PlayerAction::OfferDraw(colour) => self.offer_draw(colour),
+            PlayerAction::AcceptDraw(colour) => self.accept_draw(colour),
+            PlayerAction::DeclineDraw(colour) => self.decline_draw(colour),
+            PlayerAction::ClaimDraw => self.claim_draw().map(|_| ()),
+        }
+    }
+
+    /// Ends the game because the side to move has run out of time
+    ///
+    /// This loses the game, unless their opponent could never win, such as by not having the
+    /// material to ever checkmate them, in which case it is a draw
+    pub fn run_out_of_time(&mut self) -> Result<Status, String> {
+        if self.status().is_over() {
+            return Err("the game is already over".to_string());
+        }
+
+        let opponent = self.position.side_to_move().opponent();
+        let ending = if self.position.variant().can_win(&self.position, opponent) {
+            Status::OutOfTime
+        } else {
+            Status::Draw(DrawReason::OutOfTimeWithInsufficientMaterial)
+        };
+        self.ending = Some(ending);
+        Ok(ending)
+    }
+
+    /// Determines the state of the game, which includes draws that depend on the moves made as
+    /// well as the current position
+    pub fn status(&self) -> Status {
+        let status = self.position.status();
+        if status.is_over() {
+            return status;
+        }
+
+        if let Some(ending) = self.ending {
+            ending
+        } else if self.repetitions() >= 5 {
+            Status::Draw(DrawReason::FivefoldRepetition)
+        } else {
+            status
+        }
+    }
+}
@@ -0,0 +1,48 @@
+//! The rules of chess, independent of the Bevy app that renders the game
+//!
+//! A `Game` can be driven entirely from code: make and unmake moves, list the legal moves and
+//! query the status of the game. `Piece` and `Square` derive `Component` so that the game can
+//! attach them to entities directly, but nothing here requires an `App` to be running.
+
+pub use bitboard::Bitboard;
+pub use chess960::CHESS960_POSITIONS;
+pub use clock::{Clock, TimeControl};
+pub use dead_position::DeadPosition;
+pub use fen::{CastlingRights, STARTING_FEN};
+pub use game::{DrawReason, Game, PlayerAction, Status};
+pub use network::{NetworkEvent, NetworkPeer, NetworkRole, PROTOCOL_VERSION};
+pub use perft::{divide, perft};
+pub use pgn::{PgnGame, PgnTags};
+pub use piece::{Piece, PieceColour, PieceType};
+pub use position::{ChessMove, Position, Undo};
+pub use search::{Score, SearchInfo, SearchLimits};
+pub use session::{Session, SESSION_VERSION};
+pub use square::{
+    Square, A_FILE, B_FILE, C_FILE, D_FILE, E_FILE, F_FILE, G_FILE, H_FILE, RANK_1, RANK_2, RANK_3,
+    RANK_4, RANK_5, RANK_6, RANK_7, RANK_8,
+};
+pub use uci::{go_command, parse_info, parse_position_command, position_command, UciEngine};
+pub use variant::{
+    variant_by_name, Antichess, Atomic, Crazyhouse, KingOfTheHill, RacingKings, Standard,
+    ThreeCheck, Variant, VARIANTS,
+};
+
+mod bitboard;
+mod chess960;
+mod clock;
+mod dead_position;
+mod evaluation;
+mod fen;
+mod game;
+mod network;
+mod perft;
+mod pgn;
+mod piece;
+mod position;
+mod san;
+mod search;
+mod session;
+mod square;
+mod uci;
+mod variant;
+mod zobrist;
@@ -1,27 +1,311 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
 use bevy::prelude::*;
 use bevy_mod_picking::{DefaultPickingPlugins, PickingCameraBundle};
+use chess::{
+    variant_by_name, Clock, Game, NetworkPeer, NetworkRole, PgnGame, PieceColour, Position,
+    Session, Standard, TimeControl, UciEngine, Variant, CHESS960_POSITIONS,
+};
 
 use pieces::PiecesPlugin;
 
-use crate::board::BoardPlugin;
+use crate::ai::{AiPlugin, AiSettings};
+use crate::board::{BoardPlugin, ImportedGame, Player, PlayerNames, Players, StartingPosition};
+use crate::chess_clock::{ChessClock, ChessClockPlugin};
+use crate::multiplayer::{MultiplayerPlugin, Network};
+use crate::persistence::{read_session, LoadedSession, PersistencePlugin};
+use crate::states::AppState;
 use crate::ui::UiPlugin;
 
+mod ai;
 mod board;
+mod chess_clock;
+mod multiplayer;
+mod persistence;
 mod pieces;
+mod states;
 mod ui;
 
 fn main() {
+    let (starting_position, imported_game) = match imported_game() {
+        Some(pgn_game) => {
+            let game = pgn_game.game;
+            let moves = game.moves().copied().collect();
+            (game.starting_position().clone(), ImportedGame(Some(moves)))
+        }
+        None => (starting_fen(), ImportedGame::default()),
+    };
+
+    let network = network(&starting_position);
+    let mut players = players();
+    let player_names = player_names();
+    let chess_clock = chess_clock();
+    let mut loaded_session = loaded_session();
+    if let Some(peer) = &network.0 {
+        let peer = peer.lock().unwrap();
+        match peer.colour() {
+            Some(PieceColour::White) => players.black = Player::Remote,
+            Some(PieceColour::Black) => players.white = Player::Remote,
+            None => {
+                players.white = Player::Remote;
+                players.black = Player::Remote;
+            }
+        }
+        // players and spectators carry on with the host's game
+        if peer.role() != NetworkRole::Host {
+            loaded_session.0 = Some(Session {
+                game: peer.game().clone(),
+                white: player_names.white.clone(),
+                black: player_names.black.clone(),
+                clock: chess_clock.0.clone(),
+            });
+        }
+    }
+
     App::default()
         .insert_resource(Msaa::Sample4)
+        .insert_resource(StartingPosition(starting_position))
+        .insert_resource(imported_game)
+        .insert_resource(players)
+        .insert_resource(player_names)
+        .insert_resource(ai_settings())
+        .insert_resource(chess_clock)
+        .insert_resource(loaded_session)
+        .insert_resource(network)
         .add_plugins(DefaultPlugins)
         .add_plugins(DefaultPickingPlugins)
+        .add_state::<AppState>()
         .add_plugin(BoardPlugin)
         .add_plugin(PiecesPlugin)
         .add_plugin(UiPlugin)
+        .add_plugin(AiPlugin)
+        .add_plugin(ChessClockPlugin)
+        .add_plugin(PersistencePlugin)
+        .add_plugin(MultiplayerPlugin)
         .add_startup_system(setup)
         .run();
 }
 
+/// Reads the starting position from the `--fen` command line argument, or the Chess960 position
+/// numbered by `--chess960`, which may be "random", falling back to the starting position of the
+/// variant if neither is given
+///
+/// The game is played under the rules of the variant named by `--variant`, or standard chess
+fn starting_fen() -> Position {
+    let args: Vec<_> = std::env::args().collect();
+    let variant = variant();
+    if let Some(index) = args.iter().position(|arg| arg == "--chess960") {
+        let number = args
+            .get(index + 1)
+            .expect("--chess960 requires a position number or \"random\"");
+        let number = if number == "random" {
+            let nanos = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .subsec_nanos();
+            (nanos % CHESS960_POSITIONS as u32) as u16
+        } else {
+            number
+                .parse()
+                .unwrap_or_else(|_| panic!("invalid Chess960 position '{number}'"))
+        };
+        println!("Chess960 position {number}");
+        let mut position = Position::chess960(number).unwrap_or_else(|err| panic!("{err}"));
+        position.set_variant(variant);
+        return position;
+    }
+
+    let Some(index) = args.iter().position(|arg| arg == "--fen") else {
+        return Position::starting(variant);
+    };
+
+    let fen = args.get(index + 1).expect("--fen requires a position");
+    Position::from_fen(fen, variant).unwrap_or_else(|err| panic!("invalid FEN '{fen}': {err}"))
+}
+
+/// Reads the variant to play from the `--variant` command line argument, e.g. "kingofthehill" or
+/// "Racing Kings"
+fn variant() -> &'static dyn Variant {
+    let args: Vec<_> = std::env::args().collect();
+    let Some(index) = args.iter().position(|arg| arg == "--variant") else {
+        return &Standard;
+    };
+
+    let name = args.get(index + 1).expect("--variant requires a name");
+    variant_by_name(name).unwrap_or_else(|| panic!("unknown variant '{name}'"))
+}
+
+/// Reads the game to replay from the PGN file given by the `--pgn` command line argument, choosing
+/// the game numbered by `--game` if the file has more than one
+fn imported_game() -> Option<PgnGame> {
+    let args: Vec<_> = std::env::args().collect();
+    let index = args.iter().position(|arg| arg == "--pgn")?;
+
+    let path = args.get(index + 1).expect("--pgn requires a file");
+    let number = match args.iter().position(|arg| arg == "--game") {
+        Some(index) => {
+            let number = args.get(index + 1).expect("--game requires a number");
+            number
+                .parse()
+                .unwrap_or_else(|_| panic!("invalid game number '{number}'"))
+        }
+        None => 1,
+    };
+
+    let pgn = std::fs::read_to_string(path)
+        .unwrap_or_else(|err| panic!("unable to read '{path}': {err}"));
+    let mut games =
+        PgnGame::parse_all(&pgn).unwrap_or_else(|err| panic!("invalid PGN in '{path}': {err}"));
+    let count = games.len();
+    if number == 0 || number > count {
+        panic!("'{path}' has {count} games, so there is no game {number}");
+    }
+
+    let pgn_game = games.swap_remove(number - 1);
+    println!(
+        "{} - {} {}",
+        pgn_game.tag("White").unwrap_or("?"),
+        pgn_game.tag("Black").unwrap_or("?"),
+        pgn_game.result
+    );
+    Some(pgn_game)
+}
+
+/// Reads the sides played by the computer from the `--ai` command line argument, which can be
+/// "white", "black" or "both"
+fn players() -> Players {
+    let args: Vec<_> = std::env::args().collect();
+    let Some(index) = args.iter().position(|arg| arg == "--ai") else {
+        return Players::default();
+    };
+
+    let side = args.get(index + 1).expect("--ai requires a side");
+    let (white, black) = match side.as_str() {
+        "white" => (Player::Computer, Player::Human),
+        "black" => (Player::Human, Player::Computer),
+        "both" => (Player::Computer, Player::Computer),
+        _ => panic!("invalid side '{side}', expected white, black or both"),
+    };
+    Players { white, black }
+}
+
+/// Reads the names of the players from the `--white-name` and `--black-name` command line
+/// arguments
+fn player_names() -> PlayerNames {
+    let args: Vec<_> = std::env::args().collect();
+    let name = |flag: &str| {
+        let index = args.iter().position(|arg| arg == flag)?;
+        let name = args
+            .get(index + 1)
+            .unwrap_or_else(|| panic!("{flag} requires a name"));
+        Some(name.clone())
+    };
+
+    let default = PlayerNames::default();
+    PlayerNames {
+        white: name("--white-name").unwrap_or(default.white),
+        black: name("--black-name").unwrap_or(default.black),
+    }
+}
+
+/// Reads the seconds the computer spends on each move from the `--ai-time` command line argument,
+/// and starts the UCI engine given by `--engine` to find its moves
+fn ai_settings() -> AiSettings {
+    let args: Vec<_> = std::env::args().collect();
+    let mut settings = AiSettings::default();
+
+    if let Some(index) = args.iter().position(|arg| arg == "--ai-time") {
+        let seconds = args
+            .get(index + 1)
+            .expect("--ai-time requires a number of seconds");
+        let seconds: f64 = seconds
+            .parse()
+            .unwrap_or_else(|_| panic!("invalid number of seconds '{seconds}'"));
+        settings.limits.time = Some(Duration::from_secs_f64(seconds));
+    }
+
+    if let Some(index) = args.iter().position(|arg| arg == "--engine") {
+        let path = args.get(index + 1).expect("--engine requires a program");
+        let engine = UciEngine::start(path, &[]).unwrap_or_else(|err| panic!("{err}"));
+        println!("Using {}", engine.name().unwrap_or(path));
+        settings.engine = Some(Arc::new(Mutex::new(engine)));
+    }
+    settings
+}
+
+/// Reads the time control from the `--clock` command line argument, e.g. "5" for five minutes
+/// each, "3+2" for three minutes and a two second increment, "5d3" for a three second delay or
+/// "5b3" for a three second Bronstein delay
+fn chess_clock() -> ChessClock {
+    let args: Vec<_> = std::env::args().collect();
+    let Some(index) = args.iter().position(|arg| arg == "--clock") else {
+        return ChessClock::default();
+    };
+
+    let time_control: TimeControl = args
+        .get(index + 1)
+        .expect("--clock requires a time control")
+        .parse()
+        .unwrap_or_else(|err| panic!("{err}"));
+    ChessClock(Some(Clock::new(time_control)))
+}
+
+/// Reads the session to carry on with from the file given by the `--load` command line argument
+fn loaded_session() -> LoadedSession {
+    let args: Vec<_> = std::env::args().collect();
+    let Some(index) = args.iter().position(|arg| arg == "--load") else {
+        return LoadedSession::default();
+    };
+
+    let path = args.get(index + 1).expect("--load requires a file");
+    LoadedSession(Some(
+        read_session(path).unwrap_or_else(|err| panic!("{err}")),
+    ))
+}
+
+/// Hosts a game over the network on the address given by the `--host` command line argument,
+/// playing the colour given by `--colour`, joins the game hosted at the address given by
+/// `--connect`, or watches the game hosted at the address given by `--watch`
+fn network(starting_position: &Position) -> Network {
+    let args: Vec<_> = std::env::args().collect();
+    let address = |flag: &str| {
+        let index = args.iter().position(|arg| arg == flag)?;
+        let address = args
+            .get(index + 1)
+            .unwrap_or_else(|| panic!("{flag} requires an address"));
+        Some(address.clone())
+    };
+
+    let peer = if let Some(address) = address("--host") {
+        let colour = match args.iter().position(|arg| arg == "--colour") {
+            Some(index) => match args.get(index + 1).map(String::as_str) {
+                Some("white") => PieceColour::White,
+                Some("black") => PieceColour::Black,
+                _ => panic!("--colour requires white or black"),
+            },
+            None => PieceColour::White,
+        };
+        let game = Game::new(starting_position.clone());
+        let peer = NetworkPeer::host(&address, colour, game).unwrap_or_else(|err| panic!("{err}"));
+        println!("Waiting for the other player on {address}");
+        peer
+    } else if let Some(address) = address("--connect") {
+        let peer = NetworkPeer::connect(&address).unwrap_or_else(|err| panic!("{err}"));
+        if let Some(colour) = peer.colour() {
+            println!("Playing {colour} against {address}");
+        }
+        peer
+    } else if let Some(address) = address("--watch") {
+        let peer = NetworkPeer::watch(&address).unwrap_or_else(|err| panic!("{err}"));
+        println!("Watching the game hosted at {address}");
+        peer
+    } else {
+        return Network::default();
+    };
+    Network(Some(Mutex::new(peer)))
+}
+
 fn setup(mut commands: Commands) {
     commands
         .spawn((
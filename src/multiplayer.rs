@@ -0,0 +1,145 @@
+use std::sync::Mutex;
+
+use bevy::prelude::*;
+use chess::{NetworkEvent, NetworkPeer, NetworkRole, Session};
+
+use crate::board::{
+    play_move, show_game_action, ChessGame, GameActionTaken, GameStatus, MoveMadeEvent,
+    PlayerNames, PlayerTurn, Pockets, Taken,
+};
+use crate::chess_clock::ChessClock;
+use crate::persistence::LoadedSession;
+use crate::pieces::Piece;
+use crate::states::AppState;
+
+pub struct MultiplayerPlugin;
+
+impl Plugin for MultiplayerPlugin {
+    fn build(&self, app: &mut App) {
+        app // new line
+            .init_resource::<Network>()
+            .add_system(
+                sync_network.run_if(
+                    in_state(AppState::InGame)
+                        .or_else(in_state(AppState::PromotionPending))
+                        .or_else(in_state(AppState::GameOver)),
+                ),
+            );
+    }
+}
+
+/// The connection to the other player in a game played over the network, or to the host when
+/// watching
+///
+/// The other player's side is `Player::Remote`, so only this side's pieces can be picked. Both
+/// sides are remote for a spectator, so nothing can be picked and the board only follows the
+/// host's game
+#[derive(Resource, Default)]
+pub struct Network(pub Option<Mutex<NetworkPeer>>);
+
+/// Keeps the game on the board in step with the other player's
+///
+/// Moves picked on the board are sent as they are made, and the other player's moves are made on
+/// the board as if they had been picked. Resigning, offering or answering a draw and claiming one
+/// are sent once carried out, and the other player's are taken from the connection's game once it
+/// has the same moves as the board. Anything else that changes the game, such as taking back
+/// moves or starting a new game, is sent to the client when it happens on the host, while on the
+/// client the host's game is loaded again in its place
+#[allow(clippy::too_many_arguments)]
+fn sync_network(
+    mut commands: Commands,
+    mut network: ResMut<Network>,
+    state: Res<State<AppState>>,
+    mut game: ResMut<ChessGame>,
+    mut actions_taken: EventReader<GameActionTaken>,
+    mut turn: ResMut<PlayerTurn>,
+    mut game_status: ResMut<GameStatus>,
+    mut next_state: ResMut<NextState<AppState>>,
+    names: Res<PlayerNames>,
+    clock: Res<ChessClock>,
+    pieces: Query<(Entity, &Piece), Without<Taken>>,
+    pockets: Res<Pockets>,
+    mut loaded_session: ResMut<LoadedSession>,
+    mut move_made_event: EventWriter<MoveMadeEvent>,
+    // the number of moves on the board when the other player's next move was played, as the
+    // game only records it once the move has been handled
+    mut played_at: Local<Option<usize>>,
+) {
+    let Some(peer) = &mut network.0 else {
+        return;
+    };
+    let peer = peer.get_mut().unwrap();
+
+    for &GameActionTaken(action) in actions_taken.iter() {
+        if let Err(err) = peer.act(action) {
+            println!("Unable to send the action to the other player: {err}");
+        }
+    }
+
+    for event in peer.poll() {
+        match event {
+            NetworkEvent::Connected => println!("The other player has connected"),
+            NetworkEvent::Disconnected => println!("Lost the connection to the other player"),
+            NetworkEvent::OpponentMoved(_) | NetworkEvent::OpponentActed(_) => {}
+            NetworkEvent::Resynced => println!("Loaded the host's game"),
+            NetworkEvent::SpectatorJoined => {
+                println!("A spectator has joined, {} watching", peer.spectators())
+            }
+            NetworkEvent::SpectatorLeft => {
+                println!("A spectator has left, {} watching", peer.spectators())
+            }
+            NetworkEvent::Error(err) => println!("Network error: {err}"),
+        }
+    }
+
+    let ours: Vec<_> = game.0.moves().copied().collect();
+    let theirs: Vec<_> = peer.game().moves().copied().collect();
+    let same_start =
+        game.0.starting_position().to_fen() == peer.game().starting_position().to_fen();
+    if same_start && ours == theirs {
+        *played_at = None;
+        // this side's own actions have already been sent, so any other difference is the other
+        // player's, unless the game has ended here such as on time
+        let in_step = game.0.status() == peer.game().status()
+            && game.0.draw_offer() == peer.game().draw_offer();
+        if !in_step && !game.0.status().is_over() {
+            game.0 = peer.game().clone();
+            show_game_action(&game.0, &mut turn, &mut game_status, &mut next_state);
+        }
+        return;
+    }
+
+    let moved_here = same_start
+        && ours.len() == theirs.len() + 1
+        && ours.starts_with(&theirs)
+        && Some(peer.game().position().side_to_move()) == peer.colour();
+    let moved_there = same_start && theirs.len() > ours.len() && theirs.starts_with(&ours);
+    if moved_here {
+        if let Err(err) = peer.play(ours[theirs.len()]) {
+            println!("Unable to send the move: {err}");
+        }
+    } else if moved_there {
+        if state.0 == AppState::InGame && *played_at != Some(ours.len()) {
+            play_move(
+                &mut commands,
+                game.0.position(),
+                &pieces,
+                &pockets,
+                &theirs[ours.len()],
+                &mut move_made_event,
+            );
+            *played_at = Some(ours.len());
+        }
+    } else if peer.role() == NetworkRole::Host {
+        if let Err(err) = peer.set_game(game.0.clone()) {
+            println!("Unable to send the game: {err}");
+        }
+    } else if loaded_session.0.is_none() {
+        loaded_session.0 = Some(Session {
+            game: peer.game().clone(),
+            white: names.white.clone(),
+            black: names.black.clone(),
+            clock: clock.0.clone(),
+        });
+    }
+}
@@ -0,0 +1,933 @@
+use std::collections::hash_map::RandomState;
+use std::fmt::{Display, Formatter};
+use std::hash::{BuildHasher, Hasher};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::variant::{variant_by_name, Standard, Variant};
+use crate::{parse_position_command, position_command, ChessMove, Game, PieceColour};
+use crate::{DrawReason, PlayerAction, Status};
+
+/// The newest version of the protocol spoken by `NetworkPeer`
+pub const PROTOCOL_VERSION: u32 = 2;
+/// The oldest version of the protocol still understood, as version 1 had no way to resign or
+/// offer a draw
+const MIN_PROTOCOL_VERSION: u32 = 2;
+/// The name sent with every `hello`, so that anything else connecting is turned away
+const PROTOCOL_NAME: &str = "bevy-chess";
+/// How long the host has to answer when connecting
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// How often a client tries to connect again after losing the host
+const RECONNECT_INTERVAL: Duration = Duration::from_millis(500);
+/// How often the host checks for new connections
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(20);
+
+/// The part a side takes in a game played over the network
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub enum NetworkRole {
+    /// Listens for the other player and spectators, and has the game that counts
+    Host,
+    /// Connects to the host to play the other side
+    Player,
+    /// Connects to the host to watch, without being able to move
+    Spectator,
+}
+
+impl NetworkRole {
+    fn name(&self) -> &'static str {
+        match self {
+            NetworkRole::Host => "host",
+            NetworkRole::Player => "player",
+            NetworkRole::Spectator => "spectator",
+        }
+    }
+}
+
+/// Something that happened on the connection, reported by `NetworkPeer::poll`
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub enum NetworkEvent {
+    /// The handshake with the other player has finished, for the first time or after
+    /// reconnecting
+    Connected,
+    /// The connection to the other player was lost
+    Disconnected,
+    /// The other player moved, or for a spectator either player, and the move has been made in
+    /// the game
+    OpponentMoved(ChessMove),
+    /// The other player resigned, offered, accepted or declined a draw or claimed one, or for a
+    /// spectator either player did, and it has been carried out in the game
+    OpponentActed(PlayerAction),
+    /// The game was replaced by the host's copy, after the two got out of step or the host
+    /// changed it
+    Resynced,
+    /// Someone started watching the host's game
+    SpectatorJoined,
+    /// Someone stopped watching the host's game
+    SpectatorLeft,
+    /// A message from the other side was refused or could not be understood
+    Error(String),
+}
+
+impl Display for NetworkEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NetworkEvent::Connected => write!(f, "connected"),
+            NetworkEvent::Disconnected => write!(f, "disconnected"),
+            NetworkEvent::OpponentMoved(chess_move) => write!(f, "opponent {chess_move}"),
+            NetworkEvent::OpponentActed(action) => match action_parts(*action) {
+                (word, Some(colour)) => write!(f, "opponent {word} {}", colour_name(colour)),
+                (word, None) => write!(f, "opponent {word}"),
+            },
+            NetworkEvent::Resynced => write!(f, "resynced"),
+            NetworkEvent::SpectatorJoined => write!(f, "spectator joined"),
+            NetworkEvent::SpectatorLeft => write!(f, "spectator left"),
+            NetworkEvent::Error(err) => write!(f, "error {err}"),
+        }
+    }
+}
+
+/// What the reader and listener threads pass back to the peer, tagged with the connection they
+/// came from so anything from a closed connection can be ignored
+enum Incoming {
+    Connection(u64, TcpStream),
+    Line(u64, String),
+    Closed(u64),
+}
+
+/// Who is at the other end of a connection, known once the handshake has finished
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Seat {
+    Unknown,
+    Player,
+    Spectator,
+}
+
+struct Connection {
+    id: u64,
+    stream: TcpStream,
+    seat: Seat,
+}
+
+/// One side of a game played over TCP: the host, the player that connected to it or a spectator
+///
+/// Each side keeps its own copy of the game and only makes moves for its own colour with `play`,
+/// while `poll` makes the moves received from the other side. The protocol is one message to a
+/// line:
+///
+/// ```text
+/// hello bevy-chess <oldest version> <newest version> player [<token>]|spectator
+/// welcome <version> <colour of the player> <token>|spectator
+/// variant kingofthehill
+/// position startpos moves e2e4 e7e5
+/// move <number> <move> <position key>
+/// resign|offer|accept|decline <number of moves made> <colour of the player acting>
+/// claim <number of moves made>
+/// resync
+/// error <reason>
+/// ```
+///
+/// Whoever connects starts with `hello` and the host answers with `welcome` and its copy of the
+/// game, as a UCI `position` command. The player is given a token in its `welcome`, which it must
+/// give in its `hello` to take its seat again after losing the connection. A game of a variant other than standard chess is preceded by
+/// a `variant` message giving its UCI name. Every move is numbered and carries the key of the position
+/// it leads to, so a move that does not follow on from the receiver's game, or that leads
+/// somewhere else, is caught. Resigning, offering a draw, answering an offer and claiming a draw
+/// carry the number of moves made when they were taken, and are refused in the same way when the
+/// game has moved on. The host's game is the one that counts: when the two get out of step the
+/// host sends its game again, followed by the offer or ending the game has, and the others ask for
+/// it with `resync`.
+///
+/// The host passes every move on to its spectators, who can come and go at any time without
+/// the players noticing. A player or spectator that loses the host keeps trying to connect again,
+/// and the host lets the player with the right token take the place of the old connection, while
+/// anyone else asking to play is turned away. Moves made in the meantime are caught up once the
+/// handshake is repeated
+pub struct NetworkPeer {
+    role: NetworkRole,
+    /// The colour this side plays, which spectators do not have
+    colour: Option<PieceColour>,
+    game: Game,
+    /// The variant named by the host for the game it is about to send
+    pending_variant: Option<&'static dyn Variant>,
+    /// The token the host gave the player, which is needed to take the player's seat again
+    token: Option<String>,
+    /// Where a player or spectator connects to, and connects to again if the connection drops
+    address: Option<SocketAddr>,
+    /// Where a host is listening
+    local_addr: Option<SocketAddr>,
+    version: u32,
+    /// The host's connections to the other player and to spectators, or the one connection
+    /// anyone else has to the host
+    connections: Vec<Connection>,
+    incoming: Receiver<Incoming>,
+    sender: Sender<Incoming>,
+    next_id: Arc<AtomicU64>,
+    /// Set when the peer is dropped, to stop the threads listening or reconnecting
+    closed: Arc<AtomicBool>,
+}
+
+impl NetworkPeer {
+    fn new(role: NetworkRole, colour: Option<PieceColour>, game: Game) -> NetworkPeer {
+        let (sender, incoming) = mpsc::channel();
+        NetworkPeer {
+            role,
+            colour,
+            game,
+            pending_variant: None,
+            token: None,
+            address: None,
+            local_addr: None,
+            version: PROTOCOL_VERSION,
+            connections: Vec::new(),
+            incoming,
+            sender,
+            next_id: Arc::new(AtomicU64::new(0)),
+            closed: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Listens on `address` for the other player and spectators, playing `colour` in `game`
+    pub fn host(
+        address: impl ToSocketAddrs,
+        colour: PieceColour,
+        game: Game,
+    ) -> Result<NetworkPeer, String> {
+        let listener =
+            TcpListener::bind(address).map_err(|err| format!("unable to listen: {err}"))?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|err| format!("unable to listen: {err}"))?;
+
+        let mut peer = NetworkPeer::new(NetworkRole::Host, Some(colour), game);
+        peer.local_addr = listener.local_addr().ok();
+
+        let sender = peer.sender.clone();
+        let next_id = peer.next_id.clone();
+        let closed = peer.closed.clone();
+        thread::spawn(move || {
+            while !closed.load(Ordering::Relaxed) {
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        if stream.set_nonblocking(false).is_ok() {
+                            start_reading(&sender, &next_id, stream);
+                        }
+                    }
+                    // nobody is waiting to connect
+                    Err(_) => thread::sleep(ACCEPT_INTERVAL),
+                }
+            }
+        });
+        Ok(peer)
+    }
+
+    /// Connects to the host at `address` to play, waiting for the handshake and taking the
+    /// colour and the game the host gives
+    pub fn connect(address: impl ToSocketAddrs) -> Result<NetworkPeer, String> {
+        NetworkPeer::join(address, NetworkRole::Player)
+    }
+
+    /// Connects to the host at `address` to watch its game, waiting for the handshake
+    pub fn watch(address: impl ToSocketAddrs) -> Result<NetworkPeer, String> {
+        NetworkPeer::join(address, NetworkRole::Spectator)
+    }
+
+    fn join(address: impl ToSocketAddrs, role: NetworkRole) -> Result<NetworkPeer, String> {
+        let address = address
+            .to_socket_addrs()
+            .ok()
+            .and_then(|mut addresses| addresses.next())
+            .ok_or_else(|| "unable to find the host".to_string())?;
+        let stream = TcpStream::connect_timeout(&address, HANDSHAKE_TIMEOUT)
+            .map_err(|err| format!("unable to connect to {address}: {err}"))?;
+
+        let mut peer = NetworkPeer::new(role, None, Game::default());
+        peer.address = Some(address);
+        start_reading(&peer.sender, &peer.next_id, stream);
+
+        let deadline = Instant::now() + HANDSHAKE_TIMEOUT;
+        while !peer.is_connected() {
+            let incoming = peer
+                .incoming
+                .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                .map_err(|_| "the host did not answer in time".to_string())?;
+            if let Some(NetworkEvent::Error(err)) = peer.handle(incoming) {
+                return Err(format!("the host refused: {err}"));
+            }
+            if peer.connections.is_empty() {
+                return Err("the host closed the connection".to_string());
+            }
+        }
+        Ok(peer)
+    }
+
+    pub fn role(&self) -> NetworkRole {
+        self.role
+    }
+
+    /// The colour this side plays, or `None` for a spectator
+    pub fn colour(&self) -> Option<PieceColour> {
+        self.colour
+    }
+
+    /// This side's copy of the game
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// The version of the protocol agreed in the last handshake
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// The address a host is listening on, which is useful after binding to port 0
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.local_addr
+    }
+
+    /// Returns true while the handshake with the other player has finished, or for a spectator
+    /// with the host
+    pub fn is_connected(&self) -> bool {
+        self.connections.iter().any(|connection| match self.role {
+            NetworkRole::Host => connection.seat == Seat::Player,
+            NetworkRole::Player | NetworkRole::Spectator => connection.seat != Seat::Unknown,
+        })
+    }
+
+    /// The number of spectators watching the host's game
+    pub fn spectators(&self) -> usize {
+        self.connections
+            .iter()
+            .filter(|connection| connection.seat == Seat::Spectator)
+            .count()
+    }
+
+    /// Makes a move for this side and sends it to the other player, and from the host to its
+    /// spectators
+    ///
+    /// A move made while disconnected is kept, and sent once the other player is back
+    pub fn play(&mut self, chess_move: ChessMove) -> Result<(), String> {
+        if self.role == NetworkRole::Spectator {
+            return Err("spectators cannot move".to_string());
+        }
+        if Some(self.game.position().side_to_move()) != self.colour {
+            return Err("it is not your turn".to_string());
+        }
+        self.game.make_move(chess_move)?;
+        let line = self.last_move_line();
+        self.broadcast(&line, |_| true);
+        Ok(())
+    }
+
+    /// Resigns, offers or answers a draw, or claims one, for this side and sends it to the other
+    /// player, and from the host to its spectators
+    ///
+    /// Unlike a move this is refused while the other player is disconnected, as it would be lost
+    /// if the host sent its game again on reconnecting
+    pub fn act(&mut self, action: PlayerAction) -> Result<(), String> {
+        if self.role == NetworkRole::Spectator {
+            return Err("spectators can only watch".to_string());
+        }
+        let acting = action_parts(action)
+            .1
+            .unwrap_or_else(|| self.game.position().side_to_move());
+        if Some(acting) != self.colour {
+            return Err(format!("{acting} is played by the other player"));
+        }
+        if !self.is_connected() {
+            return Err("the other player is not connected".to_string());
+        }
+
+        let number = self.game.moves().count();
+        self.game.act(action)?;
+        self.broadcast(&action_line(number, action), |_| true);
+        Ok(())
+    }
+
+    /// Replaces the game, e.g. after taking back moves or starting again, and sends it to the
+    /// other player and spectators
+    ///
+    /// Only the host can do this, as everyone else always follows the host's game
+    pub fn set_game(&mut self, game: Game) -> Result<(), String> {
+        if self.role != NetworkRole::Host {
+            return Err("only the host can replace the game".to_string());
+        }
+        self.game = game;
+        let line = self.game_line();
+        self.broadcast(&line, |_| true);
+        Ok(())
+    }
+
+    /// Handles everything received since the last call without waiting, returning what
+    /// happened
+    pub fn poll(&mut self) -> Vec<NetworkEvent> {
+        let mut events = Vec::new();
+        while let Ok(incoming) = self.incoming.try_recv() {
+            events.extend(self.handle(incoming));
+        }
+        events
+    }
+
+    fn handle(&mut self, incoming: Incoming) -> Option<NetworkEvent> {
+        match incoming {
+            Incoming::Connection(id, stream) => {
+                if self.role != NetworkRole::Host {
+                    self.close_all();
+                }
+                self.connections.push(Connection {
+                    id,
+                    stream,
+                    seat: Seat::Unknown,
+                });
+                if self.role != NetworkRole::Host {
+                    let mut hello = format!(
+                        "hello {PROTOCOL_NAME} {MIN_PROTOCOL_VERSION} {PROTOCOL_VERSION} {}",
+                        self.role.name()
+                    );
+                    if let Some(token) = &self.token {
+                        hello.push_str(&format!(" {token}"));
+                    }
+                    self.send_to(id, &hello);
+                }
+                None
+            }
+            Incoming::Closed(id) => {
+                let index = self
+                    .connections
+                    .iter()
+                    .position(|connection| connection.id == id)?;
+                let seat = self.connections.remove(index).seat;
+                if self.role != NetworkRole::Host {
+                    self.reconnect();
+                }
+                match (self.role, seat) {
+                    (_, Seat::Unknown) => None,
+                    (NetworkRole::Host, Seat::Spectator) => Some(NetworkEvent::SpectatorLeft),
+                    _ => Some(NetworkEvent::Disconnected),
+                }
+            }
+            Incoming::Line(id, line) => {
+                let seat = self.seat(id)?;
+                self.handle_line(id, seat, &line)
+            }
+        }
+    }
+
+    fn seat(&self, id: u64) -> Option<Seat> {
+        self.connections
+            .iter()
+            .find(|connection| connection.id == id)
+            .map(|connection| connection.seat)
+    }
+
+    fn set_seat(&mut self, id: u64, seat: Seat) {
+        if let Some(connection) = self
+            .connections
+            .iter_mut()
+            .find(|connection| connection.id == id)
+        {
+            connection.seat = seat;
+        }
+    }
+
+    fn handle_line(&mut self, id: u64, seat: Seat, line: &str) -> Option<NetworkEvent> {
+        let mut words = line.split_whitespace();
+        let host = self.role == NetworkRole::Host;
+        match (words.next(), seat) {
+            (Some("hello"), Seat::Unknown) if host => self.handle_hello(id, words.collect()),
+            (Some("welcome"), Seat::Unknown) if !host => self.handle_welcome(id, words.collect()),
+            (Some("variant"), _) if !host => match words.next().and_then(variant_by_name) {
+                Some(variant) => {
+                    self.pending_variant = Some(variant);
+                    None
+                }
+                None => self.refuse(id, format!("unknown variant in '{line}'")),
+            },
+            (Some("position"), _) if !host => self.handle_position(id, seat, line),
+            (Some("move"), Seat::Player) => self.handle_move(id, words.collect()),
+            (Some("move"), Seat::Spectator) if !host => self.handle_move(id, words.collect()),
+            (Some(word), Seat::Player | Seat::Spectator)
+                if ACTION_WORDS.contains(&word) && (seat == Seat::Player || !host) =>
+            {
+                self.handle_action(id, word, words.collect())
+            }
+            (Some("resync"), Seat::Player | Seat::Spectator) if host => {
+                let line = self.game_line();
+                self.send_to(id, &line);
+                None
+            }
+            (Some("error"), _) => Some(NetworkEvent::Error(words.collect::<Vec<_>>().join(" "))),
+            // blank lines are ignored
+            (None, _) => None,
+            (Some(word), _) => {
+                let err = format!("unexpected message '{word}'");
+                self.send_to(id, &format!("error {err}"));
+                Some(NetworkEvent::Error(err))
+            }
+        }
+    }
+
+    /// Agrees the newest version both sides understand, and sends the player its colour and token
+    /// or the spectator its welcome, followed by the game
+    ///
+    /// Once a player has been welcomed, only a player giving its token can take its seat, which
+    /// is how players reconnect
+    fn handle_hello(&mut self, id: u64, words: Vec<&str>) -> Option<NetworkEvent> {
+        let hello = match words[..] {
+            [PROTOCOL_NAME, oldest, newest, seat, ref token @ ..] if token.len() <= 1 => {
+                let seat = match (seat, token) {
+                    ("player", _) => Some(Seat::Player),
+                    ("spectator", []) => Some(Seat::Spectator),
+                    _ => None,
+                };
+                oldest
+                    .parse::<u32>()
+                    .ok()
+                    .zip(newest.parse::<u32>().ok())
+                    .zip(seat.map(|seat| (seat, token.first().copied())))
+            }
+            _ => None,
+        };
+        let Some(((oldest, newest), (seat, token))) = hello else {
+            return self.refuse(id, "invalid hello".to_string());
+        };
+        if newest < MIN_PROTOCOL_VERSION || oldest > PROTOCOL_VERSION {
+            return self.refuse(
+                id,
+                format!(
+                    "no protocol version in common, the host speaks versions \
+                     {MIN_PROTOCOL_VERSION} to {PROTOCOL_VERSION}"
+                ),
+            );
+        }
+
+        let version = newest.min(PROTOCOL_VERSION);
+        let welcome = match (seat, self.colour) {
+            (Seat::Player, Some(colour)) => {
+                if self.token.is_some() && token != self.token.as_deref() {
+                    return self.refuse(id, "the game already has another player".to_string());
+                }
+                let token = self.token.get_or_insert_with(new_token).clone();
+                self.version = version;
+                self.close_where(|connection| connection.seat == Seat::Player);
+                format!(
+                    "welcome {version} {} {token}",
+                    colour_name(colour.opponent())
+                )
+            }
+            _ => format!("welcome {version} spectator"),
+        };
+        self.send_to(id, &welcome);
+        let line = self.game_line();
+        self.send_to(id, &line);
+        self.set_seat(id, seat);
+
+        if seat == Seat::Player {
+            Some(NetworkEvent::Connected)
+        } else {
+            Some(NetworkEvent::SpectatorJoined)
+        }
+    }
+
+    fn handle_welcome(&mut self, id: u64, words: Vec<&str>) -> Option<NetworkEvent> {
+        let [version, seat, ref token @ ..] = words[..] else {
+            return self.refuse(id, "invalid welcome".to_string());
+        };
+        let Ok(version) = version.parse::<u32>() else {
+            return self.refuse(id, "invalid welcome".to_string());
+        };
+        if !(MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION).contains(&version) {
+            return self.refuse(id, format!("unsupported protocol version {version}"));
+        }
+        let (colour, token) = match (self.role, parse_colour(seat), token) {
+            (NetworkRole::Spectator, None, []) if seat == "spectator" => (None, None),
+            (NetworkRole::Player, Some(colour), [token]) => (Some(colour), Some(token)),
+            _ => return self.refuse(id, "invalid welcome".to_string()),
+        };
+
+        self.version = version;
+        self.colour = colour;
+        self.token = token.map(|token| token.to_string());
+        None
+    }
+
+    /// Takes the host's game, unless the only difference is a move this player made while
+    /// disconnected, which is sent again instead
+    ///
+    /// The game is played under the variant given just before it, or standard chess if none was
+    fn handle_position(&mut self, id: u64, seat: Seat, line: &str) -> Option<NetworkEvent> {
+        let variant = self.pending_variant.take().unwrap_or(&Standard);
+        let game = match parse_position_command(line, variant) {
+            Ok(game) => game,
+            Err(err) => return self.refuse(id, format!("invalid game: {err}")),
+        };
+        let ready = seat != Seat::Unknown;
+        if !ready {
+            let seat = match self.role {
+                NetworkRole::Spectator => Seat::Spectator,
+                _ => Seat::Player,
+            };
+            self.set_seat(id, seat);
+        }
+
+        let ours: Vec<_> = self.game.moves().copied().collect();
+        let theirs: Vec<_> = game.moves().copied().collect();
+        let same_start = self.game.starting_position().to_fen()
+            == game.starting_position().to_fen()
+            && self.game.position().variant().uci_name() == variant.uci_name();
+        let in_step = same_start && ours == theirs;
+        let one_move_ahead = same_start
+            && ours.len() == theirs.len() + 1
+            && ours.starts_with(&theirs)
+            && Some(game.position().side_to_move()) == self.colour;
+        if one_move_ahead {
+            let line = self.last_move_line();
+            self.send_to(id, &line);
+        } else {
+            // even with the same moves the host's game is taken, as any offer or ending it has
+            // follows to be carried out again
+            self.game = game;
+            if ready && !in_step {
+                return Some(NetworkEvent::Resynced);
+            }
+        }
+
+        if ready {
+            None
+        } else {
+            Some(NetworkEvent::Connected)
+        }
+    }
+
+    /// Makes the other player's move, as long as it follows on from this side's game and reaches
+    /// the same position, passing it on to spectators if this is the host
+    fn handle_move(&mut self, id: u64, words: Vec<&str>) -> Option<NetworkEvent> {
+        let [number, uci, key] = words[..] else {
+            return Some(NetworkEvent::Error("invalid move message".to_string()));
+        };
+        let Ok(number) = number.parse::<usize>() else {
+            return Some(NetworkEvent::Error(format!(
+                "invalid move number '{number}'"
+            )));
+        };
+
+        // a move sent again after reconnecting may already have arrived
+        if number >= 1
+            && self
+                .game
+                .moves()
+                .nth(number - 1)
+                .is_some_and(|chess_move| chess_move.to_string() == uci)
+        {
+            return None;
+        }
+
+        match self.make_opponent_move(number, uci, key) {
+            Ok(chess_move) => {
+                if self.role == NetworkRole::Host {
+                    let line = self.last_move_line();
+                    self.broadcast(&line, |connection| connection.seat == Seat::Spectator);
+                }
+                Some(NetworkEvent::OpponentMoved(chess_move))
+            }
+            Err(err) => {
+                let line = if self.role == NetworkRole::Host {
+                    self.game_line()
+                } else {
+                    "resync".to_string()
+                };
+                self.send_to(id, &line);
+                Some(NetworkEvent::Error(format!(
+                    "out of step with the other side: {err}"
+                )))
+            }
+        }
+    }
+
+    fn make_opponent_move(
+        &mut self,
+        number: usize,
+        uci: &str,
+        key: &str,
+    ) -> Result<ChessMove, String> {
+        let expected = self.game.moves().count() + 1;
+        if number != expected {
+            return Err(format!("received move {number} instead of move {expected}"));
+        }
+        if Some(self.game.position().side_to_move()) == self.colour {
+            return Err(format!("received {uci} on this side's turn"));
+        }
+
+        let chess_move = self.game.position().parse_uci(uci)?;
+        self.game.make_move(chess_move)?;
+        let reached = format!("{:016x}", self.game.position().key());
+        if reached != key {
+            self.game.unmake_move();
+            return Err(format!("{uci} leads to a different position"));
+        }
+        Ok(chess_move)
+    }
+
+    /// Carries out the other player's action, as long as it was taken after as many moves as this
+    /// side's game has, passing it on to spectators if this is the host
+    fn handle_action(&mut self, id: u64, word: &str, words: Vec<&str>) -> Option<NetworkEvent> {
+        let parsed = match words[..] {
+            [number, colour] => number
+                .parse::<usize>()
+                .ok()
+                .zip(parse_action(word, parse_colour(colour))),
+            [number] => number.parse().ok().zip(parse_action(word, None)),
+            _ => None,
+        };
+        let Some((number, action)) = parsed else {
+            return Some(NetworkEvent::Error(format!("invalid {word} message")));
+        };
+
+        match self.take_opponent_action(number, action) {
+            Ok(()) => {
+                if self.role == NetworkRole::Host {
+                    let line = action_line(number, action);
+                    self.broadcast(&line, |connection| connection.seat == Seat::Spectator);
+                }
+                Some(NetworkEvent::OpponentActed(action))
+            }
+            Err(err) => {
+                let line = if self.role == NetworkRole::Host {
+                    self.game_line()
+                } else {
+                    "resync".to_string()
+                };
+                self.send_to(id, &line);
+                Some(NetworkEvent::Error(format!(
+                    "out of step with the other side: {err}"
+                )))
+            }
+        }
+    }
+
+    fn take_opponent_action(&mut self, number: usize, action: PlayerAction) -> Result<(), String> {
+        let (word, colour) = action_parts(action);
+        let expected = self.game.moves().count();
+        if number != expected {
+            return Err(format!(
+                "received {word} after {number} moves instead of {expected}"
+            ));
+        }
+        // the host also sends this side's own offer or ending when sending its game again
+        let acting = colour.unwrap_or_else(|| self.game.position().side_to_move());
+        if self.role == NetworkRole::Host && Some(acting) == self.colour {
+            return Err(format!("received {word} for this side"));
+        }
+        self.game.act(action)
+    }
+
+    /// Sends `error` to the other side and drops the connection
+    fn refuse(&mut self, id: u64, err: String) -> Option<NetworkEvent> {
+        self.send_to(id, &format!("error {err}"));
+        self.close_where(|connection| connection.id == id);
+        Some(NetworkEvent::Error(err))
+    }
+
+    fn last_move_line(&self) -> String {
+        match self.game.last_move() {
+            Some(chess_move) => format!(
+                "move {} {chess_move} {:016x}",
+                self.game.moves().count(),
+                self.game.position().key()
+            ),
+            None => String::new(),
+        }
+    }
+
+    /// The `position` message for the game, after a `variant` message on its own line when the
+    /// game is not standard chess, and followed by the messages that make any offer of a draw or
+    /// ending other than by moving again
+    ///
+    /// Running out of time is left to each side's own clock
+    fn game_line(&self) -> String {
+        let moves: Vec<_> = self.game.moves().copied().collect();
+        let mut lines = Vec::new();
+        let variant = self.game.starting_position().variant();
+        if !variant.is_standard() {
+            lines.push(format!("variant {}", variant.uci_name()));
+        }
+        lines.push(position_command(self.game.starting_position(), &moves));
+
+        let colour = self.game.position().side_to_move();
+        let actions = match (self.game.status(), self.game.draw_offer()) {
+            (Status::Resigned(colour), _) => vec![PlayerAction::Resign(colour)],
+            (Status::Draw(DrawReason::Agreement), _) => vec![
+                PlayerAction::OfferDraw(colour),
+                PlayerAction::AcceptDraw(colour.opponent()),
+            ],
+            (Status::Draw(DrawReason::ThreefoldRepetition | DrawReason::FiftyMoveRule), _) => {
+                vec![PlayerAction::ClaimDraw]
+            }
+            (_, Some(offered_by)) => vec![PlayerAction::OfferDraw(offered_by)],
+            _ => Vec::new(),
+        };
+        lines.extend(
+            actions
+                .into_iter()
+                .map(|action| action_line(moves.len(), action)),
+        );
+        lines.join("\n")
+    }
+
+    /// Sends a line on one connection
+    ///
+    /// A failed write is not reported here, as the reader thread finds the connection closed
+    fn send_to(&mut self, id: u64, line: &str) {
+        if let Some(connection) = self
+            .connections
+            .iter_mut()
+            .find(|connection| connection.id == id)
+        {
+            let _ = writeln!(connection.stream, "{line}").and_then(|_| connection.stream.flush());
+        }
+    }
+
+    /// Sends a line on every connection that has finished its handshake and matches `filter`
+    fn broadcast(&mut self, line: &str, filter: impl Fn(&Connection) -> bool) {
+        for connection in &mut self.connections {
+            if connection.seat != Seat::Unknown && filter(connection) {
+                let _ =
+                    writeln!(connection.stream, "{line}").and_then(|_| connection.stream.flush());
+            }
+        }
+    }
+
+    /// Drops the connections matching `filter`, whose reader threads then find them closed
+    fn close_where(&mut self, filter: impl Fn(&Connection) -> bool) {
+        self.connections.retain(|connection| {
+            if filter(connection) {
+                let _ = connection.stream.shutdown(Shutdown::Both);
+                false
+            } else {
+                true
+            }
+        });
+    }
+
+    fn close_all(&mut self) {
+        self.close_where(|_| true);
+    }
+
+    /// Keeps trying to connect to the host again on another thread
+    fn reconnect(&self) {
+        let Some(address) = self.address else {
+            return;
+        };
+        let sender = self.sender.clone();
+        let next_id = self.next_id.clone();
+        let closed = self.closed.clone();
+        thread::spawn(move || {
+            while !closed.load(Ordering::Relaxed) {
+                thread::sleep(RECONNECT_INTERVAL);
+                if let Ok(stream) = TcpStream::connect_timeout(&address, RECONNECT_INTERVAL) {
+                    start_reading(&sender, &next_id, stream);
+                    return;
+                }
+            }
+        });
+    }
+}
+
+impl Drop for NetworkPeer {
+    fn drop(&mut self) {
+        self.closed.store(true, Ordering::Relaxed);
+        self.close_all();
+    }
+}
+
+/// Hands a new connection to the peer, then reads it a line at a time on another thread
+fn start_reading(sender: &Sender<Incoming>, next_id: &AtomicU64, stream: TcpStream) {
+    let id = next_id.fetch_add(1, Ordering::Relaxed);
+    let Ok(reader) = stream.try_clone() else {
+        return;
+    };
+    if sender.send(Incoming::Connection(id, stream)).is_err() {
+        return;
+    }
+
+    let sender = sender.clone();
+    thread::spawn(move || {
+        for line in BufReader::new(reader).lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if sender.send(Incoming::Line(id, line)).is_err() {
+                return;
+            }
+        }
+        let _ = sender.send(Incoming::Closed(id));
+    });
+}
+
+/// A token for the player to take its seat again with, which nobody else can guess
+fn new_token() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    // each `RandomState` is seeded with random keys, and the time makes sure two never match
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(nanos);
+    let mut second = RandomState::new().build_hasher();
+    second.write_u64(hasher.finish());
+    format!("{:016x}{:016x}", hasher.finish(), second.finish())
+}
+
+/// The messages for resigning, offering a draw, answering an offer and claiming a draw
+const ACTION_WORDS: [&str; 5] = ["resign", "offer", "accept", "decline", "claim"];
+
+/// The word an action is sent as, with the colour of the player taking it unless that is the side
+/// to move, as for a claim
+fn action_parts(action: PlayerAction) -> (&'static str, Option<PieceColour>) {
+    match action {
+        PlayerAction::Resign(colour) => ("resign", Some(colour)),
+        PlayerAction::OfferDraw(colour) => ("offer", Some(colour)),
+        PlayerAction::AcceptDraw(colour) => ("accept", Some(colour)),
+        PlayerAction::DeclineDraw(colour) => ("decline", Some(colour)),
+        PlayerAction::ClaimDraw => ("claim", None),
+    }
+}
+
+fn parse_action(word: &str, colour: Option<PieceColour>) -> Option<PlayerAction> {
+    match (word, colour) {
+        ("resign", Some(colour)) => Some(PlayerAction::Resign(colour)),
+        ("offer", Some(colour)) => Some(PlayerAction::OfferDraw(colour)),
+        ("accept", Some(colour)) => Some(PlayerAction::AcceptDraw(colour)),
+        ("decline", Some(colour)) => Some(PlayerAction::DeclineDraw(colour)),
+        ("claim", None) => Some(PlayerAction::ClaimDraw),
+        _ => None,
+    }
+}
+
+fn action_line(number: usize, action: PlayerAction) -> String {
+    match action_parts(action) {
+        (word, Some(colour)) => format!("{word} {number} {}", colour_name(colour)),
+        (word, None) => format!("{word} {number}"),
+    }
+}
+
+fn colour_name(colour: PieceColour) -> &'static str {
+    match colour {
+        PieceColour::White => "white",
+        PieceColour::Black => "black",
+    }
+}
+
+fn parse_colour(colour: &str) -> Option<PieceColour> {
+    match colour {
+        "white" => Some(PieceColour::White),
+        "black" => Some(PieceColour::Black),
+        _ => None,
+    }
+}
@@ -0,0 +1,41 @@
+use crate::{ChessMove, Position};
+
+/// Counts the number of leaf nodes in the tree of legal moves `depth` moves deep from `position`
+///
+/// Comparing the result against published counts for known positions is the standard way of
+/// verifying a move generator. `position` is restored before returning
+pub fn perft(position: &mut Position, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+
+    let moves = position.legal_moves();
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+
+    moves
+        .into_iter()
+        .map(|chess_move| {
+            let undo = position.make_move(chess_move);
+            let nodes = perft(position, depth - 1);
+            position.unmake_move(chess_move, undo);
+            nodes
+        })
+        .sum()
+}
+
+/// Splits the perft count by the legal moves from `position`, which makes it possible to find
+/// the move that is mishandled when a count is wrong
+pub fn divide(position: &mut Position, depth: u32) -> Vec<(ChessMove, u64)> {
+    position
+        .legal_moves()
+        .into_iter()
+        .map(|chess_move| {
+            let undo = position.make_move(chess_move);
+            let nodes = perft(position, depth.saturating_sub(1));
+            position.unmake_move(chess_move, undo);
+            (chess_move, nodes)
+        })
+        .collect()
+}
@@ -0,0 +1,289 @@
+use std::fs;
+use std::iter;
+
+use bevy::prelude::*;
+use chess::{ChessMove, Position, Session};
+
+use crate::board::{
+    ChessGame, Graveyard, MoveMadeEvent, MoveStack, PendingPromotion, PlayerNames, Pockets,
+    Promote, ResetSelectedEvent, StartingPosition, Taken,
+};
+use crate::chess_clock::ChessClock;
+use crate::pieces::{spawn_piece_at, spawn_pocket_pieces, Meshes, Piece};
+use crate::states::AppState;
+
+/// Where F5 saves the session and F9 loads it from, in the working directory
+const SESSION_FILE: &str = "bevy-chess.session";
+
+pub struct PersistencePlugin;
+
+impl Plugin for PersistencePlugin {
+    fn build(&self, app: &mut App) {
+        app // new line
+            .init_resource::<LoadedSession>()
+            .add_system(save_session)
+            .add_system(load_session)
+            .add_system(restore_session);
+    }
+}
+
+/// A session read from disk, waiting to replace the game on the board
+#[derive(Resource, Default)]
+pub struct LoadedSession(pub Option<Session>);
+
+/// Saves the game, the players' names and the clock when F5 is pressed
+fn save_session(
+    keys: Res<Input<KeyCode>>,
+    game: Res<ChessGame>,
+    names: Res<PlayerNames>,
+    clock: Res<ChessClock>,
+) {
+    if !keys.just_pressed(KeyCode::F5) {
+        return;
+    }
+
+    let session = Session {
+        game: game.0.clone(),
+        white: names.white.clone(),
+        black: names.black.clone(),
+        clock: clock.0.clone(),
+    };
+    match fs::write(SESSION_FILE, session.to_string()) {
+        Ok(()) => println!("Saved the session to {SESSION_FILE}"),
+        Err(err) => println!("Unable to save the session to {SESSION_FILE}: {err}"),
+    }
+}
+
+/// Loads the session saved by F5 when F9 is pressed
+fn load_session(keys: Res<Input<KeyCode>>, mut loaded_session: ResMut<LoadedSession>) {
+    if !keys.just_pressed(KeyCode::F9) {
+        return;
+    }
+
+    match read_session(SESSION_FILE) {
+        Ok(session) => loaded_session.0 = Some(session),
+        Err(err) => println!("{err}"),
+    }
+}
+
+pub fn read_session(path: &str) -> Result<Session, String> {
+    let text = fs::read_to_string(path).map_err(|err| format!("Unable to read '{path}': {err}"))?;
+    text.parse()
+        .map_err(|err| format!("Unable to load the session in '{path}': {err}"))
+}
+
+/// Replaces the game on the board with a loaded session
+///
+/// Every piece is spawned again where the session left it, with taken pieces in the graveyard in
+/// the order they were taken, and the moves are recorded as if they had been made on the board so
+/// they can be taken back. In variants with pockets a piece can be taken and dropped again any
+/// number of times, so every piece that is taken is followed through the rest of the game. The
+/// turn, status and move history follow from the new game and move stack as they do after any move
+#[allow(clippy::too_many_arguments)]
+fn restore_session(
+    mut commands: Commands,
+    mut loaded_session: ResMut<LoadedSession>,
+    meshes: Res<Meshes>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    pieces: Query<Entity, With<Piece>>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut reset_selected_event: EventWriter<ResetSelectedEvent>,
+) {
+    let Some(session) = loaded_session.0.take() else {
+        return;
+    };
+
+    for entity in pieces.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let game = session.game;
+    let mut position = game.starting_position().clone();
+    let mut graveyard = Graveyard::default();
+    let mut pockets = Pockets::default();
+    let mut stack = MoveStack::default();
+    // the entities of the pieces on each square that have already been spawned, having been
+    // dropped from a pocket
+    let mut entities = [None; 64];
+    spawn_pocket_pieces(
+        &mut commands,
+        &meshes,
+        &mut materials,
+        &position,
+        &mut pockets,
+    );
+    for chess_move in game.moves() {
+        let pieces_before: Vec<_> = position.pieces().copied().collect();
+
+        if let Some(piece_type) = chess_move.drop {
+            let colour = position.side_to_move();
+            let entity = pockets.last(colour, piece_type).unwrap();
+            pockets.remove(entity);
+            entities[chess_move.to.index()] = Some(entity);
+            let piece = Piece {
+                colour,
+                piece_type,
+                pos: chess_move.to,
+            };
+            stack.stack.push((
+                MoveMadeEvent::drop(piece, entity),
+                pieces_before,
+                Vec::new(),
+                None,
+            ));
+            position.make_move(*chess_move);
+            continue;
+        }
+
+        let piece = *position.piece_at(chess_move.from).unwrap();
+        let piece_entity = entities[chess_move.from.index()].take();
+        let move_made = if let Some((rook_from, rook_to)) = position.castling_rook(chess_move) {
+            entities[rook_to.index()] = entities[rook_from.index()].take();
+            MoveMadeEvent::castling(piece, chess_move.from, chess_move.to)
+        } else {
+            let taken_piece = position.captured_square(chess_move).map(|square| {
+                let taken = *position.piece_at(square).unwrap();
+                let entity = entities[square.index()].take();
+                if position.variant().has_pockets() {
+                    let held = Pockets::held_as(&position, taken, piece.colour);
+                    let entity = place_piece(
+                        &mut commands,
+                        &meshes,
+                        &mut materials,
+                        entity,
+                        held,
+                        Vec3::ZERO,
+                    );
+                    let grave = pockets.push(held.colour, held.piece_type, entity);
+                    commands
+                        .entity(entity)
+                        .insert((Taken { grave }, Transform::from_translation(grave)));
+                    entity
+                } else {
+                    let grave = graveyard.next(piece.colour);
+                    let entity =
+                        place_piece(&mut commands, &meshes, &mut materials, entity, taken, grave);
+                    commands.entity(entity).insert(Taken { grave });
+                    entity
+                }
+            });
+            MoveMadeEvent {
+                promotion: chess_move.promotion,
+                ..MoveMadeEvent::not_castling(
+                    piece,
+                    chess_move.from,
+                    chess_move.to,
+                    taken_piece,
+                    position.is_en_passant(chess_move),
+                )
+            }
+        };
+
+        entities[position.destination(chess_move).index()] = piece_entity;
+        let exploded = explode_pieces(
+            &mut commands,
+            &meshes,
+            &mut materials,
+            &position,
+            chess_move,
+            &mut entities,
+            &mut graveyard,
+        );
+        stack.stack.push((move_made, pieces_before, exploded, None));
+        position.make_move(*chess_move);
+    }
+
+    for piece in position.pieces() {
+        let translation = Vec3::new(piece.pos.rank as f32, 0.0, piece.pos.file as f32);
+        let entity = entities[piece.pos.index()];
+        if let Some(entity) = entity {
+            commands.entity(entity).remove::<Taken>();
+        }
+        place_piece(
+            &mut commands,
+            &meshes,
+            &mut materials,
+            entity,
+            *piece,
+            translation,
+        );
+    }
+
+    println!(
+        "Loaded the game between {} and {}",
+        session.white, session.black
+    );
+    next_state.set(AppState::playing(game.status().is_over()));
+    commands.insert_resource(StartingPosition(game.starting_position().clone()));
+    commands.insert_resource(ChessGame(game));
+    commands.insert_resource(stack);
+    commands.insert_resource(graveyard);
+    commands.insert_resource(pockets);
+    commands.insert_resource(PendingPromotion::default());
+    commands.insert_resource(ChessClock(session.clock));
+    commands.insert_resource(PlayerNames {
+        white: session.white,
+        black: session.black,
+    });
+    reset_selected_event.send(ResetSelectedEvent);
+}
+
+/// Puts the pieces blown up by `chess_move` in the graveyard, returning their entities with the
+/// capturing piece first, in variants where captures explode
+fn explode_pieces(
+    commands: &mut Commands,
+    meshes: &Meshes,
+    materials: &mut Assets<StandardMaterial>,
+    position: &Position,
+    chess_move: &ChessMove,
+    entities: &mut [Option<Entity>; 64],
+    graveyard: &mut Graveyard,
+) -> Vec<Entity> {
+    if !position.variant().explodes() || position.captured_square(chess_move).is_none() {
+        return Vec::new();
+    }
+
+    let mut capturer = *position.piece_at(chess_move.from).unwrap();
+    capturer.pos = chess_move.to;
+    if let Some(promotion) = chess_move.promotion {
+        capturer.piece_type = promotion;
+    }
+    let exploded = position
+        .exploded_squares(chess_move)
+        .squares()
+        .map(|square| *position.piece_at(square).unwrap());
+
+    iter::once(capturer)
+        .chain(exploded)
+        .map(|piece| {
+            let grave = graveyard.next(piece.colour.opponent());
+            let entity = entities[piece.pos.index()].take();
+            let entity = place_piece(commands, meshes, materials, entity, piece, grave);
+            commands.entity(entity).insert(Taken { grave });
+            entity
+        })
+        .collect()
+}
+
+/// Spawns `piece` at `translation`, or if it already has an entity from being dropped, turns that
+/// entity into `piece` and moves it there
+fn place_piece(
+    commands: &mut Commands,
+    meshes: &Meshes,
+    materials: &mut Assets<StandardMaterial>,
+    entity: Option<Entity>,
+    piece: Piece,
+    translation: Vec3,
+) -> Entity {
+    let Some(entity) = entity else {
+        return spawn_piece_at(commands, meshes, materials, piece, translation);
+    };
+    commands.entity(entity).insert((
+        piece,
+        Promote {
+            to: piece.piece_type,
+        },
+        Transform::from_translation(translation),
+    ));
+    entity
+}
@@ -0,0 +1,408 @@
+use crate::variant::{variant_by_name, Standard};
+use crate::{Game, PieceColour, Position, Status};
+
+/// The longest line written in the movetext of a PGN game
+const LINE_WIDTH: usize = 80;
+
+/// The names the Variant tag gives games played by the standard rules from a set-up position,
+/// which is all Chess960 needs, in lower case
+const STANDARD_RULES: [&str; 4] = ["chess960", "chess 960", "fischerandom", "from position"];
+
+/// The Seven Tag Roster that starts every PGN game, apart from the result which is taken from the
+/// game itself
+///
+/// Unknown values are written as "?", and the date as "????.??.??" with question marks for any
+/// unknown part
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct PgnTags {
+    pub event: String,
+    pub site: String,
+    pub date: String,
+    pub round: String,
+    pub white: String,
+    pub black: String,
+}
+
+impl Default for PgnTags {
+    fn default() -> Self {
+        PgnTags {
+            event: "?".to_string(),
+            site: "?".to_string(),
+            date: "????.??.??".to_string(),
+            round: "?".to_string(),
+            white: "?".to_string(),
+            black: "?".to_string(),
+        }
+    }
+}
+
+/// A game read from PGN, replayed and validated by the rules engine
+///
+/// Comments, variations and annotations are not kept, only the moves of the main line
+#[derive(Clone)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct PgnGame {
+    /// Every tag pair in the order they were given
+    pub tags: Vec<(String, String)>,
+    pub game: Game,
+    /// The game termination marker: "1-0", "0-1", "1/2-1/2" or "*"
+    pub result: String,
+}
+
+impl PgnGame {
+    /// Returns the value of the tag called `name`, if the game has one
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Reads every game in `pgn`, checking that each move is legal
+    ///
+    /// A game starts from the position in its FEN tag if it has one. A game missing its
+    /// termination marker, at the end of the text or before the tags of the next game, is read as
+    /// unfinished
+    pub fn parse_all(pgn: &str) -> Result<Vec<PgnGame>, String> {
+        let mut games = Vec::new();
+        read_games(&mut Tokens::new(pgn), &mut games)
+            .map_err(|err| format!("game {}: {err}", games.len() + 1))?;
+        Ok(games)
+    }
+}
+
+/// Reads the games from `tokens` into `games`, stopping at the first error
+fn read_games(tokens: &mut Tokens, games: &mut Vec<PgnGame>) -> Result<(), String> {
+    let mut tags = Vec::new();
+    let mut game = None;
+    let mut variation_depth = 0;
+
+    while let Some(token) = tokens.next_token() {
+        match token? {
+            Token::TagPair(name, value) => {
+                if game.is_some() {
+                    games.push(finish_game(&mut tags, &mut game, "*")?);
+                }
+                tags.push((name, value));
+            }
+            Token::StartVariation => variation_depth += 1,
+            Token::EndVariation if variation_depth == 0 => return Err("unexpected ')'".to_string()),
+            Token::EndVariation => variation_depth -= 1,
+            // only the main line is replayed
+            Token::Symbol(_) if variation_depth > 0 => {}
+            Token::Symbol(result @ ("1-0" | "0-1" | "1/2-1/2" | "*")) => {
+                games.push(finish_game(&mut tags, &mut game, result)?);
+            }
+            Token::Symbol(symbol) => {
+                // a move may be written straight after its number, as in "1.e4"
+                let san = symbol
+                    .trim_start_matches(|c: char| c.is_ascii_digit())
+                    .trim_start_matches('.');
+                if san.trim_matches(['!', '?']).is_empty() {
+                    continue;
+                }
+
+                let game = match &mut game {
+                    Some(game) => game,
+                    None => game.insert(start_game(&tags)?),
+                };
+                game.position()
+                    .parse_san(san)
+                    .and_then(|chess_move| game.make_move(chess_move))?;
+            }
+        }
+    }
+
+    if variation_depth > 0 {
+        return Err("unterminated variation".to_string());
+    }
+    if game.is_some() || !tags.is_empty() {
+        games.push(finish_game(&mut tags, &mut game, "*")?);
+    }
+    Ok(())
+}
+
+/// Completes the game being read, taking its tags and moves
+fn finish_game(
+    tags: &mut Vec<(String, String)>,
+    game: &mut Option<Game>,
+    result: &str,
+) -> Result<PgnGame, String> {
+    let game = match game.take() {
+        Some(game) => game,
+        None => start_game(tags)?,
+    };
+    Ok(PgnGame {
+        tags: std::mem::take(tags),
+        game,
+        result: result.to_string(),
+    })
+}
+
+/// Creates the game described by `tags`, starting from their FEN if there is one and played under
+/// the rules of their Variant
+fn start_game(tags: &[(String, String)]) -> Result<Game, String> {
+    let tag = |tag| {
+        tags.iter()
+            .find(|(name, _)| name == tag)
+            .map(|(_, value)| value)
+    };
+    let variant = match tag("Variant") {
+        Some(name) if !STANDARD_RULES.contains(&name.to_lowercase().as_str()) => {
+            variant_by_name(name).ok_or_else(|| format!("unsupported variant '{name}'"))?
+        }
+        _ => &Standard,
+    };
+
+    let position = match tag("FEN") {
+        Some(fen) => {
+            Position::from_fen(fen, variant).map_err(|err| format!("invalid FEN '{fen}': {err}"))?
+        }
+        None => Position::starting(variant),
+    };
+    Ok(Game::new(position))
+}
+
+/// The parts of PGN that matter when reading the main line of a game
+enum Token<'a> {
+    TagPair(String, String),
+    StartVariation,
+    EndVariation,
+    /// Move numbers, moves and termination markers
+    Symbol(&'a str),
+}
+
+/// Splits PGN into tokens, skipping comments, escaped lines and Numeric Annotation Glyphs
+struct Tokens<'a> {
+    pgn: &'a str,
+    index: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(pgn: &'a str) -> Tokens<'a> {
+        Tokens {
+            pgn: pgn.trim_start_matches('\u{feff}'),
+            index: 0,
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.pgn[self.index..]
+    }
+
+    fn skip_until(&mut self, end: char) -> bool {
+        match self.rest().find(end) {
+            Some(offset) => {
+                self.index += offset + end.len_utf8();
+                true
+            }
+            None => {
+                self.index = self.pgn.len();
+                false
+            }
+        }
+    }
+
+    fn next_token(&mut self) -> Option<Result<Token<'a>, String>> {
+        loop {
+            let c = self.rest().chars().next()?;
+            let line_start = self.pgn[..self.index].ends_with('\n') || self.index == 0;
+            match c {
+                _ if c.is_whitespace() => self.index += c.len_utf8(),
+                '%' if line_start => {
+                    self.skip_until('\n');
+                }
+                ';' => {
+                    self.skip_until('\n');
+                }
+                '{' => {
+                    if !self.skip_until('}') {
+                        return Some(Err("unterminated comment".to_string()));
+                    }
+                }
+                '$' => {
+                    self.index += 1;
+                    let digits = self.rest().len()
+                        - self
+                            .rest()
+                            .trim_start_matches(|c: char| c.is_ascii_digit())
+                            .len();
+                    self.index += digits;
+                }
+                '(' => {
+                    self.index += 1;
+                    return Some(Ok(Token::StartVariation));
+                }
+                ')' => {
+                    self.index += 1;
+                    return Some(Ok(Token::EndVariation));
+                }
+                '[' => return Some(self.tag_pair()),
+                _ => {
+                    let rest = self.rest();
+                    let length = rest
+                        .find(|c: char| c.is_whitespace() || "{}()[];$\"".contains(c))
+                        .unwrap_or(rest.len())
+                        .max(c.len_utf8());
+                    self.index += length;
+                    return Some(Ok(Token::Symbol(&rest[..length])));
+                }
+            }
+        }
+    }
+
+    /// Reads a tag pair such as `[Event "Casual game"]`, unescaping the value
+    fn tag_pair(&mut self) -> Result<Token<'a>, String> {
+        let start = self.index;
+        let invalid = |tokens: &Self| {
+            let end = tokens
+                .rest()
+                .find('\n')
+                .map_or(tokens.pgn.len(), |end| tokens.index + end);
+            format!("invalid tag pair '{}'", &tokens.pgn[start..end])
+        };
+
+        self.index += 1;
+        let rest = self.rest().trim_start();
+        self.index = self.pgn.len() - rest.len();
+        let name_length = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        let name = rest[..name_length].to_string();
+        self.index += name_length;
+
+        let rest = self.rest().trim_start();
+        self.index = self.pgn.len() - rest.len();
+        if name.is_empty() || !rest.starts_with('"') {
+            return Err(invalid(self));
+        }
+        self.index += 1;
+
+        let mut value = String::new();
+        let mut chars = self.rest().char_indices();
+        loop {
+            match chars.next() {
+                Some((_, '\\')) => match chars.next() {
+                    Some((_, c)) => value.push(c),
+                    None => return Err(invalid(self)),
+                },
+                Some((offset, '"')) => {
+                    self.index += offset + 1;
+                    break;
+                }
+                Some((_, c)) => value.push(c),
+                None => return Err(invalid(self)),
+            }
+        }
+
+        let rest = self.rest().trim_start();
+        if !rest.starts_with(']') {
+            return Err(invalid(self));
+        }
+        self.index = self.pgn.len() - rest.len() + 1;
+        Ok(Token::TagPair(name, value))
+    }
+}
+
+impl Game {
+    /// The PGN result token: "1-0" or "0-1" for a win, "1/2-1/2" for a draw and "*" for a game
+    /// that is still in progress
+    pub fn result(&self) -> &'static str {
+        match self.status() {
+            Status::Checkmate | Status::OutOfTime => match self.position().side_to_move() {
+                PieceColour::White => "0-1",
+                PieceColour::Black => "1-0",
+            },
+            Status::Resigned(PieceColour::White) => "0-1",
+            Status::Resigned(PieceColour::Black) => "1-0",
+            Status::VariantWin(PieceColour::White) => "1-0",
+            Status::VariantWin(PieceColour::Black) => "0-1",
+            Status::Draw(_) => "1/2-1/2",
+            Status::OnGoing | Status::Check => "*",
+        }
+    }
+
+    /// Returns the game in Portable Game Notation, in the export format with the movetext
+    /// wrapped at 80 columns
+    ///
+    /// Games that did not start from the standard starting position include the SetUp and FEN
+    /// tags needed to recreate the starting position, and games of other variants, including
+    /// Chess960, are marked by the Variant tag
+    pub fn to_pgn(&self, tags: &PgnTags) -> String {
+        let result = self.result();
+        let mut pgn = String::new();
+        for (name, value) in [
+            ("Event", tags.event.as_str()),
+            ("Site", tags.site.as_str()),
+            ("Date", tags.date.as_str()),
+            ("Round", tags.round.as_str()),
+            ("White", tags.white.as_str()),
+            ("Black", tags.black.as_str()),
+            ("Result", result),
+        ] {
+            pgn.push_str(&tag_pair(name, value));
+        }
+
+        let starting_fen = self.starting_position().to_fen();
+        let variant = self.starting_position().variant();
+        if !variant.is_standard() {
+            pgn.push_str(&tag_pair("Variant", variant.name()));
+        } else if self.starting_position().is_chess960() {
+            pgn.push_str(&tag_pair("Variant", "Chess960"));
+        }
+        if starting_fen != variant.starting_fen() {
+            pgn.push_str(&tag_pair("SetUp", "1"));
+            pgn.push_str(&tag_pair("FEN", &starting_fen));
+        }
+        pgn.push('\n');
+
+        let mut tokens = self.movetext();
+        tokens.push(result.to_string());
+        pgn.push_str(&wrap(&tokens));
+        pgn.push('\n');
+        pgn
+    }
+
+    /// Each move in SAN, preceded by its move number when White moves or the game starts with
+    /// Black to move
+    fn movetext(&self) -> Vec<String> {
+        let mut position = self.starting_position().clone();
+        let mut tokens = Vec::new();
+        for chess_move in self.moves() {
+            let move_number = position.fullmove_number();
+            match position.side_to_move() {
+                PieceColour::White => tokens.push(format!("{move_number}.")),
+                PieceColour::Black if tokens.is_empty() => tokens.push(format!("{move_number}...")),
+                PieceColour::Black => {}
+            }
+            tokens.push(position.to_san(chess_move));
+            position.make_move(*chess_move);
+        }
+        tokens
+    }
+}
+
+/// Formats a tag pair, escaping quotes and backslashes in the value
+fn tag_pair(name: &str, value: &str) -> String {
+    let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+    format!("[{name} \"{value}\"]\n")
+}
+
+/// Joins the tokens with spaces, starting a new line rather than letting a line exceed
+/// `LINE_WIDTH` characters
+fn wrap(tokens: &[String]) -> String {
+    let mut lines = vec![String::new()];
+    for token in tokens {
+        let line = lines.last_mut().unwrap();
+        if line.is_empty() {
+            line.push_str(token);
+        } else if line.len() + 1 + token.len() <= LINE_WIDTH {
+            line.push(' ');
+            line.push_str(token);
+        } else {
+            lines.push(token.clone());
+        }
+    }
+    lines.join("\n")
+}
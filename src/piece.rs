@@ -0,0 +1,124 @@
+use std::fmt::{Debug, Display, Formatter};
+
+use bevy::prelude::Component;
+
+use crate::Square;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub enum PieceColour {
+    White,
+    Black,
+}
+
+impl PieceColour {
+    pub fn opponent(&self) -> PieceColour {
+        match self {
+            PieceColour::White => PieceColour::Black,
+            PieceColour::Black => PieceColour::White,
+        }
+    }
+
+    /// Returns the standard direction of pawn movement for the colour (i.e. 1 for `White` -1 for
+    /// `Black`)
+    pub fn pawn_movement_direction(&self) -> i8 {
+        match self {
+            PieceColour::White => 1,
+            PieceColour::Black => -1,
+        }
+    }
+
+    /// The index of the colour in arrays with an entry for each colour
+    pub(crate) fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+impl Display for PieceColour {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                PieceColour::White => "White",
+                PieceColour::Black => "Black",
+            }
+        )
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum PieceType {
+    King,
+    Queen,
+    Bishop,
+    Knight,
+    Rook,
+    Pawn,
+}
+
+impl Display for PieceType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(self, f)
+    }
+}
+
+impl PieceType {
+    /// The index of the piece type in arrays with an entry for each piece type
+    pub(crate) fn index(&self) -> usize {
+        *self as usize
+    }
+
+    pub fn notation_letter(&self) -> String {
+        match self {
+            PieceType::King => "K",
+            PieceType::Queen => "Q",
+            PieceType::Bishop => "B",
+            PieceType::Knight => "N",
+            PieceType::Rook => "R",
+            PieceType::Pawn => "",
+        }
+        .to_string()
+    }
+
+    /// The upper case letter used for this piece type in FEN, which unlike `notation_letter` is 'P'
+    /// for pawns
+    pub fn fen_letter(&self) -> char {
+        match self {
+            PieceType::Pawn => 'P',
+            piece_type => piece_type.notation_letter().chars().next().unwrap(),
+        }
+    }
+
+    /// Parses the upper case letter used for this piece type in FEN, which unlike
+    /// `notation_letter` includes 'P' for pawns
+    pub fn from_fen_letter(letter: char) -> Option<PieceType> {
+        match letter {
+            'K' => Some(PieceType::King),
+            'Q' => Some(PieceType::Queen),
+            'B' => Some(PieceType::Bishop),
+            'N' => Some(PieceType::Knight),
+            'R' => Some(PieceType::Rook),
+            'P' => Some(PieceType::Pawn),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Component, Debug)]
+pub struct Piece {
+    pub colour: PieceColour,
+    pub piece_type: PieceType,
+    pub pos: Square,
+}
+
+impl Piece {
+    /// Returns the letter used for this piece in FEN, upper case for white and lower case for black
+    pub fn fen_letter(&self) -> char {
+        let letter = self.piece_type.fen_letter();
+        match self.colour {
+            PieceColour::White => letter,
+            PieceColour::Black => letter.to_ascii_lowercase(),
+        }
+    }
+}
@@ -1,9 +1,9 @@
 use bevy::prelude::*;
 
-pub use components::{Piece, PieceColour, PieceType};
-use resources::Meshes;
+pub use chess::{Piece, PieceColour, PieceType};
+pub use resources::Meshes;
+pub use systems::{spawn_piece_at, spawn_pocket_pieces};
 
-mod components;
 mod resources;
 mod systems;
 
@@ -14,6 +14,7 @@ impl Plugin for PiecesPlugin {
         app // new line
             .init_resource::<Meshes>()
             .add_startup_system(systems::create_pieces)
+            .add_system(systems::respawn_pieces)
             .add_system(systems::change_mesh)
             .add_system(systems::move_pieces);
     }
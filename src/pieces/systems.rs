@@ -1,6 +1,6 @@
 use bevy::prelude::*;
 
-pub use creation::create_pieces;
+pub use creation::{create_pieces, respawn_pieces, spawn_piece_at, spawn_pocket_pieces};
 
 use crate::board::{Promote, Taken};
 use crate::pieces::resources::{Meshes, PieceMesh};
@@ -1,147 +1,156 @@
 use bevy::prelude::*;
 
-use crate::board::Square;
-use crate::pieces::components::{Piece, PieceColour};
+use chess::{Position, Square, A_FILE, RANK_1};
+
+use crate::board::{NewGameEvent, Pockets, StartingPosition, Taken};
 use crate::pieces::resources::{Meshes, PieceMesh};
+use crate::pieces::{Piece, PieceColour};
 
+/// Spawns a `Piece` entity for every piece in the `StartingPosition`, including any held in the
+/// pockets
 pub fn create_pieces(
     mut commands: Commands,
     meshes: Res<Meshes>,
+    starting_position: Res<StartingPosition>,
     mut materials: ResMut<Assets<StandardMaterial>>,
+    mut pockets: ResMut<Pockets>,
 ) {
-    spawn_set(
+    spawn_pieces(
         &mut commands,
-        PieceColour::White,
-        &mut materials,
         &meshes,
-        (1.0, 0.0),
+        &mut materials,
+        &starting_position.0,
+        &mut pockets,
     );
-    spawn_set(
+}
+
+/// Replaces every `Piece` entity, including those that have been taken, with the pieces of the
+/// `StartingPosition` when a new game is started
+pub fn respawn_pieces(
+    mut commands: Commands,
+    mut events: EventReader<NewGameEvent>,
+    meshes: Res<Meshes>,
+    starting_position: Res<StartingPosition>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut pockets: ResMut<Pockets>,
+    pieces: Query<Entity, With<Piece>>,
+) {
+    if events.iter().count() == 0 {
+        return;
+    }
+
+    for entity in pieces.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    spawn_pieces(
         &mut commands,
-        PieceColour::Black,
-        &mut materials,
         &meshes,
-        (6.0, 7.0),
+        &mut materials,
+        &starting_position.0,
+        &mut pockets,
     );
 }
 
-fn spawn_set(
+/// Spawns the pieces of `position`, filling the pockets afresh with the pieces held in hand
+fn spawn_pieces(
     commands: &mut Commands,
-    piece_colour: PieceColour,
-    materials: &mut ResMut<Assets<StandardMaterial>>,
-    pieces: &Meshes,
-    (front_row, back_row): (f32, f32),
+    meshes: &Meshes,
+    materials: &mut Assets<StandardMaterial>,
+    position: &Position,
+    pockets: &mut Pockets,
 ) {
-    let material = match piece_colour {
-        PieceColour::White => materials.add(Color::rgb(1.0, 0.8, 0.8).into()),
-        PieceColour::Black => materials.add(Color::rgb(0.0, 0.2, 0.2).into()),
-    };
+    let white_material = materials.add(Color::rgb(1.0, 0.8, 0.8).into());
+    let black_material = materials.add(Color::rgb(0.0, 0.2, 0.2).into());
 
-    for piece in pieces.0.iter() {
-        match piece {
-            PieceMesh::King(_, _, _) => spawn_king(
-                commands,
-                material.clone(),
-                piece_colour,
-                piece.clone(),
-                Vec3::new(back_row, 0.0, 4.0),
-            ),
-            PieceMesh::Queen(_, _) => spawn_queen(
-                commands,
-                material.clone(),
-                piece_colour,
-                piece.clone(),
-                Vec3::new(back_row, 0.0, 3.0),
-            ),
-            PieceMesh::Rook(_, _) => {
-                spawn_rook(
-                    commands,
-                    material.clone(),
-                    piece_colour,
-                    piece.clone(),
-                    Vec3::new(back_row, 0.0, 0.0),
-                );
-                spawn_rook(
-                    commands,
-                    material.clone(),
-                    piece_colour,
-                    piece.clone(),
-                    Vec3::new(back_row, 0.0, 7.0),
-                );
-            }
-            PieceMesh::Bishop(_, _) => {
-                spawn_bishop(
-                    commands,
-                    material.clone(),
-                    piece_colour,
-                    piece.clone(),
-                    Vec3::new(back_row, 0.0, 2.0),
-                );
-                spawn_bishop(
-                    commands,
-                    material.clone(),
-                    piece_colour,
-                    piece.clone(),
-                    Vec3::new(back_row, 0.0, 5.0),
-                );
-            }
-            PieceMesh::Knight(_, _, _) => {
-                spawn_knight(
-                    commands,
-                    material.clone(),
-                    piece_colour,
-                    piece.clone(),
-                    Vec3::new(back_row, 0.0, 1.0),
-                );
-                spawn_knight(
-                    commands,
-                    material.clone(),
-                    piece_colour,
-                    piece.clone(),
-                    Vec3::new(back_row, 0.0, 6.0),
-                );
-            }
-            PieceMesh::Pawn(_, _) => {
-                for i in 0..=7 {
-                    spawn_pawn(
-                        commands,
-                        material.clone(),
-                        piece_colour,
-                        piece.clone(),
-                        Vec3::new(front_row, 0.0, i as f32),
-                    );
-                }
+    for piece in position.pieces() {
+        let material = match piece.colour {
+            PieceColour::White => white_material.clone(),
+            PieceColour::Black => black_material.clone(),
+        };
+        let mesh = meshes
+            .0
+            .iter()
+            .find(|mesh| mesh.matches_type(piece.piece_type))
+            .unwrap()
+            .clone();
+
+        let translation = Vec3::new(piece.pos.rank as f32, 0.0, piece.pos.file as f32);
+        spawn_piece(commands, material, *piece, mesh, translation);
+    }
+
+    *pockets = Pockets::default();
+    spawn_pocket_pieces(commands, meshes, materials, position, pockets);
+}
+
+/// Spawns a `Piece` entity for every piece held in the pockets of `position`, adding them to
+/// `pockets`
+pub fn spawn_pocket_pieces(
+    commands: &mut Commands,
+    meshes: &Meshes,
+    materials: &mut Assets<StandardMaterial>,
+    position: &Position,
+    pockets: &mut Pockets,
+) {
+    for colour in [PieceColour::White, PieceColour::Black] {
+        for piece_type in Pockets::PIECE_TYPES {
+            for _ in 0..position.pocket(colour, piece_type) {
+                // the square is only known once the piece is dropped
+                let piece = Piece {
+                    colour,
+                    piece_type,
+                    pos: Square::new(RANK_1, A_FILE),
+                };
+                let entity = spawn_piece_at(commands, meshes, materials, piece, Vec3::ZERO);
+                let grave = pockets.push(colour, piece_type, entity);
+                commands
+                    .entity(entity)
+                    .insert((Taken { grave }, Transform::from_translation(grave)));
             }
         }
     }
 }
 
+/// Spawns a `Piece` entity for `piece` at `translation`, which is away from its square if it has
+/// been taken, returning the entity
+pub fn spawn_piece_at(
+    commands: &mut Commands,
+    meshes: &Meshes,
+    materials: &mut Assets<StandardMaterial>,
+    piece: Piece,
+    translation: Vec3,
+) -> Entity {
+    let material = match piece.colour {
+        PieceColour::White => materials.add(Color::rgb(1.0, 0.8, 0.8).into()),
+        PieceColour::Black => materials.add(Color::rgb(0.0, 0.2, 0.2).into()),
+    };
+    let mesh = meshes
+        .0
+        .iter()
+        .find(|mesh| mesh.matches_type(piece.piece_type))
+        .unwrap()
+        .clone();
+
+    spawn_piece(commands, material, piece, mesh, translation)
+}
+
 fn spawn_piece(
     commands: &mut Commands,
     material: Handle<StandardMaterial>,
-    piece_colour: PieceColour,
-    piece: PieceMesh,
-    position: Vec3,
-) {
+    piece: Piece,
+    mesh: PieceMesh,
+    translation: Vec3,
+) -> Entity {
     commands
         .spawn((
             PbrBundle {
-                transform: Transform::from_translation(position),
+                transform: Transform::from_translation(translation),
                 ..Default::default()
             },
-            Piece {
-                colour: piece_colour,
-                piece_type: (&piece).into(), // from impl on ref to allow mesh to be reused later
-                pos: Square {
-                    rank: position.x as i8,
-                    file: position.z as i8,
-                },
-                has_moved: false,
-            },
+            piece,
         ))
         .with_children(|parent| {
             use PieceMesh::*;
-            match piece {
+            match mesh {
                 King(mesh_1, mesh_2, transform) | Knight(mesh_1, mesh_2, transform) => {
                     spawn_child(mesh_1, material.clone(), parent, transform);
                     spawn_child(mesh_2, material.clone(), parent, transform);
@@ -153,7 +162,8 @@ fn spawn_piece(
                     spawn_child(mesh, material.clone(), parent, transform);
                 }
             }
-        });
+        })
+        .id()
 }
 
 fn spawn_child(
@@ -169,63 +179,3 @@ fn spawn_child(
         ..Default::default()
     });
 }
-
-fn spawn_king(
-    commands: &mut Commands,
-    material: Handle<StandardMaterial>,
-    piece_colour: PieceColour,
-    piece: PieceMesh,
-    position: Vec3,
-) {
-    spawn_piece(commands, material, piece_colour, piece, position);
-}
-
-fn spawn_knight(
-    commands: &mut Commands,
-    material: Handle<StandardMaterial>,
-    piece_colour: PieceColour,
-    piece: PieceMesh,
-    position: Vec3,
-) {
-    spawn_piece(commands, material, piece_colour, piece, position);
-}
-
-fn spawn_queen(
-    commands: &mut Commands,
-    material: Handle<StandardMaterial>,
-    piece_colour: PieceColour,
-    piece: PieceMesh,
-    position: Vec3,
-) {
-    spawn_piece(commands, material, piece_colour, piece, position)
-}
-
-fn spawn_bishop(
-    commands: &mut Commands,
-    material: Handle<StandardMaterial>,
-    piece_colour: PieceColour,
-    piece: PieceMesh,
-    position: Vec3,
-) {
-    spawn_piece(commands, material, piece_colour, piece, position)
-}
-
-fn spawn_rook(
-    commands: &mut Commands,
-    material: Handle<StandardMaterial>,
-    piece_colour: PieceColour,
-    piece: PieceMesh,
-    position: Vec3,
-) {
-    spawn_piece(commands, material, piece_colour, piece, position)
-}
-
-fn spawn_pawn(
-    commands: &mut Commands,
-    material: Handle<StandardMaterial>,
-    piece_colour: PieceColour,
-    piece: PieceMesh,
-    position: Vec3,
-) {
-    spawn_piece(commands, material, piece_colour, piece, position)
-}
@@ -0,0 +1,886 @@
+use std::fmt::{Display, Formatter};
+
+use crate::bitboard::{
+    between, bishop_attacks, king_attacks, knight_attacks, pawn_attacks, queen_attacks,
+    rook_attacks,
+};
+use crate::fen::CastlingRights;
+use crate::game::{DrawReason, Status};
+use crate::variant::{Standard, Variant};
+use crate::zobrist::piece_key;
+use crate::{Bitboard, Piece, PieceColour, PieceType, Square};
+use crate::{
+    A_FILE, C_FILE, D_FILE, E_FILE, F_FILE, G_FILE, H_FILE, RANK_1, RANK_2, RANK_7, RANK_8,
+};
+
+/// A single move of a piece from one square to another, or of a piece dropped from the pocket
+///
+/// Castling is represented as the King moving two squares towards the rook, or in Chess960 as the
+/// King taking its own rook, and the captured piece is not stored as it can be determined from the
+/// `Position` the move is made in. A drop, which only variants such as Crazyhouse allow, has the
+/// square it is dropped on as both `from` and `to`
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct ChessMove {
+    pub from: Square,
+    pub to: Square,
+    pub promotion: Option<PieceType>,
+    /// The type of piece dropped from the pocket, if the move is a drop
+    pub drop: Option<PieceType>,
+}
+
+impl ChessMove {
+    pub fn new(from: Square, to: Square) -> ChessMove {
+        ChessMove {
+            from,
+            to,
+            promotion: None,
+            drop: None,
+        }
+    }
+
+    /// Dropping a piece of `piece_type` from the pocket onto `to`
+    pub fn new_drop(piece_type: PieceType, to: Square) -> ChessMove {
+        ChessMove {
+            drop: Some(piece_type),
+            ..ChessMove::new(to, to)
+        }
+    }
+}
+
+/// Display the move in the long algebraic notation used by UCI, e.g. "e2e4", "e7e8q" or "N@f3"
+impl Display for ChessMove {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some(piece_type) = self.drop {
+            return write!(f, "{}@{}", piece_type.fen_letter(), self.to);
+        }
+        write!(f, "{}{}", self.from, self.to)?;
+        if let Some(promotion) = self.promotion {
+            write!(f, "{}", promotion.notation_letter().to_lowercase())?;
+        }
+        Ok(())
+    }
+}
+
+/// The state required to reverse a move made with `Position::make_move`
+#[derive(Clone, Copy)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct Undo {
+    captured: Option<Piece>,
+    /// The pieces blown up by a capture in variants where captures explode, including the
+    /// capturing piece
+    exploded: [Option<Piece>; 9],
+    castling: CastlingRights,
+    castling_rook: Option<(Square, Square)>,
+    en_passant: Option<Square>,
+    halfmove_clock: u32,
+    checks: [u8; 2],
+    pockets: [[u8; 6]; 2],
+    promoted: Bitboard,
+    key: u64,
+}
+
+/// The complete state of a game at a single point in time, independent of how it was reached
+///
+/// Pieces are stored twice: a `Bitboard` for each colour and piece type, which move generation
+/// works with, and a square-indexed board for looking up the piece on a given square
+///
+/// The position also knows the `Variant` it is played under, which decides the legal moves and
+/// the status of the game, along with any state the variant needs such as the checks given or the
+/// pieces in each player's pocket
+#[derive(Clone)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct Position {
+    pub(crate) board: [Option<Piece>; 64],
+    pub(crate) colours: [Bitboard; 2],
+    pub(crate) piece_types: [Bitboard; 6],
+    pub(crate) side_to_move: PieceColour,
+    pub(crate) castling: CastlingRights,
+    pub(crate) castling_files: [(i8, i8); 2],
+    pub(crate) en_passant: Option<Square>,
+    pub(crate) halfmove_clock: u32,
+    pub(crate) fullmove_number: u32,
+    /// The number of times each side has given check, kept for variants that count them
+    pub(crate) checks: [u8; 2],
+    /// The number of pieces of each type each side holds in hand, kept for variants with pockets
+    pub(crate) pockets: [[u8; 6]; 2],
+    /// The pieces that were pawns before promoting, which go back to the pocket as pawns when
+    /// taken in variants with pockets
+    pub(crate) promoted: Bitboard,
+    pub(crate) variant: &'static dyn Variant,
+    pub(crate) key: u64,
+}
+
+const PROMOTION_TYPES: [PieceType; 4] = [
+    PieceType::Queen,
+    PieceType::Rook,
+    PieceType::Bishop,
+    PieceType::Knight,
+];
+
+impl Default for Position {
+    fn default() -> Self {
+        crate::STARTING_FEN
+            .parse()
+            .expect("starting position is valid")
+    }
+}
+
+impl Position {
+    /// An empty board with no castling rights, which pieces can be added to with `put_piece`
+    ///
+    /// The key must be recalculated after changing anything other than the pieces
+    pub(crate) fn empty(side_to_move: PieceColour) -> Position {
+        let mut position = Position {
+            board: [None; 64],
+            colours: [Bitboard::EMPTY; 2],
+            piece_types: [Bitboard::EMPTY; 6],
+            side_to_move,
+            castling: CastlingRights::default(),
+            castling_files: [(H_FILE, A_FILE); 2],
+            en_passant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            checks: [0; 2],
+            pockets: [[0; 6]; 2],
+            promoted: Bitboard::EMPTY,
+            variant: &Standard,
+            key: 0,
+        };
+        position.key = position.calculate_key();
+        position
+    }
+
+    /// The starting position of `variant`, to be played under its rules
+    pub fn starting(variant: &'static dyn Variant) -> Position {
+        let mut position: Position = variant
+            .starting_fen()
+            .parse()
+            .expect("starting position is valid");
+        position.set_variant(variant);
+        position
+    }
+
+    pub fn variant(&self) -> &'static dyn Variant {
+        self.variant
+    }
+
+    /// Plays the rest of the game under the rules of `variant`
+    pub fn set_variant(&mut self, variant: &'static dyn Variant) {
+        self.variant = variant;
+        self.key = self.calculate_key();
+    }
+
+    /// The number of times `colour` has given check, which is only counted in variants such as
+    /// Three-check that need it
+    pub fn checks(&self, colour: PieceColour) -> u8 {
+        self.checks[colour.index()]
+    }
+
+    /// The number of pieces of `piece_type` that `colour` holds in its pocket, ready to be dropped,
+    /// which is only ever more than zero in variants such as Crazyhouse
+    pub fn pocket(&self, colour: PieceColour, piece_type: PieceType) -> u8 {
+        self.pockets[colour.index()][piece_type.index()]
+    }
+
+    /// Returns true if the piece on `square` was promoted from a pawn, which is only kept track of
+    /// in variants with pockets
+    pub fn is_promoted(&self, square: Square) -> bool {
+        self.promoted.contains(square)
+    }
+
+    /// Every piece on the board, from a1 to h8
+    pub fn pieces(&self) -> impl Iterator<Item = &Piece> {
+        self.board.iter().flatten()
+    }
+
+    pub fn side_to_move(&self) -> PieceColour {
+        self.side_to_move
+    }
+
+    pub fn castling(&self) -> CastlingRights {
+        self.castling
+    }
+
+    /// Returns the (kingside, queenside) files of the rooks `colour` castles with
+    ///
+    /// These are the h- and a-files unless the game started from a Chess960 position
+    pub fn castling_files(&self, colour: PieceColour) -> (i8, i8) {
+        self.castling_files[colour.index()]
+    }
+
+    /// Returns true if castling is written as the King taking its own rook, as in Chess960
+    ///
+    /// This is the case while either side can castle with its King off the e-file or a rook off the
+    /// a- and h-files, as the King's destination no longer always tells castling apart from an
+    /// ordinary King move
+    pub fn is_chess960(&self) -> bool {
+        [PieceColour::White, PieceColour::Black]
+            .into_iter()
+            .any(|colour| {
+                let (kingside, queenside) = self.castling.for_colour(colour);
+                let (kingside_file, queenside_file) = self.castling_files(colour);
+                let king_on_e_file = self
+                    .pieces_of(colour, PieceType::King)
+                    .first()
+                    .is_some_and(|king| king.file == E_FILE);
+                ((kingside || queenside) && !king_on_e_file)
+                    || (kingside && kingside_file != H_FILE)
+                    || (queenside && queenside_file != A_FILE)
+            })
+    }
+
+    /// The square a pawn passed over with a double step on the previous move, if any
+    pub fn en_passant(&self) -> Option<Square> {
+        self.en_passant
+    }
+
+    /// The number of moves by either player since the last capture or pawn movement
+    pub fn halfmove_clock(&self) -> u32 {
+        self.halfmove_clock
+    }
+
+    pub fn fullmove_number(&self) -> u32 {
+        self.fullmove_number
+    }
+
+    pub fn piece_at(&self, square: Square) -> Option<&Piece> {
+        self.board[square.index()].as_ref()
+    }
+
+    /// The squares occupied by a piece of either colour
+    pub fn occupied(&self) -> Bitboard {
+        self.colours[0] | self.colours[1]
+    }
+
+    /// The squares occupied by a piece of `colour`
+    pub fn occupied_by(&self, colour: PieceColour) -> Bitboard {
+        self.colours[colour.index()]
+    }
+
+    /// The squares occupied by a piece of `colour` and `piece_type`
+    pub fn pieces_of(&self, colour: PieceColour, piece_type: PieceType) -> Bitboard {
+        self.colours[colour.index()] & self.piece_types[piece_type.index()]
+    }
+
+    /// The Zobrist key of the position, which identifies it for repetition detection
+    ///
+    /// Two positions share a key when the same pieces are on the same squares with the same side to
+    /// move and the same castling rights, and en passant is available in both or neither, as well
+    /// as the same number of checks given and the same pieces in hand in variants that keep them.
+    /// The key is updated incrementally as moves are made
+    pub fn key(&self) -> u64 {
+        self.key
+    }
+
+    /// Returns every legal move for the side to move under the rules of the variant, which is
+    /// none once a player has won
+    pub fn legal_moves(&self) -> Vec<ChessMove> {
+        if self.variant.winner(self).is_some() {
+            return Vec::new();
+        }
+        self.variant.legal_moves(self)
+    }
+
+    /// Returns every move that is legal in standard chess, which variants build on
+    pub fn standard_legal_moves(&self) -> Vec<ChessMove> {
+        let mut moves = self.pseudo_legal_moves();
+        moves.retain(|chess_move| self.avoids_check(chess_move));
+        moves
+    }
+
+    /// Returns the legal moves of the piece on `square`, which will be empty if the square is empty
+    /// or the piece does not belong to the side to move
+    pub fn legal_moves_from(&self, square: Square) -> Vec<ChessMove> {
+        self.legal_moves()
+            .into_iter()
+            .filter(|chess_move| chess_move.from == square)
+            .collect()
+    }
+
+    pub fn is_legal(&self, chess_move: &ChessMove) -> bool {
+        self.legal_moves().contains(chess_move)
+    }
+
+    /// Returns true if the King of `colour` is attacked
+    ///
+    /// In variants where captures explode a King next to the other King is never in check, as
+    /// taking it would blow up both
+    pub fn is_in_check(&self, colour: PieceColour) -> bool {
+        let Some(king) = self.pieces_of(colour, PieceType::King).first() else {
+            return false;
+        };
+        let kings_touch =
+            !(king_attacks(king) & self.pieces_of(colour.opponent(), PieceType::King)).is_empty();
+        !(self.variant.explodes() && kings_touch) && self.is_attacked(king, colour.opponent())
+    }
+
+    /// Determines the state of the game for the side to move
+    ///
+    /// The variant decides who has won, what having no moves means and which positions are
+    /// drawn, while the seventy-five-move rule applies to every variant
+    pub fn status(&self) -> Status {
+        if let Some(winner) = self.variant.winner(self) {
+            return Status::VariantWin(winner);
+        }
+
+        let no_moves = self
+            .variant
+            .legal_moves(self)
+            .is_empty()
+            .then(|| self.variant.no_moves(self));
+        let check = self.variant.has_check() && self.is_in_check(self.side_to_move);
+
+        if let Some(status) = no_moves.filter(|status| !matches!(status, Status::Draw(_))) {
+            status
+        } else if self.halfmove_clock >= 150 {
+            Status::Draw(DrawReason::SeventyFiveMoveRule)
+        } else if let Some(reason) = self.variant.draw(self) {
+            Status::Draw(reason)
+        } else if let Some(status) = no_moves {
+            status
+        } else if check {
+            Status::Check
+        } else {
+            Status::OnGoing
+        }
+    }
+
+    /// Returns true if `chess_move` is a King moving two squares to castle, or in Chess960 a King
+    /// taking its own rook
+    pub fn is_castling(&self, chess_move: &ChessMove) -> bool {
+        let Some(king) = self
+            .piece_at(chess_move.from)
+            .filter(|piece| piece.piece_type == PieceType::King)
+        else {
+            return false;
+        };
+
+        if self.is_chess960() {
+            self.piece_at(chess_move.to).is_some_and(|piece| {
+                piece.colour == king.colour && piece.piece_type == PieceType::Rook
+            })
+        } else {
+            (chess_move.from.file - chess_move.to.file).abs() == 2
+        }
+    }
+
+    /// Returns true if `chess_move` is a pawn taking en passant
+    pub fn is_en_passant(&self, chess_move: &ChessMove) -> bool {
+        Some(chess_move.to) == self.en_passant
+            && self
+                .piece_at(chess_move.from)
+                .is_some_and(|piece| piece.piece_type == PieceType::Pawn)
+            && chess_move.from.file != chess_move.to.file
+    }
+
+    /// Returns the square of the piece taken by `chess_move`, if any
+    ///
+    /// This is the destination of the move unless the move takes en passant, or castles in Chess960
+    pub fn captured_square(&self, chess_move: &ChessMove) -> Option<Square> {
+        if self.is_castling(chess_move) {
+            None
+        } else if self.is_en_passant(chess_move) {
+            Some(Square::new(chess_move.from.rank, chess_move.to.file))
+        } else {
+            self.piece_at(chess_move.to).map(|piece| piece.pos)
+        }
+    }
+
+    /// Returns the squares of the pieces blown up by `chess_move` besides the piece it takes and
+    /// the piece making it, which is every piece other than a pawn next to its destination in
+    /// variants where captures explode
+    pub fn exploded_squares(&self, chess_move: &ChessMove) -> Bitboard {
+        if !self.variant.explodes() || self.captured_square(chess_move).is_none() {
+            return Bitboard::EMPTY;
+        }
+        king_attacks(chess_move.to)
+            & self.occupied()
+            & !self.piece_types[PieceType::Pawn.index()]
+            & !Bitboard::from_square(chess_move.from)
+            & !Bitboard::from_square(chess_move.to)
+    }
+
+    /// Returns the origin and destination of the rook when `chess_move` is castling
+    pub fn castling_rook(&self, chess_move: &ChessMove) -> Option<(Square, Square)> {
+        if !self.is_castling(chess_move) {
+            return None;
+        }
+
+        let rank = chess_move.from.rank;
+        let colour = self.piece_at(chess_move.from)?.colour;
+        let (kingside_file, queenside_file) = self.castling_files(colour);
+        if chess_move.to.file > chess_move.from.file {
+            Some((Square::new(rank, kingside_file), Square::new(rank, F_FILE)))
+        } else {
+            Some((Square::new(rank, queenside_file), Square::new(rank, D_FILE)))
+        }
+    }
+
+    /// Returns the square the moving piece finishes on
+    ///
+    /// This is the destination of the move, except when castling in Chess960 where the King
+    /// finishes on the c- or g-file rather than on its rook's square
+    pub fn destination(&self, chess_move: &ChessMove) -> Square {
+        self.castling_rook(chess_move)
+            .map_or(chess_move.to, |(_, rook_to)| king_destination(rook_to))
+    }
+
+    /// Applies `chess_move` to the position, returning the state required to reverse it
+    ///
+    /// The move is assumed to be legal. In variants with pockets a piece that is taken goes to the
+    /// pocket of the side that took it, as a pawn if it had been promoted, and in variants where
+    /// captures explode the pieces around the capture are removed along with the capturing piece
+    pub fn make_move(&mut self, chess_move: ChessMove) -> Undo {
+        let rook_move = self.castling_rook(&chess_move);
+        let destination = self.destination(&chess_move);
+        let exploded_squares = self.exploded_squares(&chess_move);
+        let undo = Undo {
+            captured: None,
+            exploded: [None; 9],
+            castling: self.castling,
+            castling_rook: rook_move,
+            en_passant: self.en_passant,
+            halfmove_clock: self.halfmove_clock,
+            checks: self.checks,
+            pockets: self.pockets,
+            promoted: self.promoted,
+            key: self.key,
+        };
+        self.key ^= self.state_key();
+
+        if let Some(piece_type) = chess_move.drop {
+            self.drop_piece(piece_type, chess_move.to);
+            self.finish_move();
+            return undo;
+        }
+
+        let captured = self
+            .captured_square(&chess_move)
+            .map(|square| self.remove_piece(square));
+        if let Some(captured) = captured.filter(|_| self.variant.has_pockets()) {
+            let piece_type = if self.promoted.contains(captured.pos) {
+                PieceType::Pawn
+            } else {
+                captured.piece_type
+            };
+            self.pockets[self.side_to_move.index()][piece_type.index()] += 1;
+            self.promoted &= !Bitboard::from_square(captured.pos);
+        }
+
+        let mut moving_piece = self.remove_piece(chess_move.from);
+        self.update_castling_rights(&moving_piece, &chess_move);
+        self.en_passant = (moving_piece.piece_type == PieceType::Pawn
+            && (chess_move.from.rank - chess_move.to.rank).abs() == 2)
+            .then(|| {
+                Square::new(
+                    (chess_move.from.rank + chess_move.to.rank) / 2,
+                    chess_move.from.file,
+                )
+            });
+
+        if moving_piece.piece_type == PieceType::Pawn || captured.is_some() {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+
+        // the rook is lifted before the King is put down, as in Chess960 either may finish on the
+        // square the other started on
+        let rook = rook_move.map(|(rook_from, rook_to)| self.lift_piece(rook_from, rook_to));
+        moving_piece.pos = destination;
+        if let Some(promotion) = chess_move.promotion {
+            moving_piece.piece_type = promotion;
+        }
+        if self.variant.has_pockets()
+            && (chess_move.promotion.is_some() || self.promoted.contains(chess_move.from))
+        {
+            self.promoted &= !Bitboard::from_square(chess_move.from);
+            self.promoted |= Bitboard::from_square(destination);
+        }
+        self.put_piece(moving_piece);
+        if let Some(rook) = rook {
+            self.put_piece(rook);
+        }
+
+        let mut exploded = [None; 9];
+        if self.variant.explodes() && captured.is_some() {
+            let squares = exploded_squares.squares().chain([destination]);
+            for (piece, square) in exploded.iter_mut().zip(squares) {
+                let removed = self.remove_piece(square);
+                if removed.piece_type == PieceType::King {
+                    self.castling.remove(removed.colour, true);
+                    self.castling.remove(removed.colour, false);
+                }
+                self.remove_castling_rights_on(square);
+                *piece = Some(removed);
+            }
+        }
+
+        self.finish_move();
+        Undo {
+            captured,
+            exploded,
+            ..undo
+        }
+    }
+
+    /// Puts a piece of `piece_type` from the pocket of the side to move down on `square`
+    fn drop_piece(&mut self, piece_type: PieceType, square: Square) {
+        let colour = self.side_to_move;
+        self.pockets[colour.index()][piece_type.index()] -= 1;
+        self.put_piece(Piece {
+            colour,
+            piece_type,
+            pos: square,
+        });
+        self.en_passant = None;
+        if piece_type == PieceType::Pawn {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+    }
+
+    /// Passes the move to the other side once the pieces have moved, counting any check given and
+    /// adding the state back into the key
+    fn finish_move(&mut self) {
+        if self.side_to_move == PieceColour::Black {
+            self.fullmove_number += 1;
+        }
+        self.side_to_move = self.side_to_move.opponent();
+        if self.variant.counts_checks() && self.is_in_check(self.side_to_move) {
+            let checks = &mut self.checks[self.side_to_move.opponent().index()];
+            *checks = checks.saturating_add(1);
+        }
+        self.key ^= self.state_key();
+    }
+
+    /// Reverses `chess_move`, which must be the last move made with `make_move`
+    pub fn unmake_move(&mut self, chess_move: ChessMove, undo: Undo) {
+        self.side_to_move = self.side_to_move.opponent();
+        if self.side_to_move == PieceColour::Black {
+            self.fullmove_number -= 1;
+        }
+        self.castling = undo.castling;
+        self.en_passant = undo.en_passant;
+        self.halfmove_clock = undo.halfmove_clock;
+        self.checks = undo.checks;
+        self.pockets = undo.pockets;
+        self.promoted = undo.promoted;
+
+        if chess_move.drop.is_some() {
+            self.remove_piece(chess_move.to);
+            self.key = undo.key;
+            return;
+        }
+
+        for piece in undo.exploded.into_iter().flatten() {
+            self.put_piece(piece);
+        }
+
+        let rook = undo
+            .castling_rook
+            .map(|(rook_from, rook_to)| self.lift_piece(rook_to, rook_from));
+        let destination = undo
+            .castling_rook
+            .map_or(chess_move.to, |(_, rook_to)| king_destination(rook_to));
+        let mut moving_piece = self.remove_piece(destination);
+        moving_piece.pos = chess_move.from;
+        if chess_move.promotion.is_some() {
+            moving_piece.piece_type = PieceType::Pawn;
+        }
+        self.put_piece(moving_piece);
+        if let Some(rook) = rook {
+            self.put_piece(rook);
+        }
+
+        if let Some(captured) = undo.captured {
+            self.put_piece(captured);
+        }
+        self.key = undo.key;
+    }
+
+    /// Places `piece` on the square given by its `pos`, which must be empty
+    pub(crate) fn put_piece(&mut self, piece: Piece) {
+        let square = Bitboard::from_square(piece.pos);
+        self.colours[piece.colour.index()] |= square;
+        self.piece_types[piece.piece_type.index()] |= square;
+        self.key ^= piece_key(&piece);
+        self.board[piece.pos.index()] = Some(piece);
+    }
+
+    fn remove_piece(&mut self, square: Square) -> Piece {
+        let piece = self.board[square.index()]
+            .take()
+            .expect("no piece on square");
+        let remaining = !Bitboard::from_square(square);
+        self.colours[piece.colour.index()] &= remaining;
+        self.piece_types[piece.piece_type.index()] &= remaining;
+        self.key ^= piece_key(&piece);
+        piece
+    }
+
+    /// Removes the piece on `from`, returning it ready to be put down on `to`
+    fn lift_piece(&mut self, from: Square, to: Square) -> Piece {
+        let mut piece = self.remove_piece(from);
+        piece.pos = to;
+        piece
+    }
+
+    /// Removes any castling right that is lost when a King or Rook leaves its starting square, or a
+    /// Rook is taken on its starting square
+    fn update_castling_rights(&mut self, moving_piece: &Piece, chess_move: &ChessMove) {
+        if moving_piece.piece_type == PieceType::King {
+            self.castling.remove(moving_piece.colour, true);
+            self.castling.remove(moving_piece.colour, false);
+        }
+
+        self.remove_castling_rights_on(chess_move.from);
+        self.remove_castling_rights_on(chess_move.to);
+    }
+
+    /// Removes the castling right of the Rook that starts on `square`, if any
+    fn remove_castling_rights_on(&mut self, square: Square) {
+        for (colour, rank) in [(PieceColour::White, RANK_1), (PieceColour::Black, RANK_8)] {
+            let (kingside_file, queenside_file) = self.castling_files(colour);
+            if square == Square::new(rank, kingside_file) {
+                self.castling.remove(colour, true);
+            } else if square == Square::new(rank, queenside_file) {
+                self.castling.remove(colour, false);
+            }
+        }
+    }
+
+    /// Tests if making a move will leave the moving side in check. Returns true if a move is 'safe'
+    ///
+    /// Rather than making the move, the occupancy after the move is calculated and the King's
+    /// square is tested against it, ignoring any piece that the move captures
+    pub(crate) fn avoids_check(&self, chess_move: &ChessMove) -> bool {
+        let colour = self.side_to_move;
+        let Some(king) = self.pieces_of(colour, PieceType::King).first() else {
+            return true;
+        };
+        let destination = self.destination(chess_move);
+        let king = if king == chess_move.from {
+            destination
+        } else {
+            king
+        };
+
+        let captured = self
+            .captured_square(chess_move)
+            .map_or(Bitboard::EMPTY, Bitboard::from_square);
+        let mut occupied = (self.occupied() & !Bitboard::from_square(chess_move.from) & !captured)
+            | Bitboard::from_square(destination);
+        if let Some((rook_from, rook_to)) = self.castling_rook(chess_move) {
+            occupied = (occupied & !Bitboard::from_square(rook_from))
+                | Bitboard::from_square(destination)
+                | Bitboard::from_square(rook_to);
+        }
+
+        (self.attackers(king, colour.opponent(), occupied) & !captured).is_empty()
+    }
+
+    /// Calculates every move for the side to move that obeys the movement rules of each piece,
+    /// without considering whether the move leaves the King in check
+    pub fn pseudo_legal_moves(&self) -> Vec<ChessMove> {
+        let colour = self.side_to_move;
+        let occupied = self.occupied();
+        let targets = !self.occupied_by(colour);
+
+        let mut moves = Vec::new();
+        for from in self.pieces_of(colour, PieceType::Pawn).squares() {
+            self.pawn_moves(from, &mut moves);
+        }
+        for from in self.pieces_of(colour, PieceType::King).squares() {
+            push_moves(from, king_attacks(from) & targets, &mut moves);
+            self.castling_moves(from, &mut moves);
+        }
+
+        for from in self.pieces_of(colour, PieceType::Knight).squares() {
+            push_moves(from, knight_attacks(from) & targets, &mut moves);
+        }
+        for from in self.pieces_of(colour, PieceType::Bishop).squares() {
+            push_moves(from, bishop_attacks(from, occupied) & targets, &mut moves);
+        }
+        for from in self.pieces_of(colour, PieceType::Rook).squares() {
+            push_moves(from, rook_attacks(from, occupied) & targets, &mut moves);
+        }
+        for from in self.pieces_of(colour, PieceType::Queen).squares() {
+            push_moves(from, queen_attacks(from, occupied) & targets, &mut moves);
+        }
+        moves
+    }
+
+    /// Pawns may:
+    /// - Move a single step forward onto an empty square
+    /// - Move two steps forward from their starting rank if both squares are empty
+    /// - Take diagonally, including en passant
+    /// - Promote on reaching the last rank
+    fn pawn_moves(&self, from: Square, moves: &mut Vec<ChessMove>) {
+        let colour = self.side_to_move;
+        let direction = colour.pawn_movement_direction();
+        let starting_rank = match colour {
+            PieceColour::White => RANK_2,
+            PieceColour::Black => RANK_7,
+        };
+        let occupied = self.occupied();
+
+        let mut destinations = Bitboard::EMPTY;
+        if let Ok(single_step) = from.try_add((direction, 0)) {
+            if !occupied.contains(single_step) {
+                destinations |= Bitboard::from_square(single_step);
+
+                let double_step = single_step + (direction, 0);
+                if from.rank == starting_rank && !occupied.contains(double_step) {
+                    destinations |= Bitboard::from_square(double_step);
+                }
+            }
+        }
+
+        let mut capturable = self.occupied_by(colour.opponent());
+        if let Some(en_passant) = self.en_passant {
+            capturable |= Bitboard::from_square(en_passant);
+        }
+        destinations |= pawn_attacks(colour, from) & capturable;
+
+        for destination in destinations.squares() {
+            if destination.rank == RANK_1 || destination.rank == RANK_8 {
+                moves.extend(PROMOTION_TYPES.map(|promotion| ChessMove {
+                    promotion: Some(promotion),
+                    ..ChessMove::new(from, destination)
+                }));
+            } else {
+                moves.push(ChessMove::new(from, destination));
+            }
+        }
+    }
+
+    /// Calculates every drop of a piece from the pocket of the side to move onto an empty square,
+    /// without considering whether the move leaves the King in check
+    ///
+    /// Pawns cannot be dropped on the first or last rank, where they could never move
+    pub fn drop_moves(&self) -> Vec<ChessMove> {
+        let colour = self.side_to_move;
+        let empty = !self.occupied();
+        let mut moves = Vec::new();
+        for piece_type in [
+            PieceType::Pawn,
+            PieceType::Knight,
+            PieceType::Bishop,
+            PieceType::Rook,
+            PieceType::Queen,
+        ] {
+            if self.pocket(colour, piece_type) == 0 {
+                continue;
+            }
+            moves.extend(
+                empty
+                    .squares()
+                    .filter(|square| {
+                        piece_type != PieceType::Pawn
+                            || (square.rank != RANK_1 && square.rank != RANK_8)
+                    })
+                    .map(|square| ChessMove::new_drop(piece_type, square)),
+            );
+        }
+        moves
+    }
+
+    /// Legal castling requires:
+    /// - Neither the rook nor the King have moved (i.e. the castling right is still held)
+    /// - The squares the King and the rook pass over or finish on are empty, other than for each
+    ///   other
+    /// - The King is not in check, and does not pass through a threatened square
+    ///
+    /// In Chess960 the King and rooks may start on any file, but finish on the same squares as in
+    /// standard chess. The King's destination is checked along with every other move in
+    /// `avoids_check`
+    fn castling_moves(&self, king: Square, moves: &mut Vec<ChessMove>) {
+        let colour = self.side_to_move;
+        let (kingside, queenside) = self.castling.for_colour(colour);
+        let rank = king.rank;
+        let back_rank = match colour {
+            PieceColour::White => RANK_1,
+            PieceColour::Black => RANK_8,
+        };
+        if rank != back_rank || self.is_attacked(king, colour.opponent()) {
+            return;
+        }
+
+        let rooks = self.pieces_of(colour, PieceType::Rook);
+        let (kingside_file, queenside_file) = self.castling_files(colour);
+        let sides = [
+            (kingside, kingside_file, G_FILE, F_FILE),
+            (queenside, queenside_file, C_FILE, D_FILE),
+        ];
+        for (has_right, rook_file, king_file, rook_destination_file) in sides {
+            let rook = Square::new(rank, rook_file);
+            if !has_right || !rooks.contains(rook) {
+                continue;
+            }
+
+            let king_to = Square::new(rank, king_file);
+            let rook_to = Square::new(rank, rook_destination_file);
+            let occupied =
+                self.occupied() & !Bitboard::from_square(king) & !Bitboard::from_square(rook);
+            let path = between(king, king_to)
+                | Bitboard::from_square(king_to)
+                | between(rook, rook_to)
+                | Bitboard::from_square(rook_to);
+            let threatened = between(king, king_to).squares().any(|square| {
+                !self
+                    .attackers(square, colour.opponent(), occupied)
+                    .is_empty()
+            });
+
+            if (path & occupied).is_empty() && !threatened {
+                let to = if self.is_chess960() { rook } else { king_to };
+                moves.push(ChessMove::new(king, to));
+            }
+        }
+    }
+
+    /// Returns true if any piece of colour `by` attacks `square`
+    ///
+    /// Note the subtle distinction between attacking and being able to legally move: a pinned piece
+    /// still attacks the squares it could move to if it were not pinned
+    pub fn is_attacked(&self, square: Square, by: PieceColour) -> bool {
+        !self.attackers(square, by, self.occupied()).is_empty()
+    }
+
+    /// The pieces of colour `by` that attack `square`, with sliding pieces blocked by `occupied`
+    ///
+    /// Each attack is found in reverse: a Knight on `square` would attack exactly the squares
+    /// that Knights attacking `square` stand on, and likewise for the other piece types
+    fn attackers(&self, square: Square, by: PieceColour, occupied: Bitboard) -> Bitboard {
+        let diagonal = self.pieces_of(by, PieceType::Bishop) | self.pieces_of(by, PieceType::Queen);
+        let straight = self.pieces_of(by, PieceType::Rook) | self.pieces_of(by, PieceType::Queen);
+
+        (knight_attacks(square) & self.pieces_of(by, PieceType::Knight))
+            | (king_attacks(square) & self.pieces_of(by, PieceType::King))
+            | (pawn_attacks(by.opponent(), square) & self.pieces_of(by, PieceType::Pawn))
+            | (bishop_attacks(square, occupied) & diagonal)
+            | (rook_attacks(square, occupied) & straight)
+    }
+}
+
+/// The square the King finishes on when castling with the rook that finishes on `rook_to`
+fn king_destination(rook_to: Square) -> Square {
+    let file = if rook_to.file == F_FILE {
+        G_FILE
+    } else {
+        C_FILE
+    };
+    Square::new(rook_to.rank, file)
+}
+
+/// Adds a move from `from` to every square in `destinations`
+fn push_moves(from: Square, destinations: Bitboard, moves: &mut Vec<ChessMove>) {
+    moves.extend(
+        destinations
+            .squares()
+            .map(|destination| ChessMove::new(from, destination)),
+    );
+}
@@ -0,0 +1,196 @@
+use crate::{ChessMove, PieceType, Position, Square, Status, D_FILE, F_FILE};
+
+impl Position {
+    /// Returns `chess_move` in Standard Algebraic Notation, e.g. "Nbd7", "exd6", "O-O", "e8=Q+" or
+    /// a drop such as "N@f3"
+    ///
+    /// The move is assumed to be legal. Check and mate are only marked in variants that have check
+    pub fn to_san(&self, chess_move: &ChessMove) -> String {
+        let mut san = self.san_without_suffix(chess_move);
+        if !self.variant.has_check() {
+            return san;
+        }
+
+        let mut position = self.clone();
+        position.make_move(*chess_move);
+        if position.is_in_check(position.side_to_move) {
+            if position.status() == Status::Checkmate {
+                san.push('#');
+            } else {
+                san.push('+');
+            }
+        }
+        san
+    }
+
+    fn san_without_suffix(&self, chess_move: &ChessMove) -> String {
+        if let Some(piece_type) = chess_move.drop {
+            return format!("{}@{}", piece_type.fen_letter(), chess_move.to);
+        }
+        if let Some((_, rook_to)) = self.castling_rook(chess_move) {
+            return if rook_to.file == F_FILE {
+                "O-O".to_string()
+            } else {
+                "O-O-O".to_string()
+            };
+        }
+
+        let piece_type = self
+            .piece_at(chess_move.from)
+            .expect("no piece to move")
+            .piece_type;
+        let capture = if self.captured_square(chess_move).is_some() {
+            "x"
+        } else {
+            ""
+        };
+        let promotion = chess_move.promotion.map_or_else(String::new, |piece_type| {
+            format!("={}", piece_type.notation_letter())
+        });
+
+        let origin = if piece_type == PieceType::Pawn {
+            // pawns are only identified by their file when taking
+            if capture.is_empty() {
+                String::new()
+            } else {
+                chess_move.from.file_annotation()
+            }
+        } else {
+            format!(
+                "{}{}",
+                piece_type.notation_letter(),
+                self.disambiguation(chess_move, piece_type)
+            )
+        };
+
+        format!("{origin}{capture}{}{promotion}", chess_move.to)
+    }
+
+    /// Identifies the moving piece when another piece of the same type can move to the same square,
+    /// preferring the file, then the rank and finally the whole square
+    fn disambiguation(&self, chess_move: &ChessMove, piece_type: PieceType) -> String {
+        let others: Vec<Square> = self
+            .legal_moves()
+            .into_iter()
+            .filter(|other| {
+                other.to == chess_move.to
+                    && other.from != chess_move.from
+                    && self
+                        .piece_at(other.from)
+                        .is_some_and(|piece| piece.piece_type == piece_type)
+            })
+            .map(|other| other.from)
+            .collect();
+
+        let from = chess_move.from;
+        if others.is_empty() {
+            String::new()
+        } else if others.iter().all(|other| other.file != from.file) {
+            from.file_annotation()
+        } else if others.iter().all(|other| other.rank != from.rank) {
+            from.rank_annotation()
+        } else {
+            from.to_string()
+        }
+    }
+
+    /// Finds the legal move described by `san` in Standard Algebraic Notation
+    ///
+    /// Check and mate markers and annotations such as "!?" are ignored, castling may be written
+    /// with zeros and unnecessary disambiguation is accepted. A pawn drop may leave out the 'P',
+    /// as in "@e4", and a promotion the '=', as in "e8Q"
+    pub fn parse_san(&self, san: &str) -> Result<ChessMove, String> {
+        let body = san.trim_end_matches(['+', '#', '!', '?']);
+
+        if let Some((piece, square)) = body.split_once('@') {
+            let piece_type = match piece {
+                "" => Some(PieceType::Pawn),
+                letter => letter.chars().next().and_then(PieceType::from_fen_letter),
+            }
+            .filter(|_| piece.len() <= 1)
+            .ok_or_else(|| format!("invalid move '{san}'"))?;
+            let chess_move = ChessMove::new_drop(piece_type, square.parse()?);
+            return if self.is_legal(&chess_move) {
+                Ok(chess_move)
+            } else {
+                Err(format!("{san} is not a legal move"))
+            };
+        }
+
+        // the side is told by where the rook finishes, as in Chess960 the King may not move far
+        let rook_file = match body {
+            "O-O" | "0-0" => Some(F_FILE),
+            "O-O-O" | "0-0-0" => Some(D_FILE),
+            _ => None,
+        };
+        if let Some(file) = rook_file {
+            return self
+                .legal_moves()
+                .into_iter()
+                .find(|chess_move| {
+                    self.castling_rook(chess_move)
+                        .is_some_and(|(_, rook_to)| rook_to.file == file)
+                })
+                .ok_or_else(|| format!("{san} is not a legal move"));
+        }
+
+        // the '=' may be left out, as in "e8Q", as no other move ends in a capital letter
+        let promotion_split = body.split_once('=').or_else(|| {
+            body.char_indices()
+                .last()
+                .filter(|(_, c)| c.is_ascii_uppercase())
+                .map(|(index, _)| body.split_at(index))
+        });
+        let (body, promotion) = match promotion_split {
+            Some((body, promotion)) => {
+                let piece_type = promotion
+                    .chars()
+                    .next()
+                    .and_then(PieceType::from_fen_letter)
+                    .filter(|&piece_type| piece_type != PieceType::Pawn)
+                    .ok_or_else(|| format!("invalid promotion in '{san}'"))?;
+                (body, Some(piece_type))
+            }
+            None => (body, None),
+        };
+
+        let (piece_type, body) = match body.chars().next().and_then(PieceType::from_fen_letter) {
+            Some(piece_type) if piece_type != PieceType::Pawn => (piece_type, &body[1..]),
+            _ => (PieceType::Pawn, body),
+        };
+
+        if body.len() < 2 || !body.is_char_boundary(body.len() - 2) {
+            return Err(format!("invalid move '{san}'"));
+        }
+        let (origin, destination) = body.split_at(body.len() - 2);
+        let destination: Square = destination.parse()?;
+
+        let mut from_file = None;
+        let mut from_rank = None;
+        for c in origin.chars() {
+            match c {
+                'a'..='h' => from_file = Some(c as i8 - 'a' as i8),
+                '1'..='8' => from_rank = Some(c as i8 - '1' as i8),
+                'x' | ':' | '-' => {}
+                _ => return Err(format!("invalid move '{san}'")),
+            }
+        }
+
+        let mut candidates = self.legal_moves().into_iter().filter(|chess_move| {
+            chess_move.to == destination
+                && chess_move.drop.is_none()
+                && chess_move.promotion == promotion
+                && from_file.map_or(true, |file| chess_move.from.file == file)
+                && from_rank.map_or(true, |rank| chess_move.from.rank == rank)
+                && self
+                    .piece_at(chess_move.from)
+                    .is_some_and(|piece| piece.piece_type == piece_type)
+        });
+
+        match (candidates.next(), candidates.next()) {
+            (Some(chess_move), None) => Ok(chess_move),
+            (Some(_), Some(_)) => Err(format!("{san} is ambiguous")),
+            (None, _) => Err(format!("{san} is not a legal move")),
+        }
+    }
+}
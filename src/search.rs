@@ -0,0 +1,328 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::{ChessMove, Game, PieceType, Position, Status};
+
+/// The score of checkmate in the position being searched, reduced by a point for each move it
+/// takes to reach it so that quicker mates are preferred
+const MATE: i32 = 30_000;
+/// Scores further from zero than this are checkmates
+const MATE_THRESHOLD: i32 = MATE - 1_000;
+const INFINITY: i32 = MATE + 1;
+/// The deepest a search will go, however much time it is given
+const MAX_DEPTH: u32 = 64;
+/// How many nodes are searched between checks of the limits
+const CHECK_INTERVAL: u64 = 1_024;
+
+/// When a search should stop, whichever limit is reached first
+///
+/// A search with no limits carries on until it is stopped or reaches `MAX_DEPTH`
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct SearchLimits {
+    pub depth: Option<u32>,
+    pub time: Option<Duration>,
+    pub nodes: Option<u64>,
+}
+
+/// How good a position is for the side to move
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub enum Score {
+    Centipawns(i32),
+    /// The number of moves until checkmate, negative when the side to move is being mated
+    Mate(i32),
+}
+
+impl Score {
+    fn from_search(score: i32) -> Score {
+        if score > MATE_THRESHOLD {
+            Score::Mate((MATE - score + 1) / 2)
+        } else if score < -MATE_THRESHOLD {
+            Score::Mate(-(MATE + score + 1) / 2)
+        } else {
+            Score::Centipawns(score)
+        }
+    }
+}
+
+/// The result of searching to a depth
+#[derive(Clone)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct SearchInfo {
+    pub depth: u32,
+    pub score: Score,
+    pub nodes: u64,
+    pub time: Duration,
+    /// The moves both sides are expected to play, starting with the best move
+    pub pv: Vec<ChessMove>,
+}
+
+impl SearchInfo {
+    pub fn best_move(&self) -> ChessMove {
+        self.pv[0]
+    }
+}
+
+impl Game {
+    /// Searches for the best move for the side to move, with iterative deepening alpha-beta
+    /// search and a quiescence search of captures at the leaves
+    ///
+    /// `report` is called as each depth is completed, and the search ends early once `stop` is
+    /// set. The result of the deepest search to complete is returned, or `None` if there are no
+    /// legal moves. At least one depth is always completed, so there is a move to play
+    pub fn search(
+        &self,
+        limits: SearchLimits,
+        stop: &AtomicBool,
+        mut report: impl FnMut(&SearchInfo),
+    ) -> Option<SearchInfo> {
+        if self.legal_moves().is_empty() {
+            return None;
+        }
+
+        let mut searcher = Searcher {
+            position: self.position().clone(),
+            keys: self.keys().to_vec(),
+            limits,
+            stop,
+            start: Instant::now(),
+            nodes: 0,
+            can_stop: false,
+            stopped: false,
+            best_moves: HashMap::new(),
+        };
+
+        let max_depth = limits.depth.unwrap_or(MAX_DEPTH).clamp(1, MAX_DEPTH);
+        let mut result = None;
+        for depth in 1..=max_depth {
+            let score = searcher.alpha_beta(depth, 0, -INFINITY, INFINITY);
+            if searcher.stopped {
+                break;
+            }
+
+            let info = SearchInfo {
+                depth,
+                score: Score::from_search(score),
+                nodes: searcher.nodes,
+                time: searcher.start.elapsed(),
+                pv: searcher.principal_variation(depth),
+            };
+            report(&info);
+            result = Some(info);
+            searcher.can_stop = true;
+
+            // there is no point looking any deeper once a forced mate is found
+            if score.abs() > MATE_THRESHOLD {
+                break;
+            }
+        }
+        result
+    }
+}
+
+struct Searcher<'a> {
+    position: Position,
+    /// The key of every position in the game up to the one being searched, to find repetitions
+    keys: Vec<u64>,
+    limits: SearchLimits,
+    stop: &'a AtomicBool,
+    start: Instant,
+    nodes: u64,
+    /// The first depth is always completed, so there is a move to play
+    can_stop: bool,
+    stopped: bool,
+    /// The best move found in each position, which is searched first the next time the position
+    /// is reached to make cutoffs more likely
+    best_moves: HashMap<u64, ChessMove>,
+}
+
+impl Searcher<'_> {
+    fn alpha_beta(&mut self, depth: u32, ply: i32, mut alpha: i32, beta: i32) -> i32 {
+        if ply > 0 && self.is_draw() {
+            return 0;
+        }
+        if depth == 0 {
+            return self.quiescence(ply, alpha, beta);
+        }
+        if self.should_stop() {
+            return 0;
+        }
+        self.nodes += 1;
+
+        let mut moves = self.position.legal_moves();
+        if moves.is_empty() {
+            return self.no_moves_score(ply);
+        }
+
+        self.order_moves(&mut moves);
+        let mut best_score = -INFINITY;
+        let mut best_move = moves[0];
+        for chess_move in moves {
+            let score = -self.search_move(chess_move, |searcher| {
+                searcher.alpha_beta(depth - 1, ply + 1, -beta, -alpha)
+            });
+            if self.stopped {
+                return 0;
+            }
+
+            if score > best_score {
+                best_score = score;
+                best_move = chess_move;
+            }
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        self.best_moves.insert(self.position.key(), best_move);
+        best_score
+    }
+
+    /// Searches captures and promotions until the position is quiet, so the evaluation is not
+    /// taken in the middle of an exchange
+    ///
+    /// The side to move can stand pat rather than make a bad capture, unless in check, when every
+    /// move is searched instead
+    fn quiescence(&mut self, ply: i32, mut alpha: i32, beta: i32) -> i32 {
+        if self.should_stop() {
+            return 0;
+        }
+        self.nodes += 1;
+
+        let mut moves = self.position.legal_moves();
+        let in_check = self.position.variant().has_check()
+            && self.position.is_in_check(self.position.side_to_move());
+        if moves.is_empty() {
+            return self.no_moves_score(ply);
+        }
+
+        if !in_check {
+            let stand_pat = self.position.evaluate();
+            if stand_pat >= beta || ply as u32 >= MAX_DEPTH * 2 {
+                return stand_pat;
+            }
+            alpha = alpha.max(stand_pat);
+            moves.retain(|chess_move| {
+                chess_move.promotion.is_some()
+                    || self.position.captured_square(chess_move).is_some()
+            });
+        }
+
+        self.order_moves(&mut moves);
+        let mut best_score = if in_check { -INFINITY } else { alpha };
+        for chess_move in moves {
+            let score = -self.search_move(chess_move, |searcher| {
+                searcher.quiescence(ply + 1, -beta, -alpha)
+            });
+            if self.stopped {
+                return 0;
+            }
+
+            best_score = best_score.max(score);
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+        best_score
+    }
+
+    /// Makes `chess_move`, scores the new position with `search` and takes the move back
+    fn search_move(&mut self, chess_move: ChessMove, search: impl FnOnce(&mut Self) -> i32) -> i32 {
+        let undo = self.position.make_move(chess_move);
+        self.keys.push(self.position.key());
+        let score = search(self);
+        self.keys.pop();
+        self.position.unmake_move(chess_move, undo);
+        score
+    }
+
+    /// Scores a position with no moves, found `ply` moves into the search: checkmate, a win by
+    /// the rules of the variant or a draw
+    fn no_moves_score(&self, ply: i32) -> i32 {
+        match self.position.status() {
+            Status::Checkmate => ply - MATE,
+            Status::VariantWin(colour) if colour == self.position.side_to_move() => MATE - ply,
+            Status::VariantWin(_) => ply - MATE,
+            _ => 0,
+        }
+    }
+
+    /// Checks for a draw by the fifty-move rule, repetition or the position, which in standard
+    /// chess is a dead position
+    ///
+    /// A position that has occurred once before is scored as a draw, as whichever side could
+    /// improve on it would not repeat it
+    fn is_draw(&self) -> bool {
+        let halfmove_clock = self.position.halfmove_clock() as usize;
+        let current = self.position.key();
+        let repeated = self
+            .keys
+            .iter()
+            .rev()
+            .take(halfmove_clock + 1)
+            .skip(1)
+            .any(|&key| key == current);
+
+        halfmove_clock >= 100 || repeated || self.position.variant().draw(&self.position).is_some()
+    }
+
+    /// Puts the moves most likely to be best first: the best move found before in this position,
+    /// then captures of the most valuable pieces by the least valuable, then promotions
+    fn order_moves(&self, moves: &mut [ChessMove]) {
+        let best_move = self.best_moves.get(&self.position.key());
+        moves.sort_by_cached_key(|chess_move| {
+            if Some(chess_move) == best_move {
+                return i32::MIN;
+            }
+
+            let capture = self
+                .position
+                .captured_square(chess_move)
+                .map_or(0, |square| {
+                    let victim = self
+                        .position
+                        .piece_at(square)
+                        .map_or(PieceType::Pawn, |piece| piece.piece_type);
+                    let attacker = self.position.piece_at(chess_move.from).unwrap().piece_type;
+                    10 * victim.value() - attacker.value() + 10_000
+                });
+            let promotion = chess_move
+                .promotion
+                .map_or(0, |piece_type| piece_type.value());
+            -(capture + promotion)
+        });
+    }
+
+    /// Follows the best moves from the position being searched
+    fn principal_variation(&self, depth: u32) -> Vec<ChessMove> {
+        let mut position = self.position.clone();
+        let mut pv = Vec::new();
+        while let Some(&chess_move) = self.best_moves.get(&position.key()) {
+            if pv.len() >= depth as usize || !position.is_legal(&chess_move) {
+                break;
+            }
+            position.make_move(chess_move);
+            pv.push(chess_move);
+        }
+        pv
+    }
+
+    /// Checks whether the search has been stopped, and every `CHECK_INTERVAL` nodes whether it
+    /// has run out of time or nodes
+    fn should_stop(&mut self) -> bool {
+        if self.stopped || !self.can_stop {
+            return self.stopped;
+        }
+
+        let limits = self.limits;
+        self.stopped = self.stop.load(Ordering::Relaxed)
+            || (self.nodes % CHECK_INTERVAL == 0
+                && (limits.nodes.is_some_and(|nodes| self.nodes >= nodes)
+                    || limits.time.is_some_and(|time| self.start.elapsed() >= time)));
+        self.stopped
+    }
+}
@@ -0,0 +1,253 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::variant::{variant_by_name, Standard, Variant};
+use crate::{Clock, DrawReason, Game, PieceColour, Position, Status, TimeControl};
+
+/// The first line of every session file, followed by the version of the format
+const HEADER: &str = "bevy-chess session";
+/// The version of the format written by `Session`, which is the only version it can read
+pub const SESSION_VERSION: u32 = 1;
+
+/// Everything needed to carry on with a game later: the game itself, who is playing it and the
+/// clock
+///
+/// A session is saved as text, one field to a line after a header giving the version of the
+/// format:
+///
+/// ```text
+/// bevy-chess session 1
+/// white Alice
+/// black Bob
+/// fen rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1
+/// moves e2e4 e7e5 g1f3
+/// offer white
+/// clock fischer 300000 2000 287400 296100 1200
+/// ```
+///
+/// Moves are written in the long algebraic notation used by UCI, so promotions record the piece
+/// chosen. Draw offers and games ended off the board, by resigning, agreeing a draw, claiming a
+/// draw or running out of time, are kept with `offer` and `ending` lines. The clock gives the kind
+/// of time control, the time each player started with, the increment or delay, the time each
+/// player has left and the time spent on the move being made, all in milliseconds. Games of a
+/// variant other than standard chess start with a `variant` line naming it, e.g.
+/// `variant kingofthehill`
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct Session {
+    pub game: Game,
+    pub white: String,
+    pub black: String,
+    pub clock: Option<Clock>,
+}
+
+impl Session {
+    /// The way the game ended that cannot be worked out again from its moves, if any
+    fn ending(&self) -> Option<String> {
+        match self.game.status() {
+            Status::Resigned(colour) => Some(format!("resigned {}", colour_name(colour))),
+            Status::OutOfTime | Status::Draw(DrawReason::OutOfTimeWithInsufficientMaterial) => {
+                Some("out-of-time".to_string())
+            }
+            Status::Draw(DrawReason::Agreement) => Some("agreement".to_string()),
+            Status::Draw(DrawReason::ThreefoldRepetition | DrawReason::FiftyMoveRule) => {
+                Some("claim".to_string())
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Display for Session {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{HEADER} {SESSION_VERSION}")?;
+        // names are kept to one line so they cannot be mistaken for other fields
+        writeln!(f, "white {}", self.white.replace(['\r', '\n'], " "))?;
+        writeln!(f, "black {}", self.black.replace(['\r', '\n'], " "))?;
+        let variant = self.game.starting_position().variant();
+        if !variant.is_standard() {
+            writeln!(f, "variant {}", variant.uci_name())?;
+        }
+        writeln!(f, "fen {}", self.game.starting_position().to_fen())?;
+
+        let moves: Vec<_> = self.game.moves().map(ToString::to_string).collect();
+        writeln!(f, "moves {}", moves.join(" "))?;
+
+        if let Some(ending) = self.ending() {
+            writeln!(f, "ending {ending}")?;
+        } else if let Some(colour) = self.game.draw_offer() {
+            writeln!(f, "offer {}", colour_name(colour))?;
+        }
+
+        if let Some(clock) = &self.clock {
+            let (kind, bonus) = match clock.time_control() {
+                TimeControl::SuddenDeath { .. } => ("sudden-death", Duration::ZERO),
+                TimeControl::Fischer { increment, .. } => ("fischer", increment),
+                TimeControl::Bronstein { delay, .. } => ("bronstein", delay),
+                TimeControl::SimpleDelay { delay, .. } => ("delay", delay),
+            };
+            writeln!(
+                f,
+                "clock {kind} {} {} {} {} {}",
+                clock.time_control().time().as_millis(),
+                bonus.as_millis(),
+                clock.remaining(PieceColour::White).as_millis(),
+                clock.remaining(PieceColour::Black).as_millis(),
+                clock.spent().as_millis()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Session {
+    type Err = String;
+
+    /// Reads a session, checking every move is legal and that the ending could have happened
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s
+            .lines()
+            .map(str::trim)
+            .enumerate()
+            .filter(|(_, line)| !line.is_empty());
+
+        let version = lines
+            .next()
+            .and_then(|(_, line)| line.strip_prefix(HEADER))
+            .ok_or_else(|| "not a session file".to_string())?
+            .trim();
+        if version != SESSION_VERSION.to_string() {
+            return Err(format!("unsupported session version '{version}'"));
+        }
+
+        let mut white = "?".to_string();
+        let mut black = "?".to_string();
+        let mut variant: &'static dyn Variant = &Standard;
+        let mut fen = None;
+        let mut moves = Vec::new();
+        let mut offer = None;
+        let mut ending = None;
+        let mut clock = None;
+        for (index, line) in lines {
+            let (field, value) = line.split_once(' ').unwrap_or((line, ""));
+            let value = value.trim();
+            let result = match field {
+                "white" => {
+                    white = value.to_string();
+                    Ok(())
+                }
+                "black" => {
+                    black = value.to_string();
+                    Ok(())
+                }
+                "variant" => variant_by_name(value)
+                    .map(|parsed| variant = parsed)
+                    .ok_or_else(|| format!("unknown variant '{value}'")),
+                "fen" => {
+                    fen = Some((index, value));
+                    Ok(())
+                }
+                "moves" => {
+                    moves = value.split_whitespace().collect();
+                    Ok(())
+                }
+                "offer" => parse_colour(value).map(|colour| offer = Some(colour)),
+                "ending" => {
+                    ending = Some(value);
+                    Ok(())
+                }
+                "clock" => parse_clock(value).map(|parsed| clock = Some(parsed)),
+                _ => Err(format!("unknown field '{field}'")),
+            };
+            result.map_err(|err| format!("line {}: {err}", index + 1))?;
+        }
+
+        // the FEN is read once the variant is known, as the variant decides which are valid
+        let position = match fen {
+            Some((index, fen)) => Position::from_fen(fen, variant)
+                .map_err(|err| format!("line {}: {err}", index + 1))?,
+            None => Position::starting(variant),
+        };
+        let mut game = Game::new(position);
+        for uci in moves {
+            let chess_move = game.position().parse_uci(uci)?;
+            game.make_move(chess_move)?;
+        }
+        if let Some(colour) = offer {
+            game.offer_draw(colour)?;
+        }
+        if let Some(ending) = ending {
+            end_game(&mut game, ending)?;
+        }
+
+        Ok(Session {
+            game,
+            white,
+            black,
+            clock,
+        })
+    }
+}
+
+fn colour_name(colour: PieceColour) -> &'static str {
+    match colour {
+        PieceColour::White => "white",
+        PieceColour::Black => "black",
+    }
+}
+
+fn parse_colour(colour: &str) -> Result<PieceColour, String> {
+    match colour {
+        "white" => Ok(PieceColour::White),
+        "black" => Ok(PieceColour::Black),
+        _ => Err(format!("invalid colour '{colour}'")),
+    }
+}
+
+fn parse_clock(clock: &str) -> Result<Clock, String> {
+    let invalid = || format!("invalid clock '{clock}'");
+    let words: Vec<_> = clock.split_whitespace().collect();
+    let [kind, time, bonus, white, black, spent] = words[..] else {
+        return Err(invalid());
+    };
+    let millis = |value: &str| {
+        value
+            .parse()
+            .map(Duration::from_millis)
+            .map_err(|_| invalid())
+    };
+
+    let time = millis(time)?;
+    let bonus = millis(bonus)?;
+    let time_control = match kind {
+        "sudden-death" => TimeControl::SuddenDeath { time },
+        "fischer" => TimeControl::Fischer {
+            time,
+            increment: bonus,
+        },
+        "bronstein" => TimeControl::Bronstein { time, delay: bonus },
+        "delay" => TimeControl::SimpleDelay { time, delay: bonus },
+        _ => return Err(invalid()),
+    };
+    Ok(Clock::resume(
+        time_control,
+        millis(white)?,
+        millis(black)?,
+        millis(spent)?,
+    ))
+}
+
+/// Ends the game the way it ended when it was saved, through the same rules as during play
+fn end_game(game: &mut Game, ending: &str) -> Result<(), String> {
+    match ending.split_once(' ').unwrap_or((ending, "")) {
+        ("resigned", colour) => game.resign(parse_colour(colour)?),
+        ("out-of-time", "") => game.run_out_of_time().map(|_| ()),
+        ("agreement", "") => {
+            let colour = game.position().side_to_move();
+            game.offer_draw(colour)?;
+            game.accept_draw(colour.opponent())
+        }
+        ("claim", "") => game.claim_draw().map(|_| ()),
+        _ => Err(format!("invalid ending '{ending}'")),
+    }
+}
@@ -0,0 +1,171 @@
+use std::fmt::{Display, Formatter};
+use std::ops::Add;
+use std::str::FromStr;
+
+use bevy::prelude::Component;
+
+pub const A_FILE: i8 = 0;
+pub const B_FILE: i8 = 1;
+pub const C_FILE: i8 = 2;
+pub const D_FILE: i8 = 3;
+pub const E_FILE: i8 = 4;
+pub const F_FILE: i8 = 5;
+pub const G_FILE: i8 = 6;
+pub const H_FILE: i8 = 7;
+
+pub const RANK_1: i8 = 0;
+pub const RANK_2: i8 = 1;
+pub const RANK_3: i8 = 2;
+pub const RANK_4: i8 = 3;
+pub const RANK_5: i8 = 4;
+pub const RANK_6: i8 = 5;
+pub const RANK_7: i8 = 6;
+pub const RANK_8: i8 = 7;
+
+#[derive(Clone, Copy, Component, PartialEq, Eq, Hash)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct Square {
+    pub rank: i8,
+    pub file: i8,
+}
+
+/// Display the square using algebraic notation
+impl Display for Square {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.file_annotation(), self.rank_annotation())
+    }
+}
+
+/// Parse a square from algebraic notation, e.g. "e4"
+impl FromStr for Square {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        let (Some(file @ 'a'..='h'), Some(rank @ '1'..='8'), None) =
+            (chars.next(), chars.next(), chars.next())
+        else {
+            return Err(format!("invalid square '{s}'"));
+        };
+
+        Ok(Square {
+            rank: (rank as u8 - b'1') as i8,
+            file: (file as u8 - b'a') as i8,
+        })
+    }
+}
+
+impl Square {
+    pub fn new(rank: i8, file: i8) -> Square {
+        Square { rank, file }
+    }
+
+    /// The index of the square in a `Bitboard`, counting along each rank from a1 to h8
+    pub fn index(&self) -> usize {
+        (self.rank * 8 + self.file) as usize
+    }
+
+    pub fn from_index(index: u8) -> Square {
+        Square {
+            rank: (index / 8) as i8,
+            file: (index % 8) as i8,
+        }
+    }
+
+    pub fn is_white(&self) -> bool {
+        (self.rank + self.file + 1) % 2 == 0
+    }
+
+    pub fn file_annotation(&self) -> String {
+        match self.file {
+            A_FILE => "a",
+            B_FILE => "b",
+            C_FILE => "c",
+            D_FILE => "d",
+            E_FILE => "e",
+            F_FILE => "f",
+            G_FILE => "g",
+            H_FILE => "h",
+            _ => panic!("impossible file"),
+        }
+        .to_string()
+    }
+
+    pub fn rank_annotation(&self) -> String {
+        (self.rank + 1).to_string()
+    }
+
+    /// Returns true if `other` is adjacent to `self`. Adjacency includes diagonals
+    ///
+    /// Note: returns false if other == self
+    pub fn is_adjacent(&self, other: &Square) -> bool {
+        (self.rank - other.rank).abs() <= 1 && (self.file - other.file).abs() <= 1
+    }
+
+    /// Returns true if `other` is in the same rank as `self`
+    ///
+    /// Note: returns true if other == self
+    pub fn is_same_rank(&self, other: &Square) -> bool {
+        self.rank == other.rank
+    }
+
+    /// Returns true if `other` is in the same file as `self`
+    ///
+    /// Note: returns true if other == self
+    pub fn is_same_file(&self, other: &Square) -> bool {
+        self.file == other.file
+    }
+
+    /// Returns true if `other` is on the same diagonal as `self`
+    ///
+    /// Note: returns true if other == self
+    pub fn is_same_diagonal(&self, other: &Square) -> bool {
+        (self.rank - other.rank).abs() == (self.file - other.file).abs()
+    }
+
+    /// Checks if a square is a valid position on a chess board
+    ///
+    /// True means x and y are both between 0 and 7
+    pub fn is_valid(&self) -> bool {
+        self.rank >= RANK_1 && self.rank <= RANK_8 && self.file >= A_FILE && self.file <= H_FILE
+    }
+
+    /// Fallible add operation
+    ///
+    /// Returns Err(String) if the resulting position would be off the board
+    pub fn try_add(&self, rhs: (i8, i8)) -> Result<Square, String> {
+        let addition = self + rhs;
+        if addition.is_valid() {
+            Ok(addition)
+        } else {
+            Err(String::from("this error message should never be used"))
+        }
+    }
+}
+
+impl Add<(i8, i8)> for Square {
+    type Output = Square;
+
+    // this can be delegated to the impl for &Square but clippy thinks that that's a needless cast
+    #[allow(clippy::op_ref)]
+    fn add(self, rhs: (i8, i8)) -> Self::Output {
+        (&self) + rhs
+    }
+}
+
+impl Add<(i8, i8)> for &Square {
+    type Output = Square;
+
+    fn add(self, (rhs_x, rhs_y): (i8, i8)) -> Self::Output {
+        Square {
+            rank: self.rank + rhs_x,
+            file: self.file + rhs_y,
+        }
+    }
+}
+
+impl From<(i8, i8)> for Square {
+    fn from((x, y): (i8, i8)) -> Self {
+        Square { rank: x, file: y }
+    }
+}
@@ -0,0 +1,37 @@
+use bevy::prelude::*;
+
+/// The phases of the application, which decide the systems that run each frame
+#[derive(States, Clone, Copy, Default, PartialEq, Eq, Hash, Debug)]
+pub enum AppState {
+    /// Shown on launch, and whenever Escape is pressed
+    #[default]
+    MainMenu,
+    /// Waiting for the player to move
+    InGame,
+    /// A pawn has reached the last rank and the player is choosing what to promote it to
+    PromotionPending,
+    /// The game has finished, moves can only be taken back
+    GameOver,
+    /// Stepping through the moves of the game without being able to make new ones
+    Review,
+}
+
+impl AppState {
+    /// The state to return to from reviewing or taking back moves, depending on whether the game
+    /// has finished in the current position
+    pub fn playing(game_over: bool) -> AppState {
+        if game_over {
+            AppState::GameOver
+        } else {
+            AppState::InGame
+        }
+    }
+}
+
+/// Run condition for systems that step backwards and forwards through the moves of the game
+pub fn can_step_through_moves(state: Res<State<AppState>>) -> bool {
+    matches!(
+        state.0,
+        AppState::InGame | AppState::GameOver | AppState::Review
+    )
+}
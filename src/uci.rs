@@ -0,0 +1,374 @@
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+use crate::variant::{Standard, Variant};
+use crate::{ChessMove, Game, PieceType, Position, Score, SearchInfo, SearchLimits, STARTING_FEN};
+
+/// How long an engine has to answer `uci` and `isready`
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// How often the stop flag is checked while waiting for the engine to move
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+impl Position {
+    /// Finds the legal move written in the long algebraic notation used by UCI, e.g. "e2e4" or
+    /// "e7e8q", with castling written as the King's move
+    ///
+    /// Castling may also be written as the King taking its own rook, as it always is in Chess960,
+    /// since engines and GUIs playing Chess960 write every game that way. Promoting to a King,
+    /// which only variants such as Antichess allow, is written "e7e8k", and dropping a piece in
+    /// variants with pockets is written "N@f3"
+    pub fn parse_uci(&self, uci: &str) -> Result<ChessMove, String> {
+        let invalid = || format!("invalid move '{uci}'");
+        if !uci.is_ascii() || !(4..=5).contains(&uci.len()) {
+            return Err(invalid());
+        }
+
+        if let Some((piece, square)) = uci.split_once('@') {
+            let piece_type = piece
+                .chars()
+                .next()
+                .filter(|_| piece.len() == 1)
+                .and_then(|letter| PieceType::from_fen_letter(letter.to_ascii_uppercase()))
+                .filter(|&piece_type| piece_type != PieceType::King)
+                .ok_or_else(invalid)?;
+            let chess_move =
+                ChessMove::new_drop(piece_type, square.parse().map_err(|_| invalid())?);
+            return if self.is_legal(&chess_move) {
+                Ok(chess_move)
+            } else {
+                Err(format!("{uci} is not a legal move"))
+            };
+        }
+
+        let from = uci[0..2].parse().map_err(|_| invalid())?;
+        let to = uci[2..4].parse().map_err(|_| invalid())?;
+        let promotion = match uci[4..].chars().next() {
+            Some(letter) => Some(
+                PieceType::from_fen_letter(letter.to_ascii_uppercase())
+                    .filter(|&piece_type| piece_type != PieceType::Pawn)
+                    .ok_or_else(invalid)?,
+            ),
+            None => None,
+        };
+
+        let chess_move = ChessMove {
+            promotion,
+            ..ChessMove::new(from, to)
+        };
+        if self.is_legal(&chess_move) {
+            return Ok(chess_move);
+        }
+        self.legal_moves()
+            .into_iter()
+            .find(|castling| {
+                promotion.is_none()
+                    && castling.from == from
+                    && self
+                        .castling_rook(castling)
+                        .is_some_and(|(rook_from, _)| rook_from == to)
+            })
+            .ok_or_else(|| format!("{uci} is not a legal move"))
+    }
+}
+
+/// The `position` command that sets up the game reached by playing `moves` from
+/// `starting_position`
+pub fn position_command(starting_position: &Position, moves: &[ChessMove]) -> String {
+    let fen = starting_position.to_fen();
+    let mut command = if fen == STARTING_FEN {
+        "position startpos".to_string()
+    } else {
+        format!("position fen {fen}")
+    };
+
+    if !moves.is_empty() {
+        command.push_str(" moves");
+        for chess_move in moves {
+            command.push_str(&format!(" {chess_move}"));
+        }
+    }
+    command
+}
+
+/// Sets up the game given by a `position` command, `position startpos [moves ...]` or
+/// `position fen <fen> [moves ...]`, played under the rules of `variant` and checking every move
+/// is legal
+pub fn parse_position_command(
+    command: &str,
+    variant: &'static dyn Variant,
+) -> Result<Game, String> {
+    let mut words = command.split_whitespace();
+    if words.next() != Some("position") {
+        return Err(format!("expected a position command, not '{command}'"));
+    }
+
+    let position: Position = match words.next() {
+        Some("startpos") => Position::starting(variant),
+        Some("fen") => {
+            let fen: Vec<_> = words.by_ref().take_while(|&word| word != "moves").collect();
+            Position::from_fen(&fen.join(" "), variant)?
+        }
+        _ => return Err("expected startpos or fen".to_string()),
+    };
+
+    let mut game = Game::new(position);
+    // after a FEN the "moves" keyword has already been taken
+    for word in words.skip_while(|&word| word == "moves") {
+        let chess_move = game.position().parse_uci(word)?;
+        game.make_move(chess_move)?;
+    }
+    Ok(game)
+}
+
+/// The `go` command for a search with `limits`, which searches until told to stop when there are
+/// no limits
+pub fn go_command(limits: &SearchLimits) -> String {
+    let mut command = "go".to_string();
+    if let Some(depth) = limits.depth {
+        command.push_str(&format!(" depth {depth}"));
+    }
+    if let Some(time) = limits.time {
+        command.push_str(&format!(" movetime {}", time.as_millis()));
+    }
+    if let Some(nodes) = limits.nodes {
+        command.push_str(&format!(" nodes {nodes}"));
+    }
+    if limits == &SearchLimits::default() {
+        command.push_str(" infinite");
+    }
+    command
+}
+
+/// Reads an `info` line from an engine searching `position`
+///
+/// Only lines with a score and a principal variation describe a search, others such as
+/// `info currmove` or `info string` give `None`. The principal variation stops at the first move
+/// that is not legal
+pub fn parse_info(position: &Position, line: &str) -> Option<SearchInfo> {
+    let mut words = line.split_whitespace();
+    if words.next() != Some("info") {
+        return None;
+    }
+
+    let mut depth = 0;
+    let mut score = None;
+    let mut nodes = 0;
+    let mut time = Duration::ZERO;
+    let mut pv = Vec::new();
+    while let Some(word) = words.next() {
+        match word {
+            "depth" => depth = words.next()?.parse().ok()?,
+            "nodes" => nodes = words.next()?.parse().ok()?,
+            "time" => time = Duration::from_millis(words.next()?.parse().ok()?),
+            "score" => {
+                let value = |words: &mut std::str::SplitWhitespace| words.next()?.parse().ok();
+                score = match words.next()? {
+                    "cp" => Some(Score::Centipawns(value(&mut words)?)),
+                    "mate" => Some(Score::Mate(value(&mut words)?)),
+                    _ => return None,
+                };
+            }
+            "pv" => {
+                let mut position = position.clone();
+                for uci in words.by_ref() {
+                    let Ok(chess_move) = position.parse_uci(uci) else {
+                        break;
+                    };
+                    position.make_move(chess_move);
+                    pv.push(chess_move);
+                }
+            }
+            // the rest of the line is free text
+            "string" => return None,
+            _ => {}
+        }
+    }
+
+    if pv.is_empty() {
+        return None;
+    }
+    Some(SearchInfo {
+        depth,
+        score: score?,
+        nodes,
+        time,
+        pv,
+    })
+}
+
+/// A chess engine that speaks UCI, running as a child process
+///
+/// The engine is asked to quit when this is dropped
+pub struct UciEngine {
+    name: Option<String>,
+    /// Whether the engine has been told to play Chess960
+    chess960: bool,
+    /// The variant the engine has been told to play
+    variant: &'static dyn Variant,
+    child: Child,
+    stdin: ChildStdin,
+    /// The engine's output, read a line at a time on another thread so reading can time out
+    lines: Receiver<String>,
+}
+
+impl UciEngine {
+    /// Starts the engine at `path` and waits for it to be ready
+    pub fn start(path: &str, args: &[String]) -> Result<UciEngine, String> {
+        let mut child = Command::new(path)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|err| format!("unable to start '{path}': {err}"))?;
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut engine = UciEngine {
+            name: None,
+            chess960: false,
+            variant: &Standard,
+            child,
+            stdin,
+            lines,
+        };
+        engine.send("uci")?;
+        loop {
+            let line = engine.read_line(HANDSHAKE_TIMEOUT)?;
+            if let Some(name) = line.strip_prefix("id name ") {
+                engine.name = Some(name.trim().to_string());
+            } else if line.trim() == "uciok" {
+                break;
+            }
+        }
+        engine.wait_until_ready()?;
+        Ok(engine)
+    }
+
+    /// The name the engine gave itself, if any
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn set_option(&mut self, name: &str, value: &str) -> Result<(), String> {
+        self.send(&format!("setoption name {name} value {value}"))?;
+        self.wait_until_ready()
+    }
+
+    /// Tells the engine the next search is from a different game
+    pub fn new_game(&mut self) -> Result<(), String> {
+        self.send("ucinewgame")?;
+        self.wait_until_ready()
+    }
+
+    /// Asks the engine for its move in the position reached by playing `moves` from
+    /// `starting_position`
+    ///
+    /// `report` is called with each `info` line describing the search. Setting `stop` tells the
+    /// engine to stop searching and move straight away. Engines are switched to Chess960, or back,
+    /// to match the starting position, so that they write castling the same way, and to the
+    /// variant being played with the UCI_Variant option
+    pub fn best_move(
+        &mut self,
+        starting_position: &Position,
+        moves: &[ChessMove],
+        limits: SearchLimits,
+        stop: &AtomicBool,
+        mut report: impl FnMut(&SearchInfo),
+    ) -> Result<ChessMove, String> {
+        let mut position = starting_position.clone();
+        for chess_move in moves {
+            if !position.is_legal(chess_move) {
+                return Err(format!("{chess_move} is not a legal move"));
+            }
+            position.make_move(*chess_move);
+        }
+
+        if starting_position.is_chess960() != self.chess960 {
+            self.chess960 = starting_position.is_chess960();
+            self.set_option("UCI_Chess960", &self.chess960.to_string())?;
+        }
+        let variant = starting_position.variant();
+        if variant.uci_name() != self.variant.uci_name() {
+            self.variant = variant;
+            self.set_option("UCI_Variant", variant.uci_name())?;
+        }
+        self.send(&position_command(starting_position, moves))?;
+        self.send(&go_command(&limits))?;
+
+        let mut stopped = false;
+        loop {
+            let line = match self.lines.recv_timeout(POLL_INTERVAL) {
+                Ok(line) => line,
+                Err(RecvTimeoutError::Timeout) => {
+                    if !stopped && stop.load(Ordering::Relaxed) {
+                        self.send("stop")?;
+                        stopped = true;
+                    }
+                    continue;
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err("the engine stopped running".to_string());
+                }
+            };
+
+            if let Some(info) = parse_info(&position, &line) {
+                report(&info);
+            } else if let Some(rest) = line.strip_prefix("bestmove") {
+                let uci = rest.split_whitespace().next().unwrap_or_default();
+                return position
+                    .parse_uci(uci)
+                    .map_err(|err| format!("the engine played an illegal move: {err}"));
+            }
+        }
+    }
+
+    fn wait_until_ready(&mut self) -> Result<(), String> {
+        self.send("isready")?;
+        while self.read_line(HANDSHAKE_TIMEOUT)?.trim() != "readyok" {}
+        Ok(())
+    }
+
+    fn send(&mut self, command: &str) -> Result<(), String> {
+        writeln!(self.stdin, "{command}")
+            .and_then(|_| self.stdin.flush())
+            .map_err(|err| format!("unable to send '{command}' to the engine: {err}"))
+    }
+
+    fn read_line(&mut self, timeout: Duration) -> Result<String, String> {
+        self.lines.recv_timeout(timeout).map_err(|err| match err {
+            RecvTimeoutError::Timeout => "the engine did not answer in time".to_string(),
+            RecvTimeoutError::Disconnected => "the engine stopped running".to_string(),
+        })
+    }
+}
+
+impl Drop for UciEngine {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+        // give the engine a moment to exit on its own before it is killed
+        for _ in 0..10 {
+            if let Ok(Some(_)) = self.child.try_wait() {
+                return;
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
@@ -1,12 +1,19 @@
+use std::time::Duration;
+
 use bevy::a11y::accesskit::{NodeBuilder, Role};
 use bevy::a11y::AccessibilityNode;
+use bevy::app::AppExit;
 use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
 use bevy::prelude::*;
 
+use crate::board::NewGameEvent;
 use crate::board::{
-    DrawReason, GameStatus, MoveHistory, PlayerTurn, PromotionOutcome, SelectPromotionOutcome,
+    ChessGame, DeadPosition, DrawReason, GameAction, GameStatus, MoveHistory, PlayerTurn,
+    PromotionOutcome, SelectPromotionOutcome,
 };
-use crate::pieces::PieceType;
+use crate::chess_clock::ChessClock;
+use crate::pieces::{PieceColour, PieceType};
+use crate::states::AppState;
 
 pub struct UiPlugin;
 
@@ -18,10 +25,19 @@ impl Plugin for UiPlugin {
         app // new line
             .add_startup_system(init_next_move_text)
             .add_startup_system(display_move_log)
+            .add_startup_system(spawn_game_actions)
+            .add_system(spawn_main_menu.in_schedule(OnEnter(AppState::MainMenu)))
+            .add_system(despawn_main_menu.in_schedule(OnExit(AppState::MainMenu)))
+            .add_system(main_menu_choice.in_set(OnUpdate(AppState::MainMenu)))
+            .add_system(game_action_choice.in_set(OnUpdate(AppState::InGame)))
+            .add_system(show_game_actions)
+            .add_system(open_main_menu.run_if(not(in_state(AppState::MainMenu))))
             .add_system(mouse_scroll)
-            .add_system(make_promotion_choice)
+            .add_system(make_promotion_choice.in_set(OnUpdate(AppState::PromotionPending)))
+            .add_system(despawn_promotion_menu.in_schedule(OnExit(AppState::PromotionPending)))
             .add_system(display_promotion_menu)
             .add_system(next_move_text_update)
+            .add_system(clock_text_update)
             .add_system(update_move_log);
     }
 }
@@ -30,12 +46,33 @@ impl Plugin for UiPlugin {
 #[derive(Component)]
 struct NextMoveText;
 
+/// Marker component for the text showing the time left on the clock
+#[derive(Component)]
+struct ClockText;
+
 /// Marker component for the promotion menu
 #[derive(Component)]
 struct PromotionMenu {
     promoting_entity: Entity,
 }
 
+/// Marker component for the main menu
+#[derive(Component)]
+struct MainMenu;
+
+#[derive(Component, Clone, Copy)]
+enum MainMenuButton {
+    NewGame,
+    Quit,
+}
+
+/// Marker component for the row of buttons that resign, offer, answer or claim a draw
+#[derive(Component)]
+struct GameActions;
+
+#[derive(Component)]
+struct GameActionButton(GameAction);
+
 #[derive(Component)]
 struct PromoteButton {
     piece_type: PieceType,
@@ -54,22 +91,27 @@ fn update_move_log(
     asset_server: Res<AssetServer>,
     move_history: Res<MoveHistory>,
     scroll_list: Query<(Entity, &ScrollingList)>,
-    mut scroll_list_entries: Query<(&MoveNumber, &mut Text)>,
+    mut scroll_list_entries: Query<(Entity, &MoveNumber, &mut Text)>,
     mut max: Local<usize>,
 ) {
     if !move_history.is_changed() {
         return;
     }
 
-    scroll_list_entries.iter_mut().for_each(|(num, mut text)| {
-        let updated_text = move_history.0.get(num.0).unwrap();
-        text.sections[0].value = updated_text.clone();
-    });
+    // entries for moves that have been taken back are removed
+    for (entity, num, mut text) in scroll_list_entries.iter_mut() {
+        match move_history.0.get(num.0) {
+            Some(updated_text) => text.sections[0].value = updated_text.clone(),
+            None => commands.entity(entity).despawn_recursive(),
+        }
+    }
+    *max = (*max).min(move_history.0.len());
 
-    if move_history.0.len() > *max {
-        let (sl_entity, _) = scroll_list.iter().next().unwrap();
+    // a loaded game adds many entries at once
+    let (sl_entity, _) = scroll_list.iter().next().unwrap();
+    while move_history.0.len() > *max {
         commands.entity(sl_entity).with_children(|parent| {
-            create_scroll_list_item(&asset_server, parent, move_history.0.last().unwrap(), *max);
+            create_scroll_list_item(&asset_server, parent, &move_history.0[*max], *max);
         });
         *max += 1;
     }
@@ -230,6 +272,13 @@ fn make_promotion_choice(
     }
 }
 
+/// Removes the promotion menu if the promotion is abandoned, for example by starting a new game
+fn despawn_promotion_menu(mut commands: Commands, menu_query: Query<Entity, With<PromotionMenu>>) {
+    for entity in menu_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
 fn display_promotion_menu(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
@@ -292,30 +341,346 @@ fn spawn_button(asset_server: &Res<AssetServer>, parent: &mut ChildBuilder, piec
         });
 }
 
+fn spawn_main_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::width(Val::Percent(100.0)),
+                    align_self: AlignSelf::Center,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    flex_direction: FlexDirection::Column,
+                    position_type: PositionType::Absolute,
+                    ..default()
+                },
+                ..default()
+            },
+            MainMenu,
+        ))
+        .with_children(|parent| {
+            spawn_main_menu_button(&asset_server, parent, MainMenuButton::NewGame, "New Game");
+            spawn_main_menu_button(&asset_server, parent, MainMenuButton::Quit, "Quit");
+        });
+}
+
+fn spawn_main_menu_button(
+    asset_server: &Res<AssetServer>,
+    parent: &mut ChildBuilder,
+    button: MainMenuButton,
+    label: &str,
+) {
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    size: Size::new(Val::Px(250.0), Val::Px(65.0)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: NORMAL_BUTTON.into(),
+                ..default()
+            },
+            button,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                label,
+                TextStyle {
+                    font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                    font_size: 40.0,
+                    color: Color::rgb(0.9, 0.9, 0.9),
+                },
+            ));
+        });
+}
+
+fn despawn_main_menu(mut commands: Commands, menu_query: Query<Entity, With<MainMenu>>) {
+    for entity in menu_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn main_menu_choice(
+    mut new_game_event: EventWriter<NewGameEvent>,
+    mut exit_event: EventWriter<AppExit>,
+    mut interaction_query: Query<
+        (&Interaction, &mut BackgroundColor, &MainMenuButton),
+        (Changed<Interaction>, With<Button>),
+    >,
+) {
+    for (interaction, mut color, button) in &mut interaction_query {
+        match *interaction {
+            Interaction::Clicked => match button {
+                MainMenuButton::NewGame => new_game_event.send(NewGameEvent),
+                MainMenuButton::Quit => exit_event.send(AppExit),
+            },
+            Interaction::Hovered => {
+                *color = HOVERED_BUTTON.into();
+            }
+            Interaction::None => {
+                *color = NORMAL_BUTTON.into();
+            }
+        }
+    }
+}
+
+/// Spawns the buttons for the actions a player can take other than moving, along the bottom of
+/// the window
+fn spawn_game_actions(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        left: Val::Px(10.0),
+                        bottom: Val::Px(10.0),
+                        ..default()
+                    },
+                    gap: Size::width(Val::Px(10.0)),
+                    display: Display::None,
+                    ..default()
+                },
+                ..default()
+            },
+            GameActions,
+        ))
+        .with_children(|parent| {
+            for (action, label) in [
+                (GameAction::Resign, "Resign"),
+                (GameAction::OfferDraw, "Offer draw"),
+                (GameAction::AcceptDraw, "Accept draw"),
+                (GameAction::DeclineDraw, "Decline draw"),
+                (GameAction::ClaimDraw, "Claim draw"),
+            ] {
+                parent
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                size: Size::new(Val::Px(150.0), Val::Px(40.0)),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            background_color: NORMAL_BUTTON.into(),
+                            ..default()
+                        },
+                        GameActionButton(action),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(
+                            label,
+                            TextStyle {
+                                font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                font_size: 22.0,
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                            },
+                        ));
+                    });
+            }
+        });
+}
+
+/// Only shows the game action buttons while the game is being played
+fn show_game_actions(state: Res<State<AppState>>, mut query: Query<&mut Style, With<GameActions>>) {
+    if !state.is_changed() {
+        return;
+    }
+
+    let playing = matches!(state.0, AppState::InGame | AppState::PromotionPending);
+    for mut style in query.iter_mut() {
+        style.display = if playing {
+            Display::Flex
+        } else {
+            Display::None
+        };
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn game_action_choice(
+    mut game_action: EventWriter<GameAction>,
+    mut interaction_query: Query<
+        (&Interaction, &mut BackgroundColor, &GameActionButton),
+        (Changed<Interaction>, With<Button>),
+    >,
+) {
+    for (interaction, mut color, button) in &mut interaction_query {
+        match *interaction {
+            Interaction::Clicked => game_action.send(button.0),
+            Interaction::Hovered => {
+                *color = HOVERED_BUTTON.into();
+            }
+            Interaction::None => {
+                *color = NORMAL_BUTTON.into();
+            }
+        }
+    }
+}
+
+/// Returns to the main menu when Escape is pressed
+fn open_main_menu(keys: Res<Input<KeyCode>>, mut next_state: ResMut<NextState<AppState>>) {
+    if keys.just_pressed(KeyCode::Escape) {
+        next_state.set(AppState::MainMenu);
+    }
+}
+
 /// Updates the current move text based on the `PlayerTurn` resource
 fn next_move_text_update(
     turn: Res<PlayerTurn>,
     game_status: Res<GameStatus>,
+    game: Res<ChessGame>,
+    state: Res<State<AppState>>,
     mut query: Query<(&mut Text, &NextMoveText)>,
 ) {
-    if !turn.is_changed() && !game_status.is_changed() {
+    if !turn.is_changed() && !game_status.is_changed() && !state.is_changed() {
         return;
     }
 
     let piece_colour = turn.0;
     for (mut text, _) in query.iter_mut() {
         text.sections[0].value = match *game_status {
-            GameStatus::NotStarted => "Next move: White".to_string(),
+            GameStatus::NotStarted => format!("Next move: {piece_colour}"),
             GameStatus::OnGoing => format!("Next move: {piece_colour}"),
             GameStatus::Check => format!("Check! Next move: {piece_colour}"),
             GameStatus::Checkmate => format!("Checkmate! {piece_colour} wins"),
-            GameStatus::Draw(DrawReason::FiftyMoveRule) => {
-                "Draw! Fifty consecutive moves without a capture or a pawn movement".to_string()
+            GameStatus::OutOfTime => format!(
+                "Out of time! {piece_colour} wins as {} ran out of time",
+                piece_colour.opponent()
+            ),
+            GameStatus::Resigned(colour) => {
+                format!("{colour} resigned! {} wins", colour.opponent())
+            }
+            GameStatus::VariantWin(colour) => {
+                let variant = game.0.position().variant();
+                format!(
+                    "{}! {colour} wins by {}",
+                    variant.name(),
+                    variant.win_description()
+                )
+            }
+            GameStatus::Draw(DrawReason::FiftyMoveRule) => format!(
+                "Draw! {piece_colour} claimed fifty moves without a capture or a pawn movement"
+            ),
+            GameStatus::Draw(DrawReason::SeventyFiveMoveRule) => {
+                "Draw! Seventy-five moves without a capture or a pawn movement".to_string()
             }
+            GameStatus::Draw(DrawReason::Agreement) => "Draw! Agreed by both players".to_string(),
             GameStatus::Draw(DrawReason::Stalemate) => {
                 format!("Draw! Stalemate: {piece_colour} has no legal moves")
             }
+            GameStatus::Draw(DrawReason::ThreefoldRepetition) => {
+                format!("Draw! {piece_colour} claimed a threefold repetition")
+            }
+            GameStatus::Draw(DrawReason::FivefoldRepetition) => {
+                "Draw! The same position has occurred five times".to_string()
+            }
+            GameStatus::Draw(DrawReason::DeadPosition(dead_position)) => {
+                format!("Draw! {}", dead_position_text(dead_position))
+            }
+            GameStatus::Draw(DrawReason::OutOfTimeWithInsufficientMaterial) => format!(
+                "Draw! {} ran out of time, but {piece_colour} cannot checkmate",
+                piece_colour.opponent()
+            ),
+            GameStatus::Draw(DrawReason::VariantRule) => {
+                format!("Draw! {}", game.0.position().variant().draw_description())
+            }
         };
+
+        let in_progress = !game_status.is_over();
+        if state.0 == AppState::Review {
+            text.sections[0]
+                .value
+                .push_str(" (reviewing, press V to continue)");
+        } else if let (true, Some(offered_by)) = (in_progress, game.0.draw_offer()) {
+            text.sections[0]
+                .value
+                .push_str(&format!(" ({offered_by} offers a draw)"));
+        } else if let (true, Some(reason)) = (in_progress, game.0.claimable_draw()) {
+            let rule = match reason {
+                DrawReason::FiftyMoveRule => "fifty-move rule",
+                _ => "threefold repetition",
+            };
+            text.sections[0]
+                .value
+                .push_str(&format!(" ({rule}, press D to claim a draw)"));
+        } else if game_status.is_over() {
+            text.sections[0]
+                .value
+                .push_str(" (press N for a new game or V to review)");
+        }
+    }
+}
+
+/// Shows the time each player has left, with the player whose clock is running marked, if the
+/// game is played with a clock
+fn clock_text_update(
+    clock: Res<ChessClock>,
+    turn: Res<PlayerTurn>,
+    game_status: Res<GameStatus>,
+    mut query: Query<&mut Text, With<ClockText>>,
+) {
+    if !clock.is_changed() && !turn.is_changed() {
+        return;
+    }
+
+    let value = match &clock.0 {
+        Some(clock) => {
+            let in_progress = !game_status.is_over();
+            let side = |colour: PieceColour| {
+                let marker = if in_progress && colour == turn.0 {
+                    "> "
+                } else {
+                    ""
+                };
+                format!("{marker}{colour} {}", clock_time(clock.remaining(colour)))
+            };
+            format!(
+                "{}   {}",
+                side(PieceColour::White),
+                side(PieceColour::Black)
+            )
+        }
+        None => String::new(),
+    };
+    for mut text in query.iter_mut() {
+        text.sections[0].value = value.clone();
+    }
+}
+
+/// Formats the time left on a clock, e.g. "1:05:00" or "4:59", with tenths of a second shown in
+/// the last ten seconds, e.g. "0:09.4"
+fn clock_time(remaining: Duration) -> String {
+    let seconds = remaining.as_secs();
+    if seconds >= 3600 {
+        format!(
+            "{}:{:02}:{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        )
+    } else if seconds >= 10 {
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    } else {
+        let tenths = remaining.as_millis() / 100;
+        format!("0:0{}.{}", tenths / 10, tenths % 10)
+    }
+}
+
+fn dead_position_text(dead_position: DeadPosition) -> &'static str {
+    match dead_position {
+        DeadPosition::KingVsKing => "Insufficient material: only the Kings remain",
+        DeadPosition::KingAndBishopVsKing => "Insufficient material: King and Bishop against King",
+        DeadPosition::KingAndKnightVsKing => "Insufficient material: King and Knight against King",
+        DeadPosition::SameColouredBishops => {
+            "Insufficient material: every Bishop is on the same coloured squares"
+        }
+        DeadPosition::BlockedPawns => {
+            "Dead position: the Pawns are locked and the Kings cannot pass"
+        }
     }
 }
 
@@ -327,6 +692,7 @@ fn init_next_move_text(mut commands: Commands, asset_server: ResMut<AssetServer>
         .spawn(NodeBundle {
             style: Style {
                 position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::Column,
                 position: UiRect {
                     left: Val::Px(10.0),
                     top: Val::Px(10.0),
@@ -342,7 +708,7 @@ fn init_next_move_text(mut commands: Commands, asset_server: ResMut<AssetServer>
                     text: Text::from_section(
                         "Next move: White",
                         TextStyle {
-                            font,
+                            font: font.clone(),
                             font_size: 40.0,
                             color: Color::rgb(0.8, 0.8, 0.8),
                         },
@@ -351,5 +717,19 @@ fn init_next_move_text(mut commands: Commands, asset_server: ResMut<AssetServer>
                 },
                 NextMoveText,
             ));
+            parent.spawn((
+                TextBundle {
+                    text: Text::from_section(
+                        "",
+                        TextStyle {
+                            font,
+                            font_size: 30.0,
+                            color: Color::rgb(0.8, 0.8, 0.8),
+                        },
+                    ),
+                    ..Default::default()
+                },
+                ClockText,
+            ));
         });
 }
@@ -0,0 +1,160 @@
+use std::fmt::{Debug, Formatter};
+
+use crate::game::{DrawReason, Status};
+use crate::{ChessMove, PieceColour, Position, STARTING_FEN};
+
+pub use antichess::Antichess;
+pub use atomic::Atomic;
+pub use crazyhouse::Crazyhouse;
+pub use king_of_the_hill::KingOfTheHill;
+pub use racing_kings::RacingKings;
+pub use three_check::ThreeCheck;
+
+mod antichess;
+mod atomic;
+mod crazyhouse;
+mod king_of_the_hill;
+mod racing_kings;
+mod three_check;
+
+/// The rules a game is played by, which can change the starting position, which moves are legal
+/// and how the game is won or drawn
+///
+/// Every method has the standard rules as its default, so a variant only overrides the rules it
+/// changes. A `Position` keeps the variant it is played under, and its `legal_moves` and `status`
+/// defer to it
+pub trait Variant: Sync {
+    /// The name of the variant, as written in the PGN Variant tag
+    fn name(&self) -> &'static str;
+
+    /// The name used for the variant by the UCI_Variant option
+    fn uci_name(&self) -> &'static str;
+
+    fn starting_fen(&self) -> &'static str {
+        STARTING_FEN
+    }
+
+    /// Returns every legal move for the side to move, which is not asked for once there is a
+    /// winner
+    fn legal_moves(&self, position: &Position) -> Vec<ChessMove> {
+        position.standard_legal_moves()
+    }
+
+    /// The player who has won by a rule of the variant, checked before the moves are generated
+    fn winner(&self, _position: &Position) -> Option<PieceColour> {
+        None
+    }
+
+    /// The result when the side to move has no legal moves: checkmate, or otherwise stalemate
+    fn no_moves(&self, position: &Position) -> Status {
+        if position.is_in_check(position.side_to_move()) {
+            Status::Checkmate
+        } else {
+            Status::Draw(DrawReason::Stalemate)
+        }
+    }
+
+    /// A draw decided by the position alone, which by default is a dead position
+    fn draw(&self, position: &Position) -> Option<DrawReason> {
+        position.dead_position().map(DrawReason::DeadPosition)
+    }
+
+    /// Whether each side must have exactly one King, which is not so where the King is an ordinary
+    /// piece that can be taken
+    fn needs_kings(&self) -> bool {
+        true
+    }
+
+    /// Whether attacking the King is check, which must be answered and is marked in SAN
+    fn has_check(&self) -> bool {
+        true
+    }
+
+    /// Whether the number of checks each side has given is kept in the position
+    fn counts_checks(&self) -> bool {
+        false
+    }
+
+    /// Whether pieces that are taken go to the pocket of the side that took them, to be dropped
+    /// back on the board later
+    fn has_pockets(&self) -> bool {
+        false
+    }
+
+    /// Whether a capture blows up the capturing piece along with every piece other than a pawn on
+    /// the squares around it
+    fn explodes(&self) -> bool {
+        false
+    }
+
+    /// Returns false if `colour` could never win, which decides whether running out of time loses
+    /// or draws
+    fn can_win(&self, position: &Position, colour: PieceColour) -> bool {
+        position.can_checkmate(colour)
+    }
+
+    /// How the player who wins by the variant's rules won, e.g. "reaching the centre"
+    fn win_description(&self) -> &'static str {
+        "the rules of the variant"
+    }
+
+    /// Why the game is drawn by `DrawReason::VariantRule`, e.g. "Both Kings reached the eighth
+    /// rank"
+    fn draw_description(&self) -> &'static str {
+        "Drawn by the rules of the variant"
+    }
+}
+
+impl dyn Variant {
+    /// Returns true if the variant is standard chess, which files and protocols leave unnamed
+    pub fn is_standard(&self) -> bool {
+        self.uci_name() == Standard.uci_name()
+    }
+}
+
+impl Debug for dyn Variant {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// The rules of standard chess
+#[derive(Clone, Copy, Default)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct Standard;
+
+impl Variant for Standard {
+    fn name(&self) -> &'static str {
+        "Standard"
+    }
+
+    fn uci_name(&self) -> &'static str {
+        "chess"
+    }
+}
+
+/// Every variant that can be played, starting with standard chess
+pub const VARIANTS: [&dyn Variant; 7] = [
+    &Standard,
+    &KingOfTheHill,
+    &ThreeCheck,
+    &Antichess,
+    &RacingKings,
+    &Crazyhouse,
+    &Atomic,
+];
+
+/// Finds the variant called `name`, which may be its PGN or UCI name in any case and with or
+/// without spaces and hyphens, e.g. "King of the Hill", "kingofthehill" or "three-check"
+pub fn variant_by_name(name: &str) -> Option<&'static dyn Variant> {
+    let simplify = |name: &str| {
+        name.chars()
+            .filter(|c| !matches!(c, ' ' | '-' | '_'))
+            .collect::<String>()
+            .to_lowercase()
+    };
+    let name = simplify(name);
+    VARIANTS
+        .into_iter()
+        .find(|variant| simplify(variant.name()) == name || simplify(variant.uci_name()) == name)
+}
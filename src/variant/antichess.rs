@@ -0,0 +1,71 @@
+use crate::game::{DrawReason, Status};
+use crate::variant::Variant;
+use crate::{ChessMove, PieceColour, PieceType, Position};
+
+/// Each side tries to lose every piece: taking is compulsory, the King is an ordinary piece that
+/// can be taken and promoted to, there is no castling, and a player with no moves left wins
+#[derive(Clone, Copy, Default)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct Antichess;
+
+impl Variant for Antichess {
+    fn name(&self) -> &'static str {
+        "Antichess"
+    }
+
+    fn uci_name(&self) -> &'static str {
+        "antichess"
+    }
+
+    fn starting_fen(&self) -> &'static str {
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1"
+    }
+
+    fn legal_moves(&self, position: &Position) -> Vec<ChessMove> {
+        let mut moves = position.pseudo_legal_moves();
+        moves.retain(|chess_move| !position.is_castling(chess_move));
+
+        let king_promotions: Vec<_> = moves
+            .iter()
+            .filter(|chess_move| chess_move.promotion == Some(PieceType::Queen))
+            .map(|&chess_move| ChessMove {
+                promotion: Some(PieceType::King),
+                ..chess_move
+            })
+            .collect();
+        moves.extend(king_promotions);
+
+        if moves
+            .iter()
+            .any(|chess_move| position.captured_square(chess_move).is_some())
+        {
+            moves.retain(|chess_move| position.captured_square(chess_move).is_some());
+        }
+        moves
+    }
+
+    /// Running out of moves, including by losing every piece, wins
+    fn no_moves(&self, position: &Position) -> Status {
+        Status::VariantWin(position.side_to_move())
+    }
+
+    fn draw(&self, _position: &Position) -> Option<DrawReason> {
+        None
+    }
+
+    fn needs_kings(&self) -> bool {
+        false
+    }
+
+    fn has_check(&self) -> bool {
+        false
+    }
+
+    fn can_win(&self, _position: &Position, _colour: PieceColour) -> bool {
+        true
+    }
+
+    fn win_description(&self) -> &'static str {
+        "running out of moves"
+    }
+}
@@ -0,0 +1,74 @@
+use crate::game::DrawReason;
+use crate::variant::Variant;
+use crate::{ChessMove, DeadPosition, PieceColour, PieceType, Position};
+
+/// Every capture is an explosion that blows up the capturing piece and every piece other than a
+/// pawn next to the square of the capture, and the first player to blow up the other King wins
+///
+/// Kings cannot take, as they would blow themselves up, and a King standing next to the other King
+/// is never in check. A move that blows up the King of the side making it is illegal, even if it
+/// blows up the other King as well
+#[derive(Clone, Copy, Default)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct Atomic;
+
+impl Variant for Atomic {
+    fn name(&self) -> &'static str {
+        "Atomic"
+    }
+
+    fn uci_name(&self) -> &'static str {
+        "atomic"
+    }
+
+    fn legal_moves(&self, position: &Position) -> Vec<ChessMove> {
+        let colour = position.side_to_move();
+        let mut moves = position.pseudo_legal_moves();
+        moves.retain(|&chess_move| {
+            let takes = position.captured_square(&chess_move).is_some();
+            let is_king = position
+                .piece_at(chess_move.from)
+                .is_some_and(|piece| piece.piece_type == PieceType::King);
+            if takes && is_king {
+                return false;
+            }
+
+            let mut after = position.clone();
+            after.make_move(chess_move);
+            let has_king = |colour| !after.pieces_of(colour, PieceType::King).is_empty();
+            has_king(colour) && (!has_king(colour.opponent()) || !after.is_in_check(colour))
+        });
+        moves
+    }
+
+    /// The player whose opponent's King has been blown up
+    fn winner(&self, position: &Position) -> Option<PieceColour> {
+        [PieceColour::White, PieceColour::Black]
+            .into_iter()
+            .find(|colour| {
+                position
+                    .pieces_of(colour.opponent(), PieceType::King)
+                    .is_empty()
+            })
+    }
+
+    /// Any piece can blow up a King by taking a piece next to it, so only bare Kings are dead
+    fn draw(&self, position: &Position) -> Option<DrawReason> {
+        let kings = position.piece_types[PieceType::King.index()];
+        (kings.count() == 2 && position.occupied() == kings)
+            .then_some(DrawReason::DeadPosition(DeadPosition::KingVsKing))
+    }
+
+    fn explodes(&self) -> bool {
+        true
+    }
+
+    /// A player with only their King left can never take anything
+    fn can_win(&self, position: &Position, colour: PieceColour) -> bool {
+        position.occupied_by(colour) != position.pieces_of(colour, PieceType::King)
+    }
+
+    fn win_description(&self) -> &'static str {
+        "blowing up the other King"
+    }
+}
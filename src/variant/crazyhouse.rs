@@ -0,0 +1,49 @@
+use crate::game::DrawReason;
+use crate::variant::Variant;
+use crate::{ChessMove, DeadPosition, PieceColour, PieceType, Position};
+
+/// Standard chess in which a piece that is taken joins the pocket of the side that took it, and a
+/// move can drop a piece from the pocket onto any empty square instead
+///
+/// Pawns cannot be dropped on the first or last rank, and a promoted piece goes back to the pocket
+/// as a pawn. The pockets are written in brackets after the placement in the FEN, e.g. "[Nn]", and
+/// promoted pieces are marked with a '~'
+#[derive(Clone, Copy, Default)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct Crazyhouse;
+
+impl Variant for Crazyhouse {
+    fn name(&self) -> &'static str {
+        "Crazyhouse"
+    }
+
+    fn uci_name(&self) -> &'static str {
+        "crazyhouse"
+    }
+
+    fn starting_fen(&self) -> &'static str {
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[] w KQkq - 0 1"
+    }
+
+    fn legal_moves(&self, position: &Position) -> Vec<ChessMove> {
+        let mut moves = position.pseudo_legal_moves();
+        moves.extend(position.drop_moves());
+        moves.retain(|chess_move| position.avoids_check(chess_move));
+        moves
+    }
+
+    /// Pieces are never out of the game for good, so only bare Kings with empty pockets are dead
+    fn draw(&self, position: &Position) -> Option<DrawReason> {
+        let bare_kings = position.occupied() == position.piece_types[PieceType::King.index()];
+        (bare_kings && position.pockets == [[0; 6]; 2])
+            .then_some(DrawReason::DeadPosition(DeadPosition::KingVsKing))
+    }
+
+    fn has_pockets(&self) -> bool {
+        true
+    }
+
+    fn can_win(&self, position: &Position, _colour: PieceColour) -> bool {
+        self.draw(position).is_none()
+    }
+}
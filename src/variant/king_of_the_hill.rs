@@ -0,0 +1,46 @@
+use crate::game::DrawReason;
+use crate::variant::Variant;
+use crate::{PieceColour, PieceType, Position, D_FILE, E_FILE, RANK_4, RANK_5};
+
+/// Standard chess, which can also be won by bringing the King to one of the four centre squares
+#[derive(Clone, Copy, Default)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct KingOfTheHill;
+
+impl Variant for KingOfTheHill {
+    fn name(&self) -> &'static str {
+        "King of the Hill"
+    }
+
+    fn uci_name(&self) -> &'static str {
+        "kingofthehill"
+    }
+
+    fn winner(&self, position: &Position) -> Option<PieceColour> {
+        // the centre squares are next to each other, so only one King can be there
+        [PieceColour::White, PieceColour::Black]
+            .into_iter()
+            .find(|&colour| {
+                position
+                    .pieces_of(colour, PieceType::King)
+                    .squares()
+                    .any(|king| {
+                        (D_FILE..=E_FILE).contains(&king.file)
+                            && (RANK_4..=RANK_5).contains(&king.rank)
+                    })
+            })
+    }
+
+    /// A lone King can still walk to the centre, so no position is dead
+    fn draw(&self, _position: &Position) -> Option<DrawReason> {
+        None
+    }
+
+    fn can_win(&self, _position: &Position, _colour: PieceColour) -> bool {
+        true
+    }
+
+    fn win_description(&self) -> &'static str {
+        "bringing the King to the centre"
+    }
+}
@@ -0,0 +1,81 @@
+use crate::game::DrawReason;
+use crate::variant::Variant;
+use crate::{ChessMove, PieceColour, PieceType, Position, RANK_8};
+
+/// Both Kings race to the eighth rank, with every piece on the first two ranks at the start and
+/// no move allowed to give check
+///
+/// Black moves second, so when White's King reaches the eighth rank first Black has one move to
+/// reach it too, which draws
+#[derive(Clone, Copy, Default)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct RacingKings;
+
+impl Variant for RacingKings {
+    fn name(&self) -> &'static str {
+        "Racing Kings"
+    }
+
+    fn uci_name(&self) -> &'static str {
+        "racingkings"
+    }
+
+    fn starting_fen(&self) -> &'static str {
+        "8/8/8/8/8/8/krbnNBRK/qrbnNBRQ w - - 0 1"
+    }
+
+    fn legal_moves(&self, position: &Position) -> Vec<ChessMove> {
+        let mut moves = position.standard_legal_moves();
+        moves.retain(|&chess_move| {
+            let mut after = position.clone();
+            after.make_move(chess_move);
+            !after.is_in_check(after.side_to_move())
+        });
+        moves
+    }
+
+    fn winner(&self, position: &Position) -> Option<PieceColour> {
+        match (
+            has_finished(position, PieceColour::White),
+            has_finished(position, PieceColour::Black),
+        ) {
+            (false, true) => Some(PieceColour::Black),
+            (true, false) => {
+                let can_catch_up = position.side_to_move() == PieceColour::Black
+                    && self.legal_moves(position).iter().any(|chess_move| {
+                        chess_move.to.rank == RANK_8
+                            && position
+                                .piece_at(chess_move.from)
+                                .is_some_and(|piece| piece.piece_type == PieceType::King)
+                    });
+                (!can_catch_up).then_some(PieceColour::White)
+            }
+            _ => None,
+        }
+    }
+
+    fn draw(&self, position: &Position) -> Option<DrawReason> {
+        (has_finished(position, PieceColour::White) && has_finished(position, PieceColour::Black))
+            .then_some(DrawReason::VariantRule)
+    }
+
+    fn can_win(&self, _position: &Position, _colour: PieceColour) -> bool {
+        true
+    }
+
+    fn win_description(&self) -> &'static str {
+        "reaching the eighth rank first"
+    }
+
+    fn draw_description(&self) -> &'static str {
+        "Both Kings reached the eighth rank"
+    }
+}
+
+/// Returns true if the King of `colour` is on the eighth rank
+fn has_finished(position: &Position, colour: PieceColour) -> bool {
+    position
+        .pieces_of(colour, PieceType::King)
+        .squares()
+        .any(|king| king.rank == RANK_8)
+}
@@ -0,0 +1,48 @@
+use crate::game::DrawReason;
+use crate::variant::Variant;
+use crate::{DeadPosition, PieceColour, PieceType, Position};
+
+/// The number of checks that wins the game
+const CHECKS_TO_WIN: u8 = 3;
+
+/// Standard chess, which can also be won by giving check three times
+///
+/// The checks each side has given are kept in the position and written at the end of its FEN,
+/// e.g. "+2+1" once White has given two checks and Black one
+#[derive(Clone, Copy, Default)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct ThreeCheck;
+
+impl Variant for ThreeCheck {
+    fn name(&self) -> &'static str {
+        "Three-check"
+    }
+
+    fn uci_name(&self) -> &'static str {
+        "3check"
+    }
+
+    fn winner(&self, position: &Position) -> Option<PieceColour> {
+        [PieceColour::White, PieceColour::Black]
+            .into_iter()
+            .find(|&colour| position.checks(colour) >= CHECKS_TO_WIN)
+    }
+
+    /// Even a single minor piece can give check, so only bare Kings are a dead position
+    fn draw(&self, position: &Position) -> Option<DrawReason> {
+        (position.occupied() == position.piece_types[PieceType::King.index()])
+            .then_some(DrawReason::DeadPosition(DeadPosition::KingVsKing))
+    }
+
+    fn counts_checks(&self) -> bool {
+        true
+    }
+
+    fn can_win(&self, position: &Position, colour: PieceColour) -> bool {
+        position.occupied_by(colour) != position.pieces_of(colour, PieceType::King)
+    }
+
+    fn win_description(&self) -> &'static str {
+        "giving check three times"
+    }
+}
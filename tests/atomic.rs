@@ -0,0 +1,129 @@
+use std::sync::atomic::AtomicBool;
+
+use chess::{
+    Atomic, DeadPosition, DrawReason, Game, PgnGame, PgnTags, PieceColour, Position, Score,
+    SearchLimits, Session, Status,
+};
+use common::position;
+
+mod common;
+
+const WHITE: PieceColour = PieceColour::White;
+
+#[test]
+fn captures_explode_every_piece_around_them_but_pawns() {
+    let mut position = position("4k3/8/8/2nrb3/3p4/2P5/3R4/4K3 w - - 0 1", &Atomic);
+    let fen = position.to_fen();
+    let key = position.key();
+
+    let capture = position.parse_uci("d2d4").unwrap();
+    assert_eq!(position.to_san(&capture), "Rxd4");
+    assert_eq!(position.exploded_squares(&capture).count(), 3);
+    let undo = position.make_move(capture);
+    assert_eq!(position.to_fen(), "4k3/8/8/8/8/2P5/8/4K3 b - - 0 1");
+    assert_eq!(
+        position.key(),
+        self::position(&position.to_fen(), &Atomic).key()
+    );
+
+    position.unmake_move(capture, undo);
+    assert_eq!(position.to_fen(), fen);
+    assert_eq!(position.key(), key);
+}
+
+#[test]
+fn exploded_rooks_lose_their_castling_rights() {
+    let mut position = position("r3k2r/8/8/8/8/8/8/4K2R w Kkq - 0 1", &Atomic);
+    let capture = position.parse_uci("h1h8").unwrap();
+    position.make_move(capture);
+    assert_eq!(position.to_fen(), "r3k3/8/8/8/8/8/8/4K3 b q - 0 1");
+}
+
+#[test]
+fn kings_cannot_take() {
+    let position = position("4k3/8/8/8/8/8/4p3/4K3 w - - 0 1", &Atomic);
+    assert!(position.parse_uci("e1e2").is_err());
+    assert!(position.parse_uci("e1f2").is_ok());
+
+    // taking the Queen would blow up White's own King, so the King has to move away
+    let checked = self::position("8/8/8/8/8/8/3qK3/k2R4 w - - 0 1", &Atomic);
+    assert_eq!(checked.status(), Status::Check);
+    assert!(checked.parse_uci("d1d2").is_err());
+    assert!(checked.parse_uci("e2d2").is_err());
+    assert!(checked.parse_uci("e2f1").is_ok());
+}
+
+#[test]
+fn blowing_up_the_other_king_wins() {
+    let mut game = Game::new(position("4k3/4q3/8/8/8/8/8/4R1K1 w - - 0 1", &Atomic));
+    let capture = game.position().parse_san("Rxe7").unwrap();
+    game.make_move(capture).unwrap();
+    assert_eq!(game.position().to_fen(), "8/8/8/8/8/8/8/6K1 b - - 0 1");
+    assert_eq!(game.status(), Status::VariantWin(WHITE));
+    assert!(game.legal_moves().is_empty());
+    assert_eq!(game.result(), "1-0");
+
+    // two bare Kings can never blow each other up
+    let kings = position("4k3/8/8/8/8/8/8/4K3 w - - 0 1", &Atomic);
+    assert_eq!(
+        kings.status(),
+        Status::Draw(DrawReason::DeadPosition(DeadPosition::KingVsKing))
+    );
+
+    let game = Game::new(position("4k3/4q3/8/8/8/8/8/4R1K1 w - - 0 1", &Atomic));
+    let limits = SearchLimits {
+        depth: Some(2),
+        ..SearchLimits::default()
+    };
+    let info = game
+        .search(limits, &AtomicBool::new(false), |_| {})
+        .unwrap();
+    assert_eq!(info.score, Score::Mate(1));
+}
+
+#[test]
+fn kings_next_to_each_other_are_not_in_check() {
+    let position = position("8/8/8/8/8/8/8/r2Kk3 w - - 0 1", &Atomic);
+    assert!(!position.is_in_check(WHITE));
+    assert_eq!(position.status(), Status::OnGoing);
+
+    // the King can stay next to the other King, but not walk away into the Rook's line
+    assert!(position.parse_uci("d1d2").is_ok());
+    assert!(position.parse_uci("d1c2").is_ok());
+    assert!(position.parse_uci("d1c1").is_err());
+
+    let standard: Position = "8/8/8/8/8/8/8/r2K1k2 w - - 0 1".parse().unwrap();
+    assert!(standard.is_in_check(WHITE));
+}
+
+#[test]
+fn atomic_games_are_saved_and_loaded() {
+    let mut game = Game::new(Position::starting(&Atomic));
+    for san in ["e4", "d5", "exd5"] {
+        let chess_move = game.position().parse_san(san).unwrap();
+        game.make_move(chess_move).unwrap();
+    }
+    assert_eq!(
+        game.position().to_fen(),
+        "rnbqkbnr/ppp1pppp/8/8/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 2"
+    );
+
+    let pgn = game.to_pgn(&PgnTags::default());
+    assert!(pgn.contains("[Variant \"Atomic\"]\n"));
+    assert!(pgn.contains("1. e4 d5 2. exd5"));
+    let games = PgnGame::parse_all(&pgn).unwrap();
+    assert_eq!(games[0].game.position().to_fen(), game.position().to_fen());
+
+    let session = Session {
+        game,
+        white: "Alice".to_string(),
+        black: "Bob".to_string(),
+        clock: None,
+    };
+    let reloaded: Session = session.to_string().parse().unwrap();
+    assert_eq!(reloaded.game.position().variant().name(), "Atomic");
+    assert_eq!(
+        reloaded.game.position().to_fen(),
+        session.game.position().to_fen()
+    );
+}
@@ -0,0 +1,146 @@
+//! The perft results are from the Chess960 positions verified by several engines, listed at
+//! https://www.chessprogramming.org/Chess960_Perft_Results
+
+use std::collections::HashSet;
+
+use chess::{perft, PieceColour, Position, CHESS960_POSITIONS, STARTING_FEN};
+
+fn back_rank(position: &Position) -> String {
+    position.to_fen().split('/').nth(7).unwrap()[..8].to_string()
+}
+
+#[test]
+fn numbered_start_positions() {
+    assert_eq!(Position::chess960(518).unwrap().to_fen(), STARTING_FEN);
+    assert_eq!(
+        Position::chess960(0).unwrap().to_fen(),
+        "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w KQkq - 0 1"
+    );
+    assert_eq!(back_rank(&Position::chess960(959).unwrap()), "RKRNNQBB");
+    assert!(Position::chess960(CHESS960_POSITIONS).is_err());
+}
+
+#[test]
+fn every_start_position_is_different_and_valid() {
+    let mut back_ranks = HashSet::new();
+    for index in 0..CHESS960_POSITIONS {
+        let position = Position::chess960(index).unwrap();
+        let back_rank = back_rank(&position);
+        let files = |letter| back_rank.match_indices(letter).map(|(file, _)| file);
+
+        let bishops: Vec<_> = files('B').collect();
+        assert_ne!(bishops[0] % 2, bishops[1] % 2, "{back_rank}");
+        let rooks: Vec<_> = files('R').collect();
+        let king = files('K').next().unwrap();
+        assert!(rooks[0] < king && king < rooks[1], "{back_rank}");
+        assert!(back_ranks.insert(back_rank));
+    }
+}
+
+#[test]
+fn perft_results() {
+    let positions: [(&str, &[u64]); 2] = [
+        (
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+            &[21, 528, 12_189, 326_672],
+        ),
+        (
+            "2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9",
+            &[21, 807, 18_002, 667_366],
+        ),
+    ];
+    for (fen, expected) in positions {
+        let mut position: Position = fen.parse().unwrap();
+        let before = position.to_fen();
+        for (depth, expected) in (1..).zip(expected) {
+            assert_eq!(
+                perft(&mut position, depth),
+                *expected,
+                "{fen} at depth {depth}"
+            );
+        }
+        assert_eq!(position.to_fen(), before, "position was not restored");
+    }
+}
+
+#[test]
+fn castling_fields() {
+    // Shredder-FEN names every rook by its file, X-FEN only the inner ones
+    let position: Position = "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9"
+        .parse()
+        .unwrap();
+    assert!(position.is_chess960());
+    assert_eq!(position.castling_files(PieceColour::White), (7, 5));
+    assert_eq!(
+        position.to_fen(),
+        "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq - 2 9"
+    );
+
+    let inner = "1r2k3/8/8/8/8/8/8/RR2K3 w Bq - 0 1";
+    let position: Position = inner.parse().unwrap();
+    assert_eq!(position.to_fen(), inner);
+    assert!(position.legal_moves().iter().any(|chess_move| {
+        position.is_castling(chess_move) && chess_move.to.to_string() == "b1"
+    }));
+
+    let standard: Position = "r3k2r/8/8/8/8/8/8/R3K2R w HAha - 0 1".parse().unwrap();
+    assert!(!standard.is_chess960());
+    assert_eq!(standard.to_fen(), "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+    assert!("r3k2r/8/8/8/8/8/8/R3K2R w E - 0 1"
+        .parse::<Position>()
+        .is_err());
+}
+
+#[test]
+fn castling_is_written_as_the_king_taking_its_rook() {
+    let mut position: Position = "rk5r/8/8/8/8/8/8/RK2R3 w KQkq - 0 1".parse().unwrap();
+    let fen = position.to_fen();
+
+    let queenside = position.parse_san("O-O-O").unwrap();
+    assert_eq!(queenside.to_string(), "b1a1");
+    assert_eq!(position.parse_uci("b1a1").unwrap(), queenside);
+    assert_eq!(position.to_san(&queenside), "O-O-O");
+    assert_eq!(position.captured_square(&queenside), None);
+    assert_eq!(position.destination(&queenside).to_string(), "c1");
+
+    let undo = position.make_move(queenside);
+    assert_eq!(position.to_fen(), "rk5r/8/8/8/8/8/8/2KRR3 b kq - 1 1");
+    position.unmake_move(queenside, undo);
+    assert_eq!(position.to_fen(), fen);
+
+    // the King passes over the rook's square on its way from b1 to g1
+    let kingside = position.parse_san("O-O").unwrap();
+    assert_eq!(kingside.to_string(), "b1e1");
+    position.make_move(kingside);
+    assert_eq!(position.to_fen(), "rk5r/8/8/8/8/8/8/R4RK1 b kq - 1 1");
+}
+
+#[test]
+fn castling_may_leave_the_king_or_rook_in_place() {
+    let mut position: Position = "4k3/8/8/8/8/8/8/5nKR w H - 0 1".parse().unwrap();
+    assert!(position.parse_san("O-O").is_err(), "f1 is taken");
+
+    position = "4k3/8/8/8/8/8/8/6KR w H - 0 1".parse().unwrap();
+    let castling = position.parse_san("O-O").unwrap();
+    assert_eq!(castling.to_string(), "g1h1");
+    position.make_move(castling);
+    assert_eq!(position.to_fen(), "4k3/8/8/8/8/8/8/5RK1 b - - 1 1");
+
+    position = "4k3/8/8/8/8/8/8/1RK5 w B - 0 1".parse().unwrap();
+    let castling = position.parse_san("O-O-O").unwrap();
+    assert_eq!(castling.to_string(), "c1b1");
+    position.make_move(castling);
+    assert_eq!(position.to_fen(), "4k3/8/8/8/8/8/8/2KR4 b - - 1 1");
+}
+
+#[test]
+fn castling_through_an_attacked_square_is_illegal() {
+    // the rook on e8 attacks e1, which the King would pass over
+    let position: Position = "1k2r3/8/8/8/8/8/8/RK4R1 w KQ - 0 1".parse().unwrap();
+    assert!(position.parse_san("O-O").is_err());
+    assert!(position.parse_san("O-O-O").is_ok());
+
+    // moving the rook away from b1 would uncover the queen on a1
+    let position: Position = "1k6/8/8/8/8/8/8/qRK5 w B - 0 1".parse().unwrap();
+    assert!(position.parse_san("O-O-O").is_err());
+}
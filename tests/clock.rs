@@ -0,0 +1,151 @@
+use std::time::Duration;
+
+use chess::{Clock, DrawReason, Game, PieceColour, Position, Status, TimeControl};
+
+const WHITE: PieceColour = PieceColour::White;
+const BLACK: PieceColour = PieceColour::Black;
+
+fn secs(seconds: u64) -> Duration {
+    Duration::from_secs(seconds)
+}
+
+#[test]
+fn sudden_death_counts_down() {
+    let mut clock = Clock::new(TimeControl::SuddenDeath { time: secs(60) });
+    clock.tick(WHITE, secs(10));
+    clock.press(WHITE);
+    assert_eq!(clock.remaining(WHITE), secs(50));
+    assert_eq!(clock.remaining(BLACK), secs(60));
+
+    clock.tick(BLACK, secs(70));
+    assert_eq!(clock.remaining(BLACK), Duration::ZERO);
+    assert!(clock.is_flagged(BLACK));
+    assert!(!clock.is_flagged(WHITE));
+}
+
+#[test]
+fn fischer_adds_the_increment_after_each_move() {
+    let mut clock = Clock::new(TimeControl::Fischer {
+        time: secs(60),
+        increment: secs(2),
+    });
+    clock.tick(WHITE, secs(1));
+    clock.press(WHITE);
+    assert_eq!(clock.remaining(WHITE), secs(61));
+
+    clock.tick(BLACK, secs(10));
+    clock.press(BLACK);
+    assert_eq!(clock.remaining(BLACK), secs(52));
+}
+
+#[test]
+fn bronstein_gives_back_time_up_to_the_delay() {
+    let mut clock = Clock::new(TimeControl::Bronstein {
+        time: secs(60),
+        delay: secs(3),
+    });
+    clock.tick(WHITE, secs(2));
+    clock.press(WHITE);
+    assert_eq!(clock.remaining(WHITE), secs(60));
+
+    clock.tick(BLACK, secs(5));
+    clock.tick(BLACK, secs(5));
+    clock.press(BLACK);
+    assert_eq!(clock.remaining(BLACK), secs(53));
+}
+
+#[test]
+fn simple_delay_waits_before_counting_down() {
+    let mut clock = Clock::new(TimeControl::SimpleDelay {
+        time: secs(60),
+        delay: secs(5),
+    });
+    clock.tick(WHITE, secs(3));
+    assert_eq!(clock.remaining(WHITE), secs(60));
+    clock.tick(WHITE, secs(4));
+    assert_eq!(clock.remaining(WHITE), secs(58));
+    clock.press(WHITE);
+    assert_eq!(clock.remaining(WHITE), secs(58));
+
+    // the delay starts again on each move
+    clock.tick(BLACK, secs(5));
+    assert_eq!(clock.remaining(BLACK), secs(60));
+}
+
+#[test]
+fn no_time_is_added_after_running_out() {
+    let mut clock = Clock::new(TimeControl::Fischer {
+        time: secs(1),
+        increment: secs(5),
+    });
+    clock.tick(WHITE, secs(2));
+    clock.press(WHITE);
+    assert!(clock.is_flagged(WHITE));
+
+    clock.reset();
+    assert_eq!(clock.remaining(WHITE), secs(1));
+}
+
+#[test]
+fn time_controls_are_parsed() {
+    assert_eq!(
+        "5".parse(),
+        Ok(TimeControl::SuddenDeath { time: secs(300) })
+    );
+    assert_eq!(
+        "3+2".parse(),
+        Ok(TimeControl::Fischer {
+            time: secs(180),
+            increment: secs(2),
+        })
+    );
+    assert_eq!(
+        "5d3".parse(),
+        Ok(TimeControl::SimpleDelay {
+            time: secs(300),
+            delay: secs(3),
+        })
+    );
+    assert_eq!(
+        "0.5b2".parse(),
+        Ok(TimeControl::Bronstein {
+            time: secs(30),
+            delay: secs(2),
+        })
+    );
+
+    for invalid in ["", "0", "five", "5+", "+3", "-1", "5x3"] {
+        assert!(invalid.parse::<TimeControl>().is_err(), "{invalid}");
+    }
+}
+
+#[test]
+fn running_out_of_time_loses() {
+    let mut game = Game::default();
+    assert_eq!(game.run_out_of_time(), Ok(Status::OutOfTime));
+    assert_eq!(game.status(), Status::OutOfTime);
+    assert_eq!(game.result(), "0-1");
+    assert!(game.run_out_of_time().is_err());
+}
+
+#[test]
+fn running_out_of_time_draws_when_the_opponent_cannot_mate() {
+    // a lone King can never mate
+    let position: Position = "8/8/4k3/8/8/4K3/3QR3/8 w - - 0 1".parse().unwrap();
+    let mut game = Game::new(position);
+    assert_eq!(
+        game.run_out_of_time(),
+        Ok(Status::Draw(DrawReason::OutOfTimeWithInsufficientMaterial))
+    );
+    assert_eq!(game.result(), "1/2-1/2");
+
+    // a Knight can mate when the opposing pieces box in their own King
+    let position: Position = "8/8/4k3/8/8/3nK3/4R3/8 w - - 0 1".parse().unwrap();
+    let mut game = Game::new(position);
+    assert_eq!(game.run_out_of_time(), Ok(Status::OutOfTime));
+
+    // the game is already drawn when neither side can mate
+    let position: Position = "8/8/4k3/8/8/3nK3/8/8 w - - 0 1".parse().unwrap();
+    let mut game = Game::new(position);
+    assert!(game.run_out_of_time().is_err());
+}
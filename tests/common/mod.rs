@@ -0,0 +1,25 @@
+// each test file uses only some of the helpers
+#![allow(dead_code)]
+
+use chess::{Game, Position, Variant};
+
+/// Reads `fen` as a position of `variant`
+pub fn position(fen: &str, variant: &'static dyn Variant) -> Position {
+    Position::from_fen(fen, variant).unwrap()
+}
+
+/// Makes each move, given in the long algebraic notation used by UCI
+pub fn play(game: &mut Game, moves: &[&str]) {
+    for uci in moves {
+        let chess_move = game.position().parse_uci(uci).unwrap();
+        game.make_move(chess_move).unwrap();
+    }
+}
+
+/// Makes each move, given in SAN
+pub fn play_san(game: &mut Game, moves: &[&str]) {
+    for san in moves {
+        let chess_move = game.position().parse_san(san).unwrap();
+        game.make_move(chess_move).unwrap();
+    }
+}
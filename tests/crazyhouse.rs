@@ -0,0 +1,192 @@
+use std::collections::HashSet;
+
+use chess::{
+    ChessMove, Crazyhouse, DeadPosition, DrawReason, Game, PgnGame, PgnTags, PieceColour,
+    PieceType, Position, Session, Status,
+};
+use common::{play_san, position};
+
+mod common;
+
+const WHITE: PieceColour = PieceColour::White;
+const BLACK: PieceColour = PieceColour::Black;
+
+#[test]
+fn taken_pieces_go_to_the_pocket() {
+    let mut game = Game::new(Position::starting(&Crazyhouse));
+    assert_eq!(
+        game.position().to_fen(),
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[] w KQkq - 0 1"
+    );
+
+    play_san(&mut game, &["e4", "d5", "exd5", "Qxd5", "Nc3"]);
+    assert_eq!(game.position().pocket(WHITE, PieceType::Pawn), 1);
+    assert_eq!(game.position().pocket(BLACK, PieceType::Pawn), 1);
+    assert_eq!(
+        game.position().to_fen(),
+        "rnb1kbnr/ppp1pppp/8/3q4/8/2N5/PPPP1PPP/R1BQKBNR[Pp] b KQkq - 1 3"
+    );
+
+    // the pawn can be dropped back anywhere empty, apart from the first and last ranks
+    let drops: Vec<_> = game
+        .legal_moves()
+        .into_iter()
+        .filter(|chess_move| chess_move.drop.is_some())
+        .collect();
+    assert_eq!(drops.len(), 32);
+    assert!(drops
+        .iter()
+        .all(|chess_move| chess_move.to.rank != 0 && chess_move.to.rank != 7));
+
+    let drop = game.position().parse_san("P@e4").unwrap();
+    assert_eq!(
+        drop,
+        ChessMove::new_drop(PieceType::Pawn, "e4".parse().unwrap())
+    );
+    assert_eq!(game.position().parse_san("@e4"), Ok(drop));
+    assert_eq!(game.position().parse_uci("P@e4"), Ok(drop));
+    assert_eq!(drop.to_string(), "P@e4");
+    assert!(game.position().parse_uci("N@e4").is_err());
+    assert!(game.position().parse_san("P@e8").is_err());
+}
+
+#[test]
+fn drops_are_written_with_an_at_sign() {
+    let position = position("4k3/8/8/8/8/8/8/4K3[N] w - - 0 1", &Crazyhouse);
+    let check = position.parse_uci("N@d6").unwrap();
+    assert_eq!(position.to_san(&check), "N@d6+");
+    assert_eq!(
+        position.to_san(&position.parse_uci("N@a1").unwrap()),
+        "N@a1"
+    );
+
+    // a drop can block a check as well as give one
+    let checked = self::position("4k3/8/8/8/8/8/8/r3K3[N] w - - 0 1", &Crazyhouse);
+    let blocks: Vec<_> = checked
+        .legal_moves()
+        .into_iter()
+        .filter(|chess_move| chess_move.drop.is_some())
+        .map(|chess_move| checked.to_san(&chess_move))
+        .collect();
+    assert_eq!(blocks, ["N@b1", "N@c1", "N@d1"]);
+}
+
+#[test]
+fn making_and_unmaking_a_drop_restores_the_position() {
+    let mut position = position("4k3/8/8/8/8/8/8/4K3[Qp] w - - 0 1", &Crazyhouse);
+    let fen = position.to_fen();
+    let key = position.key();
+
+    let drop = position.parse_san("Q@e2").unwrap();
+    let undo = position.make_move(drop);
+    assert_eq!(position.to_fen(), "4k3/8/8/8/8/8/4Q3/4K3[p] b - - 1 1");
+    assert_eq!(
+        position.key(),
+        self::position(&position.to_fen(), &Crazyhouse).key()
+    );
+
+    position.unmake_move(drop, undo);
+    assert_eq!(position.to_fen(), fen);
+    assert_eq!(position.key(), key);
+}
+
+#[test]
+fn promoted_pieces_go_back_to_the_pocket_as_pawns() {
+    let mut game = Game::new(position("r3k3/1P6/8/8/8/8/8/4K3[] w - - 0 1", &Crazyhouse));
+    play_san(&mut game, &["b8=Q+"]);
+    assert!(game.position().is_promoted("b8".parse().unwrap()));
+    assert_eq!(
+        game.position().to_fen(),
+        "rQ~2k3/8/8/8/8/8/8/4K3[] b - - 0 1"
+    );
+
+    play_san(&mut game, &["Rxb8"]);
+    assert_eq!(game.position().pocket(BLACK, PieceType::Pawn), 1);
+    assert_eq!(game.position().pocket(BLACK, PieceType::Queen), 0);
+    assert_eq!(
+        game.position().to_fen(),
+        "1r2k3/8/8/8/8/8/8/4K3[p] w - - 0 2"
+    );
+
+    // the mark is read back from the FEN
+    let promoted = position("rQ~2k3/8/8/8/8/8/8/4K3[] b - - 0 1", &Crazyhouse);
+    assert!(promoted.is_promoted("b8".parse().unwrap()));
+    assert!("r~Q2k3/8/8/8/8/8/8/4K3[] b - - 0 1"
+        .parse::<Position>()
+        .is_ok());
+    assert!("~rQ2k3/8/8/8/8/8/8/4K3[] b - - 0 1"
+        .parse::<Position>()
+        .is_err());
+    assert!("4k3/8/8/8/8/8/8/4K3[K] w - - 0 1"
+        .parse::<Position>()
+        .is_err());
+}
+
+#[test]
+fn overfull_pockets_are_rejected() {
+    let full = format!("4k3/8/8/8/8/8/8/4K3[{}] w - - 0 1", "P".repeat(255));
+    assert_eq!(
+        position(&full, &Crazyhouse).pocket(WHITE, PieceType::Pawn),
+        255
+    );
+
+    // every number of pieces a pocket can hold gives a different key
+    let keys: HashSet<_> = (0..=255)
+        .map(|count| {
+            let fen = format!("4k3/8/8/8/8/8/8/4K3[{}] w - - 0 1", "P".repeat(count));
+            position(&fen, &Crazyhouse).key()
+        })
+        .collect();
+    assert_eq!(keys.len(), 256);
+
+    let overfull = format!("4k3/8/8/8/8/8/8/4K3[{}] w - - 0 1", "P".repeat(256));
+    assert_eq!(
+        overfull.parse::<Position>().err(),
+        Some("too many 'P' in pocket".to_string())
+    );
+}
+
+#[test]
+fn only_bare_kings_with_empty_pockets_are_drawn() {
+    let bare = position("4k3/8/8/8/8/8/8/4K3[] w - - 0 1", &Crazyhouse);
+    assert_eq!(
+        bare.status(),
+        Status::Draw(DrawReason::DeadPosition(DeadPosition::KingVsKing))
+    );
+
+    let held = position("4k3/8/8/8/8/8/8/4K3[p] w - - 0 1", &Crazyhouse);
+    assert_eq!(held.status(), Status::OnGoing);
+    assert!(held.variant().can_win(&held, WHITE));
+
+    // a lone Knight can still mate with the help of drops
+    let knight = position("4k3/8/8/8/8/8/8/1N2K3[] w - - 0 1", &Crazyhouse);
+    assert_eq!(knight.status(), Status::OnGoing);
+}
+
+#[test]
+fn games_with_drops_are_saved_and_loaded() {
+    let mut game = Game::new(Position::starting(&Crazyhouse));
+    play_san(
+        &mut game,
+        &["e4", "d5", "exd5", "Qxd5", "Nc3", "Qa5", "P@d5"],
+    );
+
+    let pgn = game.to_pgn(&PgnTags::default());
+    assert!(pgn.contains("[Variant \"Crazyhouse\"]\n"));
+    assert!(!pgn.contains("[FEN"));
+    assert!(pgn.contains("4. P@d5"));
+    let games = PgnGame::parse_all(&pgn).unwrap();
+    assert_eq!(games[0].game.position().to_fen(), game.position().to_fen());
+
+    let session = Session {
+        game,
+        white: "Alice".to_string(),
+        black: "Bob".to_string(),
+        clock: None,
+    };
+    let reloaded: Session = session.to_string().parse().unwrap();
+    assert_eq!(
+        reloaded.game.position().to_fen(),
+        session.game.position().to_fen()
+    );
+}
@@ -0,0 +1,64 @@
+use chess::{DeadPosition, DrawReason, Position, Status};
+
+fn dead_position(fen: &str) -> Option<DeadPosition> {
+    fen.parse::<Position>().unwrap().dead_position()
+}
+
+#[test]
+fn insufficient_material() {
+    assert_eq!(
+        dead_position("8/8/4k3/8/8/3K4/8/8 w - - 0 1"),
+        Some(DeadPosition::KingVsKing)
+    );
+    assert_eq!(
+        dead_position("8/8/4k3/8/8/3KB3/8/8 w - - 0 1"),
+        Some(DeadPosition::KingAndBishopVsKing)
+    );
+    assert_eq!(
+        dead_position("8/8/4k3/8/8/3K4/8/6n1 w - - 0 1"),
+        Some(DeadPosition::KingAndKnightVsKing)
+    );
+    // both bishops are on dark squares
+    assert_eq!(
+        dead_position("8/8/4k3/8/1b6/3K4/8/2B5 w - - 0 1"),
+        Some(DeadPosition::SameColouredBishops)
+    );
+}
+
+#[test]
+fn sufficient_material() {
+    // bishops on opposite coloured squares
+    assert_eq!(dead_position("8/8/4k3/8/2b5/3K4/8/2B5 w - - 0 1"), None);
+    assert_eq!(dead_position("8/8/4k3/8/8/3K4/8/1NN5 w - - 0 1"), None);
+    assert_eq!(dead_position("8/8/4k3/8/8/3KB3/8/6n1 w - - 0 1"), None);
+    assert_eq!(dead_position("8/8/4k3/8/8/3K4/4P3/8 w - - 0 1"), None);
+    assert_eq!(dead_position(chess::STARTING_FEN), None);
+}
+
+#[test]
+fn blocked_pawns() {
+    // a locked chain across the whole board with a King on either side
+    assert_eq!(
+        dead_position("8/4k3/8/p1p1p1p1/P1P1P1P1/8/4K3/8 w - - 0 1"),
+        Some(DeadPosition::BlockedPawns)
+    );
+    // the kings can walk around the end of the chain to the undefended pawns
+    assert_eq!(
+        dead_position("8/4k3/8/2p1p1p1/2P1P1P1/8/4K3/8 w - - 0 1"),
+        None
+    );
+    // a pawn can still take
+    assert_eq!(
+        dead_position("8/4k3/8/p1p1pp2/P1P1P1P1/8/4K3/8 w - - 0 1"),
+        None
+    );
+}
+
+#[test]
+fn dead_position_ends_the_game() {
+    let position: Position = "8/8/4k3/8/8/3K4/8/8 w - - 0 1".parse().unwrap();
+    assert_eq!(
+        position.status(),
+        Status::Draw(DrawReason::DeadPosition(DeadPosition::KingVsKing))
+    );
+}
@@ -0,0 +1,88 @@
+use chess::{Antichess, Position, Square, STARTING_FEN};
+
+fn round_trip(fen: &str) {
+    let position: Position = fen.parse().unwrap();
+    assert_eq!(position.to_fen(), fen);
+}
+
+#[test]
+fn positions_are_written_as_they_were_read() {
+    round_trip(STARTING_FEN);
+    assert_eq!(Position::default().to_fen(), STARTING_FEN);
+
+    // en passant
+    round_trip("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 3");
+    round_trip("4k3/8/8/4pP2/8/8/8/4K3 w - e6 0 1");
+    // some of the castling rights
+    round_trip("r3k2r/8/8/8/8/8/8/R3K2R w Kq - 0 1");
+    round_trip("r3k2r/8/8/8/8/8/8/R3K2R b Qk - 0 1");
+    // the halfmove clock and fullmove number
+    round_trip("4k3/8/8/8/8/8/8/R3K3 b Q - 37 112");
+}
+
+#[test]
+fn squares_are_read_from_algebraic_notation() {
+    assert_eq!("a1".parse(), Ok(Square::new(0, 0)));
+    assert_eq!("h8".parse(), Ok(Square::new(7, 7)));
+    for invalid in ["", "a", "a0", "a9", "i1", "A1", "e44", "a\u{80}", "\u{80}1"] {
+        assert!(invalid.parse::<Square>().is_err(), "{invalid}");
+    }
+}
+
+#[test]
+fn malformed_placements_are_rejected() {
+    for invalid in [
+        "99999999999999999/8/8/8/8/8/8/8 w - - 0 1",
+        "4k3/8/8/8/8/8/8/4K3/8 w - - 0 1",
+        "4k3/8/08/8/8/8/8/4K3 w - - 0 1",
+        "4k3/8/9/8/8/8/8/4K3 w - - 0 1",
+        "4k3/8/45/8/8/8/8/4K3 w - - 0 1",
+        "4k4/8/8/8/8/8/8/4K3 w - - 0 1",
+        "4k2/8/8/8/8/8/8/4K3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/4X3 w - - 0 1",
+    ] {
+        assert!(invalid.parse::<Position>().is_err(), "{invalid}");
+    }
+}
+
+#[test]
+fn unreachable_positions_are_rejected() {
+    for invalid in [
+        // the number of Kings
+        "8/8/8/8/8/8/8/4K3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/K3K3 w - - 0 1",
+        // pawns on the first or eighth rank
+        "P3k3/8/8/8/8/8/8/4K3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/p3K3 b - - 0 1",
+        // castling with a missing rook or King
+        "4k3/8/8/8/8/8/8/4K3 w K - 0 1",
+        "4k3/8/8/8/8/8/8/4K2R w Q - 0 1",
+        "4k3/8/8/8/8/8/8/R3K3 w K - 0 1",
+        "4k3/8/8/8/8/8/4K3/R6R w KQ - 0 1",
+        "4k3/8/8/8/8/8/8/R3K3 w C - 0 1",
+    ] {
+        assert!(invalid.parse::<Position>().is_err(), "{invalid}");
+    }
+
+    // the King is an ordinary piece in Antichess, so there can be any number of them
+    assert!(Position::from_fen("8/8/8/8/8/8/8/K3K3 w - - 0 1", &Antichess).is_ok());
+}
+
+#[test]
+fn en_passant_targets_must_follow_a_pawn_moving_two_squares() {
+    for invalid in [
+        // no pawn in front of the target
+        "4k3/8/8/3P4/8/8/8/4K3 w - e6 0 1",
+        // the pawn in front of the target belongs to the side to move
+        "4k3/8/8/4P3/8/8/8/4K3 w - e6 0 1",
+        // the square the pawn would have come from is occupied
+        "4k3/4p3/8/4pP2/8/8/8/4K3 w - e6 0 1",
+        // the target itself is occupied
+        "4k3/8/4n3/4pP2/8/8/8/4K3 w - e6 0 1",
+        // the target is on the wrong side of the board for the side to move
+        "4k3/8/8/4pP2/8/8/8/4K3 b - e6 0 1",
+        "4k3/8/8/8/8/8/8/4K3 w - a\u{80} 0 1",
+    ] {
+        assert!(invalid.parse::<Position>().is_err(), "{invalid}");
+    }
+}
@@ -0,0 +1,108 @@
+use chess::{ChessMove, DrawReason, Game, PieceColour, Position, Status};
+use common::play;
+
+mod common;
+
+const WHITE: PieceColour = PieceColour::White;
+const BLACK: PieceColour = PieceColour::Black;
+
+#[test]
+fn resigning_loses() {
+    let mut game = Game::default();
+    play(&mut game, &["e2e4"]);
+    // either player can resign, whoever is to move
+    assert_eq!(game.resign(WHITE), Ok(()));
+    assert_eq!(game.status(), Status::Resigned(WHITE));
+    assert_eq!(game.result(), "0-1");
+    assert!(game.resign(BLACK).is_err());
+
+    let mut game = Game::default();
+    game.resign(BLACK).unwrap();
+    assert_eq!(game.result(), "1-0");
+}
+
+#[test]
+fn no_moves_after_resigning() {
+    let mut game = Game::default();
+    play(&mut game, &["e2e4"]);
+    game.resign(BLACK).unwrap();
+
+    let reply = ChessMove::new("e7".parse().unwrap(), "e5".parse().unwrap());
+    assert_eq!(
+        game.make_move(reply),
+        Err("the game is already over".to_string())
+    );
+    assert!(game.legal_moves().is_empty());
+    assert_eq!(game.moves().count(), 1);
+    assert_eq!(game.status(), Status::Resigned(BLACK));
+}
+
+#[test]
+fn accepting_a_draw_offer_draws() {
+    let mut game = Game::default();
+    assert!(game.accept_draw(BLACK).is_err());
+
+    game.offer_draw(WHITE).unwrap();
+    assert_eq!(game.draw_offer(), Some(WHITE));
+    assert!(game.offer_draw(WHITE).is_err());
+    assert!(game.offer_draw(BLACK).is_err());
+    // a player cannot accept their own offer
+    assert!(game.accept_draw(WHITE).is_err());
+
+    assert_eq!(game.accept_draw(BLACK), Ok(()));
+    assert_eq!(game.status(), Status::Draw(DrawReason::Agreement));
+    assert_eq!(game.result(), "1/2-1/2");
+}
+
+#[test]
+fn declining_a_draw_offer_continues_the_game() {
+    let mut game = Game::default();
+    game.offer_draw(WHITE).unwrap();
+    assert_eq!(game.decline_draw(BLACK), Ok(()));
+    assert_eq!(game.draw_offer(), None);
+    assert_eq!(game.status(), Status::OnGoing);
+    assert!(game.decline_draw(BLACK).is_err());
+}
+
+#[test]
+fn moving_declines_a_draw_offer() {
+    let mut game = Game::default();
+    // an offer made before moving stands while the opponent considers it
+    game.offer_draw(WHITE).unwrap();
+    play(&mut game, &["e2e4"]);
+    assert_eq!(game.draw_offer(), Some(WHITE));
+
+    play(&mut game, &["e7e5"]);
+    assert_eq!(game.draw_offer(), None);
+    assert!(game.accept_draw(BLACK).is_err());
+
+    // taking back a move withdraws the offer
+    game.offer_draw(BLACK).unwrap();
+    game.unmake_move();
+    assert_eq!(game.draw_offer(), None);
+}
+
+#[test]
+fn fifty_move_rule_is_claimed() {
+    let position: Position = "4k3/8/8/8/8/8/8/R3K3 w - - 99 80".parse().unwrap();
+    let mut game = Game::new(position);
+    assert_eq!(game.claimable_draw(), None);
+
+    play(&mut game, &["a1a2"]);
+    assert_eq!(game.status(), Status::OnGoing);
+    assert_eq!(game.claimable_draw(), Some(DrawReason::FiftyMoveRule));
+    assert_eq!(game.claim_draw(), Ok(DrawReason::FiftyMoveRule));
+    assert_eq!(game.status(), Status::Draw(DrawReason::FiftyMoveRule));
+}
+
+#[test]
+fn seventy_five_move_rule_is_automatic() {
+    let position: Position = "4k3/8/8/8/8/8/8/R3K3 w - - 149 80".parse().unwrap();
+    let mut game = Game::new(position);
+    assert_eq!(game.status(), Status::OnGoing);
+
+    play(&mut game, &["a1a2"]);
+    assert_eq!(game.status(), Status::Draw(DrawReason::SeventyFiveMoveRule));
+    assert!(game.resign(BLACK).is_err());
+    assert!(game.offer_draw(BLACK).is_err());
+}
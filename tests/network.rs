@@ -0,0 +1,491 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chess::{
+    Game, KingOfTheHill, NetworkEvent, NetworkPeer, PieceColour, PlayerAction, Position, Status,
+};
+
+const NETPLAY: &str = env!("CARGO_BIN_EXE_netplay");
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A `netplay` process, with its output read a line at a time on another thread
+struct Player {
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+}
+
+impl Player {
+    fn start(args: &[&str]) -> Player {
+        let mut child = Command::new(NETPLAY)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        Player {
+            child,
+            stdin,
+            lines,
+        }
+    }
+
+    fn send(&mut self, line: &str) {
+        writeln!(self.stdin, "{line}").unwrap();
+    }
+
+    /// Waits for a line starting with `prefix`, skipping any others
+    fn expect(&mut self, prefix: &str) -> String {
+        let deadline = Instant::now() + TIMEOUT;
+        loop {
+            let line = self
+                .lines
+                .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                .unwrap_or_else(|_| panic!("expected '{prefix}'"));
+            if line.starts_with(prefix) {
+                return line;
+            }
+        }
+    }
+}
+
+impl Drop for Player {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Starts a host on a free port, returning it with the address it is listening on
+fn host(args: &[&str]) -> (Player, String) {
+    let mut host = Player::start(&[&["host", "127.0.0.1:0"], args].concat());
+    let address = host.expect("listening on ")["listening on ".len()..].to_string();
+    (host, address)
+}
+
+fn key_after(uci: &[&str]) -> String {
+    let mut position = Position::default();
+    for uci in uci {
+        let chess_move = position.parse_uci(uci).unwrap();
+        position.make_move(chess_move);
+    }
+    format!("{:016x}", position.key())
+}
+
+/// Polls `peer` until something happens
+fn poll_until_event(peer: &mut NetworkPeer) -> Vec<NetworkEvent> {
+    let deadline = Instant::now() + TIMEOUT;
+    loop {
+        let events = peer.poll();
+        if !events.is_empty() {
+            return events;
+        }
+        assert!(Instant::now() < deadline, "nothing happened");
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+fn read_line(reader: &mut impl BufRead) -> String {
+    let mut line = String::new();
+    reader.read_line(&mut line).unwrap();
+    line.trim_end().to_string()
+}
+
+#[test]
+fn two_processes_play_a_game() {
+    let (mut host, address) = host(&[]);
+    let mut client = Player::start(&["connect", &address]);
+    assert_eq!(client.expect("connected"), "connected as Black");
+    host.expect("connected");
+
+    host.send("e2e4");
+    client.expect("opponent e2e4");
+    client.send("d2d4");
+    assert!(client.expect("error").contains("d2d4 is not a legal move"));
+    host.send("d2d4");
+    assert_eq!(host.expect("error"), "error it is not your turn");
+
+    client.send("e7e5");
+    host.expect("opponent e7e5");
+    host.send("show");
+    assert_eq!(host.expect("position"), "position startpos moves e2e4 e7e5");
+}
+
+#[test]
+fn draw_offers_are_answered_across_the_network() {
+    let (mut host, address) = host(&[]);
+    let mut client = Player::start(&["connect", &address]);
+    client.expect("connected");
+    host.expect("connected");
+
+    host.send("offer");
+    client.expect("opponent offer white");
+    client.send("decline");
+    host.expect("opponent decline black");
+    host.send("e2e4");
+    client.expect("opponent e2e4");
+    client.send("offer");
+    host.expect("opponent offer black");
+    host.send("accept");
+    client.expect("opponent accept white");
+    client.send("e7e5");
+    assert_eq!(client.expect("error"), "error the game is already over");
+
+    // a spectator joining afterwards is told how the game ended
+    let mut spectator = Player::start(&["watch", &address]);
+    spectator.expect("watching");
+    spectator.expect("opponent offer black");
+    spectator.expect("opponent accept white");
+    spectator.send("resign");
+    spectator.expect("error spectators can only watch");
+}
+
+#[test]
+fn host_can_play_black() {
+    let (mut host, address) = host(&["black"]);
+    let mut client = Player::start(&["connect", &address]);
+    assert_eq!(client.expect("connected"), "connected as White");
+
+    client.send("g1f3");
+    host.expect("opponent g1f3");
+}
+
+#[test]
+fn moves_are_caught_up_after_reconnecting() {
+    let mut host = NetworkPeer::host("127.0.0.1:0", PieceColour::White, Game::default()).unwrap();
+    let address = host.local_addr().unwrap();
+    let mut stream = TcpStream::connect(address).unwrap();
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    writeln!(stream, "hello bevy-chess 2 2 player").unwrap();
+    assert_eq!(poll_until_event(&mut host), [NetworkEvent::Connected]);
+    let welcome = read_line(&mut reader);
+    let token = welcome
+        .strip_prefix("welcome 2 black ")
+        .unwrap()
+        .to_string();
+    read_line(&mut reader);
+    let e2e4 = host.game().position().parse_uci("e2e4").unwrap();
+    host.play(e2e4).unwrap();
+
+    // the client goes away, and carries on with the game when it connects again with its token
+    drop(reader);
+    drop(stream);
+    assert_eq!(poll_until_event(&mut host), [NetworkEvent::Disconnected]);
+    let mut stream = TcpStream::connect(address).unwrap();
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    writeln!(stream, "hello bevy-chess 2 2 player {token}").unwrap();
+    assert_eq!(poll_until_event(&mut host), [NetworkEvent::Connected]);
+    assert_eq!(read_line(&mut reader), format!("welcome 2 black {token}"));
+    assert_eq!(read_line(&mut reader), "position startpos moves e2e4");
+
+    writeln!(stream, "move 2 c7c5 {}", key_after(&["e2e4", "c7c5"])).unwrap();
+    assert!(matches!(
+        &poll_until_event(&mut host)[..],
+        [NetworkEvent::OpponentMoved(_)]
+    ));
+}
+
+#[test]
+fn nobody_else_can_take_the_players_seat() {
+    let (mut host, address) = host(&[]);
+    let mut client = Player::start(&["connect", &address]);
+    client.expect("connected");
+    host.expect("connected");
+
+    for hello in [
+        "hello bevy-chess 2 2 player",
+        "hello bevy-chess 2 2 player 0123456789abcdef0123456789abcdef",
+    ] {
+        let mut stream = TcpStream::connect(&address).unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        writeln!(stream, "{hello}").unwrap();
+        assert_eq!(
+            read_line(&mut reader),
+            "error the game already has another player"
+        );
+    }
+
+    // the player keeps their seat
+    host.send("e2e4");
+    client.expect("opponent e2e4");
+}
+
+#[test]
+fn client_reconnects_to_the_host() {
+    let (host, address) = host(&[]);
+    let mut client = Player::start(&["connect", &address]);
+    client.expect("connected");
+
+    drop(host);
+    client.expect("disconnected");
+    let mut host = Player::start(&["host", &address]);
+    host.expect("listening on");
+    host.expect("connected");
+    client.expect("connected");
+
+    host.send("d2d4");
+    client.expect("opponent d2d4");
+}
+
+#[test]
+fn refuses_other_protocol_versions() {
+    let mut peer = NetworkPeer::host("127.0.0.1:0", PieceColour::White, Game::default()).unwrap();
+    let mut stream = TcpStream::connect(peer.local_addr().unwrap()).unwrap();
+    writeln!(stream, "hello bevy-chess 3 4 player").unwrap();
+
+    assert!(matches!(
+        &poll_until_event(&mut peer)[..],
+        [NetworkEvent::Error(_)]
+    ));
+    let mut reader = BufReader::new(stream);
+    assert_eq!(
+        read_line(&mut reader),
+        "error no protocol version in common, the host speaks versions 2 to 2"
+    );
+    assert!(!peer.is_connected());
+}
+
+#[test]
+fn detects_games_out_of_step() {
+    let mut peer = NetworkPeer::host("127.0.0.1:0", PieceColour::White, Game::default()).unwrap();
+    let mut stream = TcpStream::connect(peer.local_addr().unwrap()).unwrap();
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    writeln!(stream, "hello bevy-chess 2 2 player").unwrap();
+
+    assert_eq!(poll_until_event(&mut peer), [NetworkEvent::Connected]);
+    assert!(read_line(&mut reader).starts_with("welcome 2 black "));
+    assert_eq!(read_line(&mut reader), "position startpos");
+
+    let e2e4 = peer.game().position().parse_uci("e2e4").unwrap();
+    peer.play(e2e4).unwrap();
+    assert_eq!(
+        read_line(&mut reader),
+        format!("move 1 e2e4 {}", key_after(&["e2e4"]))
+    );
+
+    // a move reaching a different position is refused, and the host sends its game again
+    writeln!(stream, "move 2 e7e5 0000000000000000").unwrap();
+    assert!(matches!(
+        &poll_until_event(&mut peer)[..],
+        [NetworkEvent::Error(_)]
+    ));
+    assert_eq!(read_line(&mut reader), "position startpos moves e2e4");
+    assert_eq!(peer.game().moves().count(), 1);
+
+    writeln!(stream, "move 3 e7e5 {}", key_after(&["e2e4", "e7e5"])).unwrap();
+    assert!(matches!(
+        &poll_until_event(&mut peer)[..],
+        [NetworkEvent::Error(_)]
+    ));
+    assert_eq!(read_line(&mut reader), "position startpos moves e2e4");
+
+    writeln!(stream, "move 2 e7e5 {}", key_after(&["e2e4", "e7e5"])).unwrap();
+    let mut position = Position::default();
+    position.make_move(e2e4);
+    let e7e5 = position.parse_uci("e7e5").unwrap();
+    assert_eq!(
+        poll_until_event(&mut peer),
+        [NetworkEvent::OpponentMoved(e7e5)]
+    );
+}
+
+#[test]
+fn resigning_ends_the_game_on_both_sides() {
+    let mut host = NetworkPeer::host("127.0.0.1:0", PieceColour::White, Game::default()).unwrap();
+    assert_eq!(
+        host.act(PlayerAction::Resign(PieceColour::White)),
+        Err("the other player is not connected".to_string())
+    );
+    let address = host.local_addr().unwrap();
+    let client = thread::spawn(move || NetworkPeer::connect(address));
+    assert_eq!(poll_until_event(&mut host), [NetworkEvent::Connected]);
+    let mut client = client.join().unwrap().unwrap();
+
+    assert_eq!(
+        client.act(PlayerAction::Resign(PieceColour::White)),
+        Err("White is played by the other player".to_string())
+    );
+    assert_eq!(
+        client.act(PlayerAction::ClaimDraw),
+        Err("White is played by the other player".to_string())
+    );
+    client
+        .act(PlayerAction::Resign(PieceColour::Black))
+        .unwrap();
+    assert_eq!(
+        poll_until_event(&mut host),
+        [NetworkEvent::OpponentActed(PlayerAction::Resign(
+            PieceColour::Black
+        ))]
+    );
+    assert_eq!(host.game().status(), Status::Resigned(PieceColour::Black));
+    let e2e4 = host.game().position().parse_uci("e2e4").unwrap();
+    assert_eq!(host.play(e2e4), Err("the game is already over".to_string()));
+}
+
+#[test]
+fn detects_actions_out_of_step() {
+    let mut peer = NetworkPeer::host("127.0.0.1:0", PieceColour::White, Game::default()).unwrap();
+    let mut stream = TcpStream::connect(peer.local_addr().unwrap()).unwrap();
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    writeln!(stream, "hello bevy-chess 2 2 player").unwrap();
+    assert_eq!(poll_until_event(&mut peer), [NetworkEvent::Connected]);
+    read_line(&mut reader);
+    assert_eq!(read_line(&mut reader), "position startpos");
+
+    // an offer taken after a move the host has not seen is refused, and the host sends its game
+    writeln!(stream, "offer 1 black").unwrap();
+    assert!(matches!(
+        &poll_until_event(&mut peer)[..],
+        [NetworkEvent::Error(_)]
+    ));
+    assert_eq!(read_line(&mut reader), "position startpos");
+    assert_eq!(peer.game().draw_offer(), None);
+
+    // nor can the other player act for the host
+    writeln!(stream, "resign 0 white").unwrap();
+    assert!(matches!(
+        &poll_until_event(&mut peer)[..],
+        [NetworkEvent::Error(_)]
+    ));
+    assert_eq!(read_line(&mut reader), "position startpos");
+
+    writeln!(stream, "offer 0 black").unwrap();
+    assert_eq!(
+        poll_until_event(&mut peer),
+        [NetworkEvent::OpponentActed(PlayerAction::OfferDraw(
+            PieceColour::Black
+        ))]
+    );
+    assert_eq!(peer.game().draw_offer(), Some(PieceColour::Black));
+
+    // the offer follows the game when the host sends it again
+    writeln!(stream, "decline 3 black").unwrap();
+    assert!(matches!(
+        &poll_until_event(&mut peer)[..],
+        [NetworkEvent::Error(_)]
+    ));
+    assert_eq!(read_line(&mut reader), "position startpos");
+    assert_eq!(read_line(&mut reader), "offer 0 black");
+}
+
+#[test]
+fn client_takes_the_hosts_game() {
+    let mut host = NetworkPeer::host("127.0.0.1:0", PieceColour::Black, Game::default()).unwrap();
+    let e2e4 = host.game().position().parse_uci("e2e4").unwrap();
+    let mut game = Game::default();
+    game.make_move(e2e4).unwrap();
+    host.set_game(game).unwrap();
+
+    let address = host.local_addr().unwrap();
+    let client = thread::spawn(move || NetworkPeer::connect(address));
+    assert_eq!(poll_until_event(&mut host), [NetworkEvent::Connected]);
+
+    let mut client = client.join().unwrap().unwrap();
+    assert_eq!(client.colour(), Some(PieceColour::White));
+    assert_eq!(client.version(), 2);
+    assert_eq!(client.game().moves().copied().collect::<Vec<_>>(), [e2e4]);
+    assert!(client.set_game(Game::default()).is_err());
+}
+
+#[test]
+fn spectators_come_and_go_without_affecting_the_players() {
+    let (mut host, address) = host(&[]);
+    let mut client = Player::start(&["connect", &address]);
+    client.expect("connected");
+    host.send("e2e4");
+    client.expect("opponent e2e4");
+    client.send("e7e5");
+    host.expect("opponent e7e5");
+
+    // the spectator is given the whole game on joining, then every move from either side
+    let mut spectator = Player::start(&["watch", &address]);
+    spectator.expect("watching");
+    host.expect("spectator joined");
+    spectator.send("show");
+    assert_eq!(
+        spectator.expect("position"),
+        "position startpos moves e2e4 e7e5"
+    );
+    host.send("g1f3");
+    spectator.expect("opponent g1f3");
+    client.expect("opponent g1f3");
+    client.send("b8c6");
+    spectator.expect("opponent b8c6");
+    spectator.send("f1b5");
+    spectator.expect("error spectators cannot move");
+
+    drop(spectator);
+    host.expect("spectator left");
+    host.send("f1b5");
+    client.expect("opponent f1b5");
+}
+
+#[test]
+fn spectators_cannot_move() {
+    let mut host = NetworkPeer::host("127.0.0.1:0", PieceColour::White, Game::default()).unwrap();
+    let address = host.local_addr().unwrap();
+    let spectator = thread::spawn(move || NetworkPeer::watch(address));
+    assert_eq!(poll_until_event(&mut host), [NetworkEvent::SpectatorJoined]);
+    assert!(!host.is_connected());
+    assert_eq!(host.spectators(), 1);
+
+    let mut spectator = spectator.join().unwrap().unwrap();
+    assert_eq!(spectator.colour(), None);
+    let e2e4 = spectator.game().position().parse_uci("e2e4").unwrap();
+    assert_eq!(
+        spectator.play(e2e4),
+        Err("spectators cannot move".to_string())
+    );
+
+    let mut stream = TcpStream::connect(address).unwrap();
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    writeln!(stream, "hello bevy-chess 2 2 spectator").unwrap();
+    assert_eq!(poll_until_event(&mut host), [NetworkEvent::SpectatorJoined]);
+    assert_eq!(read_line(&mut reader), "welcome 2 spectator");
+    assert_eq!(read_line(&mut reader), "position startpos");
+    writeln!(stream, "move 1 e2e4 {}", key_after(&["e2e4"])).unwrap();
+    assert!(matches!(
+        &poll_until_event(&mut host)[..],
+        [NetworkEvent::Error(_)]
+    ));
+    assert_eq!(read_line(&mut reader), "error unexpected message 'move'");
+    assert_eq!(host.game().moves().count(), 0);
+}
+
+#[test]
+fn clients_are_told_the_variant() {
+    let game = Game::new(Position::starting(&KingOfTheHill));
+    let mut host = NetworkPeer::host("127.0.0.1:0", PieceColour::White, game).unwrap();
+    let mut stream = TcpStream::connect(host.local_addr().unwrap()).unwrap();
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    writeln!(stream, "hello bevy-chess 2 2 spectator").unwrap();
+    assert_eq!(poll_until_event(&mut host), [NetworkEvent::SpectatorJoined]);
+    assert_eq!(read_line(&mut reader), "welcome 2 spectator");
+    assert_eq!(read_line(&mut reader), "variant kingofthehill");
+    assert_eq!(read_line(&mut reader), "position startpos");
+
+    let address = host.local_addr().unwrap();
+    let client = thread::spawn(move || NetworkPeer::connect(address));
+    assert_eq!(poll_until_event(&mut host), [NetworkEvent::Connected]);
+    let client = client.join().unwrap().unwrap();
+    assert_eq!(
+        client.game().position().variant().name(),
+        "King of the Hill"
+    );
+}
@@ -0,0 +1,188 @@
+use chess::{Game, PgnGame, PgnTags, Position};
+
+/// Plays each move, given in SAN, from `position`
+fn play(position: Position, moves: &str) -> Game {
+    let mut game = Game::new(position);
+    for san in moves.split_whitespace() {
+        let chess_move = game.position().parse_san(san).unwrap();
+        game.make_move(chess_move).unwrap();
+    }
+    game
+}
+
+#[test]
+fn opera_game() {
+    let game = play(
+        Position::default(),
+        "e4 e5 Nf3 d6 d4 Bg4 dxe5 Bxf3 Qxf3 dxe5 Bc4 Nf6 Qb3 Qe7 Nc3 c6 Bg5 b5 Nxb5 cxb5 \
+        Bxb5+ Nbd7 O-O-O Rd8 Rxd7 Rxd7 Rd1 Qe6 Bxd7+ Nxd7 Qb8+ Nxb8 Rd8#",
+    );
+    let tags = PgnTags {
+        event: "Casual game".to_string(),
+        site: "Paris FRA".to_string(),
+        date: "1858.??.??".to_string(),
+        white: "Morphy, Paul".to_string(),
+        black: "Duke Karl / Count Isouard".to_string(),
+        ..PgnTags::default()
+    };
+
+    assert_eq!(
+        game.to_pgn(&tags),
+        r#"[Event "Casual game"]
+[Site "Paris FRA"]
+[Date "1858.??.??"]
+[Round "?"]
+[White "Morphy, Paul"]
+[Black "Duke Karl / Count Isouard"]
+[Result "1-0"]
+
+1. e4 e5 2. Nf3 d6 3. d4 Bg4 4. dxe5 Bxf3 5. Qxf3 dxe5 6. Bc4 Nf6 7. Qb3 Qe7 8.
+Nc3 c6 9. Bg5 b5 10. Nxb5 cxb5 11. Bxb5+ Nbd7 12. O-O-O Rd8 13. Rxd7 Rxd7 14.
+Rd1 Qe6 15. Bxd7+ Nxd7 16. Qb8+ Nxb8 17. Rd8# 1-0
+"#
+    );
+}
+
+#[test]
+fn custom_starting_position() {
+    let fen = "4k3/8/8/8/8/8/4P3/4K2R b K - 0 30";
+    let game = play(fen.parse().unwrap(), "Kd7 O-O Kc6");
+    let pgn = game.to_pgn(&PgnTags::default());
+
+    assert!(pgn.contains("[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K2R b K - 0 30\"]\n"));
+    assert!(pgn.ends_with("\n\n30... Kd7 31. O-O Kc6 *\n"));
+}
+
+#[test]
+fn chess960_game() {
+    let game = play(Position::chess960(96).unwrap(), "Ng3 Ng6 O-O O-O");
+    let pgn = game.to_pgn(&PgnTags::default());
+
+    assert!(pgn.contains(
+        "[Variant \"Chess960\"]\n[SetUp \"1\"]\n\
+         [FEN \"bbqnrnkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNRNKR w KQkq - 0 1\"]\n"
+    ));
+    assert!(pgn.ends_with("\n\n1. Ng3 Ng6 2. O-O O-O *\n"));
+    let games = PgnGame::parse_all(&pgn).unwrap();
+    assert_eq!(games[0].game.position().to_fen(), game.position().to_fen());
+}
+
+#[test]
+fn results() {
+    let result = |fen: &str| Game::new(fen.parse().unwrap()).result();
+
+    assert_eq!(Game::default().result(), "*");
+    assert_eq!(result("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1"), "1-0");
+    assert_eq!(result("6k1/8/8/8/8/8/5PPP/r5K1 w - - 0 1"), "0-1");
+    assert_eq!(result("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1"), "1/2-1/2");
+    assert!(!Game::default()
+        .to_pgn(&PgnTags::default())
+        .contains("SetUp"));
+}
+
+#[test]
+fn lines_are_wrapped() {
+    let mut moves = String::new();
+    for _ in 0..20 {
+        moves.push_str("Nf3 Nf6 Ng1 Ng8 ");
+    }
+    // the fifth repetition ends the game, so only play until just before it
+    let moves: Vec<_> = moves.split_whitespace().take(15).collect();
+    let game = play(Position::default(), &moves.join(" "));
+    let pgn = game.to_pgn(&PgnTags::default());
+
+    let movetext = pgn.split("\n\n").nth(1).unwrap();
+    assert!(movetext.lines().count() > 1);
+    assert!(movetext.lines().all(|line| line.len() <= 80));
+}
+
+#[test]
+fn tag_values_are_escaped() {
+    let tags = PgnTags {
+        event: r#"The "Immortal" Game \ 1851"#.to_string(),
+        ..PgnTags::default()
+    };
+    assert!(Game::default()
+        .to_pgn(&tags)
+        .starts_with(r#"[Event "The \"Immortal\" Game \\ 1851"]"#));
+}
+
+#[test]
+fn read_exported_game() {
+    let game = play(
+        "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3"
+            .parse()
+            .unwrap(),
+        "Bb5 a6 Ba4 Nf6 O-O Be7",
+    );
+    let tags = PgnTags {
+        white: "White \"W\" Player".to_string(),
+        ..PgnTags::default()
+    };
+
+    let games = PgnGame::parse_all(&game.to_pgn(&tags)).unwrap();
+    assert_eq!(games.len(), 1);
+    assert_eq!(games[0].tag("White"), Some("White \"W\" Player"));
+    assert_eq!(games[0].tag("SetUp"), Some("1"));
+    assert_eq!(games[0].result, "*");
+    assert_eq!(games[0].game.to_pgn(&tags), game.to_pgn(&tags));
+}
+
+#[test]
+fn read_several_games() {
+    let pgn = r#"
+% written by hand
+[Event "First"]
+[Result "1-0"]
+
+1. e4 {best by test} e5 $1 2. Bc4 (2. Nf3 Nc6 (2... d6) 3. Bb5) 2... Nc6 3. Qh5 Nf6?? 4.Qxf7# 1-0
+
+[Event "Second"]
+[Result "1/2-1/2"]
+
+; a short draw
+1. d4 d5 2. c4 e6 !? 1/2-1/2
+1. Nf3 *
+"#;
+
+    let games = PgnGame::parse_all(pgn).unwrap();
+    assert_eq!(games.len(), 3);
+
+    assert_eq!(games[0].tag("Event"), Some("First"));
+    assert_eq!(games[0].result, "1-0");
+    assert_eq!(games[0].game.moves().count(), 7);
+    assert_eq!(games[0].game.result(), "1-0");
+
+    assert_eq!(games[1].tag("Event"), Some("Second"));
+    assert_eq!(games[1].result, "1/2-1/2");
+    assert_eq!(
+        games[1].game.position().to_fen(),
+        "rnbqkbnr/ppp2ppp/4p3/3p4/2PP4/8/PP2PPPP/RNBQKBNR w KQkq - 0 3"
+    );
+
+    assert!(games[2].tags.is_empty());
+    assert_eq!(games[2].game.moves().count(), 1);
+}
+
+#[test]
+fn unfinished_games() {
+    let games = PgnGame::parse_all("[Event \"A\"]\n\n1. e4 e5\n\n[Event \"B\"]\n\n1. d4").unwrap();
+    assert_eq!(games.len(), 2);
+    assert!(games.iter().all(|game| game.result == "*"));
+    assert_eq!(games[1].game.moves().count(), 1);
+
+    assert!(PgnGame::parse_all("").unwrap().is_empty());
+}
+
+#[test]
+fn invalid_pgn() {
+    let error = PgnGame::parse_all("1. e4 e5 *\n\n1. e4 e5 2. Ke3 *").unwrap_err();
+    assert!(error.starts_with("game 2: "), "{error}");
+    assert!(error.contains("Ke3"), "{error}");
+
+    assert!(PgnGame::parse_all("[Event \"Unterminated]\n1. e4 *").is_err());
+    assert!(PgnGame::parse_all("1. e4 {no end *").is_err());
+    assert!(PgnGame::parse_all("1. e4 (1. d4 *").is_err());
+    assert!(PgnGame::parse_all("1. e4 ) *").is_err());
+    assert!(PgnGame::parse_all("[FEN \"not a position\"]\n1. e4 *").is_err());
+}
@@ -0,0 +1,69 @@
+use chess::{ChessMove, DrawReason, Game, Position, Status};
+
+/// Makes each move, given in the long algebraic notation used by UCI
+fn play(game: &mut Game, moves: &[&str]) {
+    for chess_move in moves {
+        let (from, to) = chess_move.split_at(2);
+        game.make_move(ChessMove::new(from.parse().unwrap(), to.parse().unwrap()))
+            .unwrap();
+    }
+}
+
+const KNIGHT_SHUFFLE: [&str; 4] = ["g1f3", "g8f6", "f3g1", "f6g8"];
+
+#[test]
+fn threefold_repetition_can_be_claimed() {
+    let mut game = Game::default();
+    play(&mut game, &KNIGHT_SHUFFLE);
+    assert_eq!(game.repetitions(), 2);
+    assert_eq!(game.claimable_draw(), None);
+    assert!(game.claim_draw().is_err());
+
+    play(&mut game, &KNIGHT_SHUFFLE);
+    assert_eq!(game.repetitions(), 3);
+    assert_eq!(game.status(), Status::OnGoing);
+    assert_eq!(game.claim_draw(), Ok(DrawReason::ThreefoldRepetition));
+    assert_eq!(game.status(), Status::Draw(DrawReason::ThreefoldRepetition));
+}
+
+#[test]
+fn fivefold_repetition_is_automatic() {
+    let mut game = Game::default();
+    for _ in 0..3 {
+        play(&mut game, &KNIGHT_SHUFFLE);
+    }
+    assert_eq!(game.status(), Status::OnGoing);
+
+    play(&mut game, &KNIGHT_SHUFFLE);
+    assert_eq!(game.repetitions(), 5);
+    assert_eq!(game.status(), Status::Draw(DrawReason::FivefoldRepetition));
+
+    game.unmake_move();
+    assert_eq!(game.status(), Status::OnGoing);
+}
+
+#[test]
+fn castling_rights_distinguish_positions() {
+    let position: Position = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1".parse().unwrap();
+    let mut game = Game::new(position);
+    // the first rook moves lose the castling rights, so only later positions repeat
+    play(&mut game, &["h1g1", "h8g8", "g1h1", "g8h8"]);
+    assert_eq!(game.repetitions(), 1);
+
+    play(&mut game, &["h1g1", "h8g8", "g1h1", "g8h8"]);
+    assert_eq!(game.repetitions(), 2);
+}
+
+#[test]
+fn en_passant_only_counts_when_it_can_be_taken() {
+    let key = |fen: &str| fen.parse::<Position>().unwrap().key();
+
+    assert_ne!(
+        key("4k3/8/8/8/3Pp3/8/8/4K3 b - d3 0 1"),
+        key("4k3/8/8/8/3Pp3/8/8/4K3 b - - 0 1")
+    );
+    assert_eq!(
+        key("4k3/8/8/8/3P4/8/8/4K3 b - d3 0 1"),
+        key("4k3/8/8/8/3P4/8/8/4K3 b - - 0 1")
+    );
+}
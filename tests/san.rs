@@ -0,0 +1,116 @@
+use chess::{divide, ChessMove, PieceType, Position};
+
+const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+const POSITION_4: &str = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1";
+const POSITION_5: &str = "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8";
+
+/// Morphy v Duke Karl / Count Isouard, Paris 1858
+const OPERA_GAME: &str = "e4 e5 Nf3 d6 d4 Bg4 dxe5 Bxf3 Qxf3 dxe5 Bc4 Nf6 Qb3 Qe7 Nc3 c6 Bg5 b5 \
+    Nxb5 cxb5 Bxb5+ Nbd7 O-O-O Rd8 Rxd7 Rxd7 Rd1 Qe6 Bxd7+ Nxd7 Qb8+ Nxb8 Rd8#";
+
+/// Kasparov v Topalov, Wijk aan Zee 1999
+const KASPAROV_TOPALOV: &str = "e4 d6 d4 Nf6 Nc3 g6 Be3 Bg7 Qd2 c6 f3 b5 Nge2 Nbd7 Bh6 Bxh6 \
+    Qxh6 Bb7 a3 e5 O-O-O Qe7 Kb1 a6 Nc1 O-O-O Nb3 exd4 Rxd4 c5 Rd1 Nb6 g3 Kb8 Na5 Ba8 Bh3 d5 \
+    Qf4+ Ka7 Rhe1 d4 Nd5 Nbxd5 exd5 Qd6 Rxd4 cxd4 Re7+ Kb6 Qxd4+ Kxa5 b4+ Ka4 Qc3 Qxd5 Ra7 Bb7 \
+    Rxb7 Qc4 Qxf6 Kxa3 Qxa6+ Kxb4 c3+ Kxc3 Qa1+ Kd2 Qb2+ Kd1 Bf1 Rd2 Rd7 Rxd7 Bxc4 bxc4 Qxh8 \
+    Rd3 Qa8 c3 Qa4+ Ke1 f4 f5 Kc1 Rd2 Qa7";
+
+/// Plays a game given in SAN, checking that each move is written back exactly as it was read
+fn assert_round_trip(moves: &str) {
+    let mut position = Position::default();
+    for san in moves.split_whitespace() {
+        let chess_move = position
+            .parse_san(san)
+            .unwrap_or_else(|err| panic!("{err} in {}", position.to_fen()));
+        assert_eq!(position.to_san(&chess_move), san, "{}", position.to_fen());
+        position.make_move(chess_move);
+    }
+}
+
+#[test]
+fn full_games_round_trip() {
+    assert_round_trip(OPERA_GAME);
+    assert_round_trip(KASPAROV_TOPALOV);
+}
+
+#[test]
+fn every_legal_move_round_trips() {
+    for fen in [chess::STARTING_FEN, KIWIPETE, POSITION_4, POSITION_5] {
+        let mut position: Position = fen.parse().unwrap();
+        for (chess_move, _) in divide(&mut position, 1) {
+            let san = position.to_san(&chess_move);
+            assert_eq!(position.parse_san(&san), Ok(chess_move), "{san} in {fen}");
+        }
+    }
+}
+
+#[test]
+fn notation() {
+    let position: Position = "r3k2r/1P6/8/3pP3/8/1N3N2/8/R3K2R w KQkq d6 0 1"
+        .parse()
+        .unwrap();
+    let san = |from: &str, to: &str, promotion: Option<PieceType>| {
+        position.to_san(&ChessMove {
+            from: from.parse().unwrap(),
+            to: to.parse().unwrap(),
+            promotion,
+            drop: None,
+        })
+    };
+
+    assert_eq!(san("e1", "g1", None), "O-O");
+    assert_eq!(san("e1", "c1", None), "O-O-O");
+    assert_eq!(san("e5", "d6", None), "exd6");
+    assert_eq!(san("b7", "a8", Some(PieceType::Queen)), "bxa8=Q+");
+    assert_eq!(san("b7", "b8", Some(PieceType::Knight)), "b8=N");
+    assert_eq!(san("b3", "d4", None), "Nbd4");
+    assert_eq!(san("a1", "a8", None), "Rxa8+");
+}
+
+#[test]
+fn disambiguation_by_rank_and_square() {
+    let position: Position = "1k6/8/8/8/Q6Q/8/8/4K2Q w - - 0 1".parse().unwrap();
+    let queen_move = |from: &str| ChessMove::new(from.parse().unwrap(), "e4".parse().unwrap());
+
+    // the queens on a4 and h4 share a rank, while the queens on h4 and h1 share a file
+    assert_eq!(position.to_san(&queen_move("a4")), "Qae4");
+    assert_eq!(position.to_san(&queen_move("h1")), "Q1e4");
+    assert_eq!(position.to_san(&queen_move("h4")), "Qh4e4");
+    assert_eq!(position.parse_san("Qh4e4"), Ok(queen_move("h4")));
+}
+
+#[test]
+fn lenient_parsing() {
+    let position = Position::default();
+    let knight = ChessMove::new("g1".parse().unwrap(), "f3".parse().unwrap());
+
+    assert_eq!(position.parse_san("Nf3!?"), Ok(knight));
+    assert_eq!(position.parse_san("Ngf3"), Ok(knight));
+    assert_eq!(position.parse_san("Ng1f3"), Ok(knight));
+    assert!(position.parse_san("Nf4").is_err());
+    assert!(position.parse_san("O-O").is_err());
+    assert!(position.parse_san("e9").is_err());
+}
+
+#[test]
+fn promotions_may_leave_out_the_equals_sign() {
+    let position: Position = "8/4P3/8/8/8/8/8/k1K5 w - - 0 1".parse().unwrap();
+    let promotion = |piece_type| ChessMove {
+        promotion: Some(piece_type),
+        ..ChessMove::new("e7".parse().unwrap(), "e8".parse().unwrap())
+    };
+
+    assert_eq!(position.parse_san("e8=Q"), Ok(promotion(PieceType::Queen)));
+    assert_eq!(position.parse_san("e8Q"), Ok(promotion(PieceType::Queen)));
+    assert_eq!(position.parse_san("e8N"), Ok(promotion(PieceType::Knight)));
+    assert!(position.parse_san("e8").is_err());
+    assert!(position.parse_san("e8P").is_err());
+    assert!(position.parse_san("e8X").is_err());
+}
+
+#[test]
+fn ambiguous_moves_are_rejected() {
+    let position: Position = "4k3/8/8/8/8/1N3N2/8/4K3 w - - 0 1".parse().unwrap();
+    assert!(position.parse_san("Nd4").is_err());
+    assert!(position.parse_san("Nbd4").is_ok());
+}
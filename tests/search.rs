@@ -0,0 +1,112 @@
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
+
+use chess::{Game, Position, Score, SearchLimits};
+
+fn best_move(fen: &str, depth: u32) -> (String, Score) {
+    let game = Game::new(fen.parse().unwrap());
+    let limits = SearchLimits {
+        depth: Some(depth),
+        ..SearchLimits::default()
+    };
+    let info = game
+        .search(limits, &AtomicBool::new(false), |_| {})
+        .unwrap();
+    (info.best_move().to_string(), info.score)
+}
+
+#[test]
+fn starting_position_is_level() {
+    assert_eq!(Position::default().evaluate(), 0);
+}
+
+#[test]
+fn evaluation_is_from_the_side_to_move() {
+    let white: Position = "4k3/8/8/8/8/8/8/Q3K3 w - - 0 1".parse().unwrap();
+    let black: Position = "4k3/8/8/8/8/8/8/Q3K3 b - - 0 1".parse().unwrap();
+    assert!(white.evaluate() > 800);
+    assert_eq!(white.evaluate(), -black.evaluate());
+}
+
+#[test]
+fn finds_mate_in_one() {
+    let fen = "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4";
+    assert_eq!(best_move(fen, 3), ("h5f7".to_string(), Score::Mate(1)));
+}
+
+#[test]
+fn finds_mate_in_two() {
+    // the rooks need a move to cut the King off before either can mate on the back rank
+    let (_, score) = best_move("7k/8/8/8/8/8/R7/1R5K w - - 0 1", 4);
+    assert_eq!(score, Score::Mate(2));
+}
+
+#[test]
+fn avoids_being_mated() {
+    // Black threatens mate on the back rank, so White has to give the King a way out
+    let (_, score) = best_move("r5k1/5ppp/8/8/8/8/5PPP/6K1 w - - 0 1", 3);
+    assert!(matches!(score, Score::Centipawns(_)), "{score:?}");
+}
+
+#[test]
+fn takes_a_hanging_queen() {
+    let fen = "4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1";
+    assert_eq!(best_move(fen, 2).0, "d2d5");
+}
+
+#[test]
+fn does_not_lose_the_queen_to_a_recapture() {
+    // taking the pawn on e5 loses the queen to the pawn on d6
+    let fen = "4k3/8/3p4/4p3/8/8/1Q6/4K3 w - - 0 1";
+    assert_ne!(best_move(fen, 1).0, "b2e5");
+}
+
+#[test]
+fn promotes_to_a_queen() {
+    assert_eq!(best_move("8/4P2k/8/8/8/8/8/4K3 w - - 0 1", 2).0, "e7e8q");
+}
+
+#[test]
+fn no_moves_to_search() {
+    let game = Game::new("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1".parse().unwrap());
+    assert!(game
+        .search(SearchLimits::default(), &AtomicBool::new(false), |_| {})
+        .is_none());
+}
+
+#[test]
+fn reports_each_depth() {
+    let limits = SearchLimits {
+        depth: Some(3),
+        ..SearchLimits::default()
+    };
+    let mut depths = Vec::new();
+    let info = Game::default()
+        .search(limits, &AtomicBool::new(false), |info| {
+            depths.push(info.depth)
+        })
+        .unwrap();
+
+    assert_eq!(depths, vec![1, 2, 3]);
+    assert_eq!(info.depth, 3);
+    assert!(!info.pv.is_empty());
+    assert!(Game::default().legal_moves().contains(&info.best_move()));
+}
+
+#[test]
+fn stops_early() {
+    // the first depth is always completed, so there is still a move to play
+    let info = Game::default()
+        .search(SearchLimits::default(), &AtomicBool::new(true), |_| {})
+        .unwrap();
+    assert_eq!(info.depth, 1);
+
+    let limits = SearchLimits {
+        time: Some(Duration::from_millis(100)),
+        ..SearchLimits::default()
+    };
+    let info = Game::default()
+        .search(limits, &AtomicBool::new(false), |_| {})
+        .unwrap();
+    assert!(info.time < Duration::from_secs(5));
+}
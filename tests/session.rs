@@ -0,0 +1,191 @@
+use std::time::Duration;
+
+use chess::{
+    Clock, DrawReason, Game, PieceColour, PieceType, Position, Session, Status, TimeControl,
+};
+
+/// Makes each move, given in the long algebraic notation used by UCI
+fn play(game: &mut Game, moves: &[&str]) {
+    for uci in moves {
+        let chess_move = game.position().parse_uci(uci).unwrap();
+        game.make_move(chess_move).unwrap();
+    }
+}
+
+fn reload(session: &Session) -> Session {
+    session.to_string().parse().unwrap()
+}
+
+#[test]
+fn sessions_are_written_in_the_documented_format() {
+    let mut game = Game::default();
+    play(&mut game, &["e2e4", "e7e5", "g1f3"]);
+    game.offer_draw(PieceColour::White).unwrap();
+    let mut clock = Clock::new(TimeControl::Fischer {
+        time: Duration::from_secs(300),
+        increment: Duration::from_secs(2),
+    });
+    clock.tick(PieceColour::White, Duration::from_millis(1500));
+    clock.press(PieceColour::White);
+    clock.tick(PieceColour::Black, Duration::from_millis(1200));
+
+    let session = Session {
+        game,
+        white: "Alice".to_string(),
+        black: "Bob".to_string(),
+        clock: Some(clock),
+    };
+    assert_eq!(
+        session.to_string(),
+        "bevy-chess session 1\n\
+         white Alice\n\
+         black Bob\n\
+         fen rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1\n\
+         moves e2e4 e7e5 g1f3\n\
+         offer white\n\
+         clock fischer 300000 2000 300500 298800 1200\n"
+    );
+}
+
+#[test]
+fn sessions_are_restored() {
+    let position: Position = "4k3/1P5p/8/8/8/8/8/4K3 w - - 0 1".parse().unwrap();
+    let mut game = Game::new(position);
+    play(&mut game, &["b7b8n", "e8e7"]);
+    let clock = Clock::resume(
+        TimeControl::SimpleDelay {
+            time: Duration::from_secs(60),
+            delay: Duration::from_secs(3),
+        },
+        Duration::from_millis(41_250),
+        Duration::from_millis(52_000),
+        Duration::from_millis(800),
+    );
+    let session = Session {
+        game,
+        white: "A. Player".to_string(),
+        black: "?".to_string(),
+        clock: Some(clock.clone()),
+    };
+
+    let loaded = reload(&session);
+    assert_eq!(loaded.white, "A. Player");
+    assert_eq!(loaded.black, "?");
+    assert_eq!(
+        loaded.game.starting_position().to_fen(),
+        session.game.starting_position().to_fen()
+    );
+    assert!(loaded.game.moves().eq(session.game.moves()));
+    // the piece chosen for the promotion is kept
+    let knight = loaded.game.position().piece_at("b8".parse().unwrap());
+    assert_eq!(
+        knight.map(|piece| piece.piece_type),
+        Some(PieceType::Knight)
+    );
+
+    let loaded_clock = loaded.clock.unwrap();
+    assert_eq!(loaded_clock.time_control(), clock.time_control());
+    assert_eq!(
+        loaded_clock.remaining(PieceColour::White),
+        clock.remaining(PieceColour::White)
+    );
+    assert_eq!(
+        loaded_clock.remaining(PieceColour::Black),
+        clock.remaining(PieceColour::Black)
+    );
+    assert_eq!(loaded_clock.spent(), clock.spent());
+}
+
+#[test]
+fn endings_off_the_board_are_restored() {
+    let session = |game: Game| Session {
+        game,
+        white: "?".to_string(),
+        black: "?".to_string(),
+        clock: None,
+    };
+
+    let mut game = Game::default();
+    play(&mut game, &["f2f3"]);
+    game.resign(PieceColour::White).unwrap();
+    let loaded = reload(&session(game));
+    assert_eq!(loaded.game.status(), Status::Resigned(PieceColour::White));
+
+    let mut game = Game::default();
+    game.offer_draw(PieceColour::White).unwrap();
+    game.accept_draw(PieceColour::Black).unwrap();
+    let loaded = reload(&session(game));
+    assert_eq!(loaded.game.status(), Status::Draw(DrawReason::Agreement));
+
+    let mut game = Game::default();
+    play(&mut game, &["g1f3", "g8f6", "f3g1", "f6g8"]);
+    play(&mut game, &["g1f3", "g8f6", "f3g1", "f6g8"]);
+    game.claim_draw().unwrap();
+    let loaded = reload(&session(game));
+    assert_eq!(
+        loaded.game.status(),
+        Status::Draw(DrawReason::ThreefoldRepetition)
+    );
+
+    let mut game = Game::default();
+    game.run_out_of_time().unwrap();
+    let loaded = reload(&session(game));
+    assert_eq!(loaded.game.status(), Status::OutOfTime);
+    assert!(loaded.clock.is_none());
+}
+
+#[test]
+fn invalid_sessions_are_rejected() {
+    let valid = "bevy-chess session 1\nfen 4k3/8/8/8/8/8/8/4K2R w K - 0 1\nmoves e1g1\n";
+    let session: Session = valid.parse().unwrap();
+    assert_eq!(session.white, "?");
+    assert_eq!(session.game.moves().count(), 1);
+
+    for (invalid, error) in [
+        ("", "not a session file"),
+        ("[Event \"?\"]\n", "not a session file"),
+        (
+            "bevy-chess session 2\nmoves e2e4\n",
+            "unsupported session version '2'",
+        ),
+        (
+            "bevy-chess session 1\nresult 1-0\n",
+            "line 2: unknown field 'result'",
+        ),
+        (
+            "bevy-chess session 1\n\noffer red\n",
+            "line 3: invalid colour 'red'",
+        ),
+        (
+            "bevy-chess session 1\nclock fischer 300000\n",
+            "line 2: invalid clock 'fischer 300000'",
+        ),
+        (
+            "bevy-chess session 1\nfen 4k3/8/8/3P4/8/8/8/4K3 w - e6 0 1\n",
+            "line 2: invalid en passant target 'e6'",
+        ),
+        (
+            "bevy-chess session 1\nfen 99999999999999999/8/8/8/8/8/8/8 w - - 0 1\n",
+            "line 2: invalid empty squares '9' in rank 8",
+        ),
+        (
+            "bevy-chess session 1\nvariant Atomic\nfen 8/8/8/8/8/8/8/4K3 w - - 0 1\n",
+            "line 3: expected one Black King but found 0",
+        ),
+        (
+            "bevy-chess session 1\nmoves e2e5\n",
+            "e2e5 is not a legal move",
+        ),
+        (
+            "bevy-chess session 1\nending claim\n",
+            "there is no draw to claim",
+        ),
+    ] {
+        assert_eq!(invalid.parse::<Session>().err().as_deref(), Some(error));
+    }
+
+    // the FEN is read under the rules of the variant, even when it comes first
+    let antichess = "bevy-chess session 1\nfen 8/8/8/8/8/8/1q6/8 w - - 0 1\nvariant Antichess\n";
+    let session: Session = antichess.parse().unwrap();
+    assert_eq!(session.game.position().variant().name(), "Antichess");
+}
@@ -0,0 +1,221 @@
+use std::time::Duration;
+
+use chess::{go_command, parse_info, position_command, Position, Score, SearchLimits};
+
+#[test]
+fn parse_uci_moves() {
+    let position: Position = "r3k2r/1P6/8/8/8/8/8/R3K2R w KQkq - 0 1".parse().unwrap();
+
+    let castling = position.parse_uci("e1g1").unwrap();
+    assert!(position.is_castling(&castling));
+    assert_eq!(position.parse_uci("e1h1").unwrap(), castling);
+    assert_eq!(position.parse_uci("b7a8n").unwrap().to_string(), "b7a8n");
+
+    assert!(position.parse_uci("b7a8").is_err());
+    assert!(position.parse_uci("b7a8k").is_err());
+    assert!(position.parse_uci("e1e3").is_err());
+    assert!(position.parse_uci("e1").is_err());
+    assert!(position.parse_uci("z9e1").is_err());
+}
+
+#[test]
+fn commands() {
+    let position = Position::default();
+    let moves = [
+        position.parse_uci("e2e4").unwrap(),
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1"
+            .parse::<Position>()
+            .unwrap()
+            .parse_uci("c7c5")
+            .unwrap(),
+    ];
+    assert_eq!(position_command(&position, &[]), "position startpos");
+    assert_eq!(
+        position_command(&position, &moves),
+        "position startpos moves e2e4 c7c5"
+    );
+
+    let fen = "4k3/8/8/8/8/8/8/4K2R w K - 0 1";
+    assert_eq!(
+        position_command(&fen.parse().unwrap(), &[]),
+        format!("position fen {fen}")
+    );
+
+    assert_eq!(go_command(&SearchLimits::default()), "go infinite");
+    let limits = SearchLimits {
+        depth: Some(8),
+        time: Some(Duration::from_millis(1500)),
+        nodes: None,
+    };
+    assert_eq!(go_command(&limits), "go depth 8 movetime 1500");
+}
+
+#[test]
+fn info_lines() {
+    let position = Position::default();
+    let info = parse_info(
+        &position,
+        "info depth 12 seldepth 18 multipv 1 score cp -35 nodes 123456 nps 1000 time 250 pv e2e4 e7e5 g1f3",
+    )
+    .unwrap();
+    assert_eq!(info.depth, 12);
+    assert_eq!(info.score, Score::Centipawns(-35));
+    assert_eq!(info.nodes, 123456);
+    assert_eq!(info.time, Duration::from_millis(250));
+    assert_eq!(info.best_move().to_string(), "e2e4");
+    assert_eq!(info.pv.len(), 3);
+
+    let info = parse_info(&position, "info depth 3 score mate -2 pv f2f3 e7e5 zzzz").unwrap();
+    assert_eq!(info.score, Score::Mate(-2));
+    assert_eq!(info.pv.len(), 2);
+
+    assert!(parse_info(&position, "info depth 5 currmove e2e4 currmovenumber 1").is_none());
+    assert!(parse_info(&position, "info string score cp 10 pv e2e4").is_none());
+    assert!(parse_info(&position, "bestmove e2e4").is_none());
+}
+
+#[cfg(unix)]
+mod engine {
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::{Path, PathBuf};
+    use std::sync::atomic::AtomicBool;
+    use std::thread;
+    use std::time::Duration;
+
+    use chess::{Position, Score, SearchLimits, UciEngine};
+
+    /// Writes a stand-in engine that logs every command it is sent and always plays `best_move`,
+    /// only answering an infinite search once it is told to stop
+    fn scripted_engine(name: &str, best_move: &str) -> (PathBuf, PathBuf) {
+        let directory = std::env::temp_dir();
+        let id = format!("chess-uci-{name}-{}", std::process::id());
+        let script = directory.join(format!("{id}.sh"));
+        let log = directory.join(format!("{id}.log"));
+
+        let contents = format!(
+            r#"#!/bin/sh
+while read -r line; do
+    echo "$line" >> "{log}"
+    case "$line" in
+        uci) echo "id name Scripted Engine"; echo "id author Tests"; echo "option name Hash type spin default 16 min 1 max 64"; echo "uciok" ;;
+        isready) echo "readyok" ;;
+        "go infinite") echo "info depth 1 score cp 5 nodes 20 time 1 pv {best_move}" ;;
+        go*) echo "info string searching"; echo "info depth 2 score mate 1 nodes 40 time 2 pv {best_move}"; echo "bestmove {best_move} ponder a7a6" ;;
+        stop) echo "bestmove {best_move}" ;;
+        quit) exit 0 ;;
+    esac
+done
+"#,
+            log = log.display()
+        );
+        let _ = fs::remove_file(&log);
+        fs::write(&script, contents).unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+        (script, log)
+    }
+
+    fn start(script: &Path) -> UciEngine {
+        UciEngine::start(script.to_str().unwrap(), &[]).unwrap()
+    }
+
+    #[test]
+    fn handshake() {
+        let (script, log) = scripted_engine("handshake", "e2e4");
+        let mut engine = start(&script);
+        assert_eq!(engine.name(), Some("Scripted Engine"));
+        engine.set_option("Hash", "32").unwrap();
+        engine.new_game().unwrap();
+        drop(engine);
+
+        let commands = fs::read_to_string(log).unwrap();
+        assert_eq!(
+            commands.lines().collect::<Vec<_>>(),
+            vec![
+                "uci",
+                "isready",
+                "setoption name Hash value 32",
+                "isready",
+                "ucinewgame",
+                "isready",
+                "quit"
+            ]
+        );
+    }
+
+    #[test]
+    fn best_move() {
+        let (script, log) = scripted_engine("best-move", "e7e5");
+        let mut engine = start(&script);
+        let position = Position::default();
+        let moves = [position.parse_uci("e2e4").unwrap()];
+        let limits = SearchLimits {
+            time: Some(Duration::from_millis(100)),
+            ..SearchLimits::default()
+        };
+
+        let mut reports = Vec::new();
+        let chess_move = engine
+            .best_move(&position, &moves, limits, &AtomicBool::new(false), |info| {
+                reports.push(info.clone())
+            })
+            .unwrap();
+        assert_eq!(chess_move.to_string(), "e7e5");
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].score, Score::Mate(1));
+        drop(engine);
+
+        let commands = fs::read_to_string(log).unwrap();
+        assert!(commands.contains("position startpos moves e2e4\ngo movetime 100\n"));
+    }
+
+    #[test]
+    fn stop_an_infinite_search() {
+        let (script, _) = scripted_engine("stop", "d2d4");
+        let mut engine = start(&script);
+        let stop = AtomicBool::new(false);
+
+        let chess_move = thread::scope(|scope| {
+            scope.spawn(|| {
+                thread::sleep(Duration::from_millis(100));
+                stop.store(true, std::sync::atomic::Ordering::Relaxed);
+            });
+            engine.best_move(
+                &Position::default(),
+                &[],
+                SearchLimits::default(),
+                &stop,
+                |_| {},
+            )
+        });
+        assert_eq!(chess_move.unwrap().to_string(), "d2d4");
+    }
+
+    #[test]
+    fn illegal_moves() {
+        let (script, _) = scripted_engine("illegal", "e2e5");
+        let mut engine = start(&script);
+        let stop = AtomicBool::new(false);
+        let limits = SearchLimits {
+            depth: Some(1),
+            ..SearchLimits::default()
+        };
+
+        let error = engine
+            .best_move(&Position::default(), &[], limits, &stop, |_| {})
+            .unwrap_err();
+        assert!(error.contains("e2e5"), "{error}");
+
+        // the moves sent to the engine are checked too
+        let position = Position::default();
+        let moves = [position.parse_uci("e2e4").unwrap(); 2];
+        assert!(engine
+            .best_move(&position, &moves, limits, &stop, |_| {})
+            .is_err());
+    }
+
+    #[test]
+    fn missing_engine() {
+        assert!(UciEngine::start("/nonexistent/engine", &[]).is_err());
+    }
+}
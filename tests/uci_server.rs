@@ -0,0 +1,197 @@
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chess::{Position, Score, SearchLimits, UciEngine};
+
+const ENGINE: &str = env!("CARGO_BIN_EXE_uci");
+
+/// Sends `commands` to the engine followed by `quit`, returning everything it printed
+fn run(commands: &str) -> String {
+    let mut child = Command::new(ENGINE)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    writeln!(stdin, "{commands}\nquit").unwrap();
+    let output = child.wait_with_output().unwrap();
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn handshake() {
+    let mut engine = UciEngine::start(ENGINE, &[]).unwrap();
+    assert_eq!(engine.name(), Some("bevy-chess"));
+    engine.set_option("Move Overhead", "100").unwrap();
+    engine.new_game().unwrap();
+
+    let output = run("uci\nsetoption name Hash value 16\nisready");
+    assert!(output.contains("option name Move Overhead type spin"));
+    assert!(output.contains("uciok\ninfo string unknown option 'Hash'\nreadyok\n"));
+}
+
+#[test]
+fn finds_mate() {
+    let mut engine = UciEngine::start(ENGINE, &[]).unwrap();
+    let position: Position = "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/8/PPPP1PPP/RNBQK1NR w KQkq - 2 3"
+        .parse()
+        .unwrap();
+    let moves = [
+        position.parse_uci("d1h5").unwrap(),
+        "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR b KQkq - 3 3"
+            .parse::<Position>()
+            .unwrap()
+            .parse_uci("d7d6")
+            .unwrap(),
+    ];
+    let limits = SearchLimits {
+        depth: Some(3),
+        ..SearchLimits::default()
+    };
+
+    let mut scores = Vec::new();
+    let chess_move = engine
+        .best_move(&position, &moves, limits, &AtomicBool::new(false), |info| {
+            scores.push(info.score)
+        })
+        .unwrap();
+    assert_eq!(chess_move.to_string(), "h5f7");
+    assert_eq!(scores.last(), Some(&Score::Mate(1)));
+}
+
+#[test]
+fn stop() {
+    let mut engine = UciEngine::start(ENGINE, &[]).unwrap();
+    let stop = AtomicBool::new(false);
+
+    let chess_move = thread::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(Duration::from_millis(200));
+            stop.store(true, Ordering::Relaxed);
+        });
+        engine.best_move(
+            &Position::default(),
+            &[],
+            SearchLimits::default(),
+            &stop,
+            |_| {},
+        )
+    });
+    assert!(Position::default().is_legal(&chess_move.unwrap()));
+}
+
+#[test]
+fn plays_on_the_clock() {
+    let mut child = Command::new(ENGINE)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    let start = Instant::now();
+    writeln!(
+        stdin,
+        "position startpos moves e2e4\ngo wtime 300 btime 300 winc 0 binc 0"
+    )
+    .unwrap();
+
+    let bestmove = BufReader::new(child.stdout.take().unwrap())
+        .lines()
+        .map(Result::unwrap)
+        .find(|line| line.starts_with("bestmove"))
+        .unwrap();
+    assert!(start.elapsed() < Duration::from_secs(2));
+
+    let position: Position = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1"
+        .parse()
+        .unwrap();
+    let uci = bestmove.split_whitespace().nth(1).unwrap();
+    assert!(position.parse_uci(uci).is_ok(), "{bestmove}");
+
+    writeln!(stdin, "quit").unwrap();
+    child.wait().unwrap();
+}
+
+#[test]
+fn perft() {
+    let output = run("position startpos moves e2e4\nperft 2");
+    assert_eq!(
+        output.lines().filter(|line| line.contains(": ")).count(),
+        21
+    );
+    assert!(output.contains("d7d5: 31\n"));
+    assert!(output.ends_with("\nNodes searched: 600\n"));
+
+    let output = run(
+        "position fen r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1\ngo perft 1",
+    );
+    assert!(output.ends_with("\nNodes searched: 48\n"));
+}
+
+#[test]
+fn game_over() {
+    let output = run("position fen 7k/5Q2/6K1/8/8/8/8/8 b - - 0 1\ngo depth 2");
+    assert!(output.contains("bestmove 0000"));
+}
+
+#[test]
+fn invalid_commands() {
+    let output = run(
+        "position fen not a position\nposition startpos moves e2e5\nposition\ngo depth x\nisready",
+    );
+    assert_eq!(output.matches("info string").count(), 4);
+    assert!(output.ends_with("readyok\n"));
+}
+
+#[test]
+fn plays_variants() {
+    let output = run("uci");
+    assert!(output
+        .contains("option name UCI_Variant type combo default chess var chess var kingofthehill"));
+
+    // either centre square wins King of the Hill straight away
+    let output = run("setoption name UCI_Variant value kingofthehill\n\
+         position fen 4k3/8/8/8/8/4K3/8/8 w - - 0 1\n\
+         go depth 2");
+    assert!(
+        output.contains("bestmove e3d4") || output.contains("bestmove e3e4"),
+        "{output}"
+    );
+
+    let output = run("setoption name UCI_Variant value duckchess\nisready");
+    assert!(output.contains("info string unknown variant 'duckchess'"));
+}
+
+#[test]
+fn plays_chess960() {
+    let output = run("uci");
+    assert!(output.contains("option name UCI_Chess960 type check default false\n"));
+
+    // castling is the only mate, and is written as the King taking its rook once Chess960 is on
+    let position = "position fen 8/8/8/3B4/8/3N4/3PPP2/k3K2R w K - 0 1\ngo depth 2";
+    let output = run(position);
+    assert!(output.contains("bestmove e1g1"), "{output}");
+    let output = run(&format!(
+        "setoption name UCI_Chess960 value true\n{position}"
+    ));
+    assert!(!output.contains("info string"), "{output}");
+    assert!(output.contains("bestmove e1h1"), "{output}");
+
+    let output = run("setoption name UCI_Chess960 value maybe\nisready");
+    assert!(output.contains("info string invalid UCI_Chess960 'maybe'"));
+
+    // the library's own client switches the engine to Chess960 for Chess960 positions
+    let mut engine = UciEngine::start(ENGINE, &[]).unwrap();
+    let limits = SearchLimits {
+        depth: Some(1),
+        ..SearchLimits::default()
+    };
+    let position = Position::chess960(0).unwrap();
+    let chess_move = engine
+        .best_move(&position, &[], limits, &AtomicBool::new(false), |_| {})
+        .unwrap();
+    assert!(position.is_legal(&chess_move));
+}
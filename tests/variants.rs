@@ -0,0 +1,221 @@
+use std::sync::atomic::AtomicBool;
+
+use chess::{
+    variant_by_name, Antichess, DeadPosition, DrawReason, Game, KingOfTheHill, PgnGame, PgnTags,
+    PieceColour, Position, RacingKings, Score, SearchLimits, Session, Status, ThreeCheck,
+    STARTING_FEN, VARIANTS,
+};
+use common::{play, position};
+
+mod common;
+
+const WHITE: PieceColour = PieceColour::White;
+const BLACK: PieceColour = PieceColour::Black;
+
+#[test]
+fn variants_are_found_by_name() {
+    for variant in VARIANTS {
+        assert_eq!(
+            variant_by_name(variant.name()).unwrap().name(),
+            variant.name()
+        );
+        assert_eq!(
+            variant_by_name(variant.uci_name()).unwrap().name(),
+            variant.name()
+        );
+    }
+    assert_eq!(
+        variant_by_name("kingofthehill").unwrap().name(),
+        "King of the Hill"
+    );
+    assert_eq!(
+        variant_by_name("three check").unwrap().name(),
+        "Three-check"
+    );
+    assert!(variant_by_name("duck chess").is_none());
+    assert_eq!(Position::default().variant().name(), "Standard");
+}
+
+#[test]
+fn king_of_the_hill_is_won_in_the_centre() {
+    let mut game = Game::new(position("4k3/8/8/8/8/4K3/8/8 w - - 0 1", &KingOfTheHill));
+    // two bare Kings can still race to the centre
+    assert_eq!(game.status(), Status::OnGoing);
+
+    play(&mut game, &["e3e4"]);
+    assert_eq!(game.status(), Status::VariantWin(WHITE));
+    assert!(game.legal_moves().is_empty());
+    assert_eq!(game.result(), "1-0");
+
+    game.unmake_move();
+    play(&mut game, &["e3f4"]);
+    assert_eq!(game.status(), Status::OnGoing);
+
+    let starting = Position::starting(&KingOfTheHill);
+    assert_eq!(starting.to_fen(), STARTING_FEN);
+}
+
+#[test]
+fn three_check_counts_checks() {
+    let mut position = position("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", &ThreeCheck);
+    let fen = position.to_fen();
+    let key = position.key();
+
+    let check = position.parse_uci("a1a8").unwrap();
+    let undo = position.make_move(check);
+    assert_eq!(position.checks(WHITE), 1);
+    assert_eq!(position.to_fen(), "R3k3/8/8/8/8/8/8/4K3 b - - 1 1 +1+0");
+    assert_eq!(position.status(), Status::Check);
+
+    position.unmake_move(check, undo);
+    assert_eq!(position.checks(WHITE), 0);
+    assert_eq!(position.to_fen(), fen);
+    assert_eq!(position.key(), key);
+
+    // the same position with a check given has a different key
+    let checked: Position = "4k3/8/8/8/8/8/8/R3K3 w - - 0 1 +1+0".parse().unwrap();
+    assert_eq!(checked.checks(WHITE), 1);
+    assert_ne!(checked.key(), key);
+    assert!("4k3/8/8/8/8/8/8/R3K3 w - - 0 1 2+0"
+        .parse::<Position>()
+        .is_err());
+}
+
+#[test]
+fn three_check_is_won_by_the_third_check() {
+    let position = position("4k3/8/8/8/8/8/8/R3K3 w - - 0 1 +2+0", &ThreeCheck);
+    let check = position.parse_uci("a1a8").unwrap();
+    // the third check wins, even though the King could escape it
+    assert_eq!(position.to_san(&check), "Ra8+");
+
+    let mut game = Game::new(position);
+    game.make_move(check).unwrap();
+    assert_eq!(game.status(), Status::VariantWin(WHITE));
+    assert_eq!(game.result(), "1-0");
+
+    // a single Knight can still give check, so only bare Kings are dead
+    let knight = self::position("4k3/8/8/8/8/8/8/1N2K3 w - - 0 1", &ThreeCheck);
+    assert_eq!(knight.status(), Status::OnGoing);
+    let kings = self::position("4k3/8/8/8/8/8/8/4K3 w - - 0 1", &ThreeCheck);
+    assert_eq!(
+        kings.status(),
+        Status::Draw(DrawReason::DeadPosition(DeadPosition::KingVsKing))
+    );
+}
+
+#[test]
+fn antichess_captures_are_compulsory() {
+    let mut game = Game::new(Position::starting(&Antichess));
+    assert_eq!(game.position().castling().to_string(), "-");
+    play(&mut game, &["e2e4", "d7d5"]);
+    let moves: Vec<_> = game.legal_moves().iter().map(ToString::to_string).collect();
+    assert_eq!(moves, ["e4d5"]);
+
+    // Kings can be taken, and walk into attack
+    let position = position("8/8/8/8/8/8/1q6/K7 w - - 0 1", &Antichess);
+    let moves: Vec<_> = position
+        .legal_moves()
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    assert_eq!(moves, ["a1b2"]);
+    assert_eq!(position.to_san(&position.legal_moves()[0]), "Kxb2");
+}
+
+#[test]
+fn antichess_is_won_by_running_out_of_moves() {
+    let mut game = Game::new(position("8/8/8/8/8/8/1q6/K7 w - - 0 1", &Antichess));
+    play(&mut game, &["a1b2"]);
+    assert_eq!(game.status(), Status::VariantWin(BLACK));
+    assert_eq!(game.result(), "0-1");
+
+    // blocked pawns leave White with no moves, which wins
+    let blocked = position("8/8/8/8/p7/P7/8/8 w - - 0 1", &Antichess);
+    assert_eq!(blocked.status(), Status::VariantWin(WHITE));
+}
+
+#[test]
+fn antichess_pawns_promote_to_kings() {
+    let position = position("8/P7/8/8/8/8/8/7k w - - 0 1", &Antichess);
+    let promotion = position.parse_uci("a7a8k").unwrap();
+    assert_eq!(position.parse_san("a8=K").unwrap(), promotion);
+    assert_eq!(position.legal_moves().len(), 5);
+
+    let standard: Position = "8/P7/8/8/8/8/8/k6K w - - 0 1".parse().unwrap();
+    assert!(standard.parse_uci("a7a8k").is_err());
+}
+
+#[test]
+fn racing_kings_forbids_check() {
+    let position = position("8/8/8/8/8/8/k7/6RK w - - 0 1", &RacingKings);
+    assert!(position.parse_uci("g1g2").is_err());
+    assert!(position.parse_uci("g1g3").is_ok());
+
+    let starting = Position::starting(&RacingKings);
+    assert_eq!(starting.to_fen(), "8/8/8/8/8/8/krbnNBRK/qrbnNBRQ w - - 0 1");
+    assert_eq!(starting.legal_moves().len(), 21);
+}
+
+#[test]
+fn racing_kings_is_won_on_the_eighth_rank() {
+    let black = position("k7/8/8/8/8/8/8/7K w - - 0 1", &RacingKings);
+    assert_eq!(black.status(), Status::VariantWin(BLACK));
+
+    // Black cannot reach the eighth rank after White has
+    let white = position("7K/8/1k6/8/8/8/8/8 b - - 0 1", &RacingKings);
+    assert_eq!(white.status(), Status::VariantWin(WHITE));
+
+    // but Black has a move to reach it too, which draws
+    let mut game = Game::new(position("7K/1k6/8/8/8/8/8/8 b - - 0 1", &RacingKings));
+    assert_eq!(game.status(), Status::OnGoing);
+    play(&mut game, &["b7b8"]);
+    assert_eq!(game.status(), Status::Draw(DrawReason::VariantRule));
+    assert_eq!(game.result(), "1/2-1/2");
+}
+
+#[test]
+fn search_finds_variant_wins() {
+    let game = Game::new(position("4k3/8/8/8/8/8/2K5/8 w - - 0 1", &KingOfTheHill));
+    let limits = SearchLimits {
+        depth: Some(4),
+        ..SearchLimits::default()
+    };
+    let info = game
+        .search(limits, &AtomicBool::new(false), |_| {})
+        .unwrap();
+    assert_eq!(info.score, Score::Mate(2));
+}
+
+#[test]
+fn pgn_records_the_variant() {
+    let mut game = Game::new(Position::starting(&Antichess));
+    play(&mut game, &["e2e3", "b7b5", "f1b5"]);
+    let pgn = game.to_pgn(&PgnTags::default());
+    assert!(pgn.contains("[Variant \"Antichess\"]\n"));
+    // the starting position is the variant's own, so it needs no FEN
+    assert!(!pgn.contains("[FEN"));
+    assert!(pgn.contains("1. e3 b5 2. Bxb5"));
+
+    let games = PgnGame::parse_all(&pgn).unwrap();
+    assert_eq!(games[0].game.position().variant().name(), "Antichess");
+    assert_eq!(games[0].game.position().to_fen(), game.position().to_fen());
+    assert!(PgnGame::parse_all("[Variant \"Duck Chess\"]\n\n*").is_err());
+}
+
+#[test]
+fn sessions_record_the_variant() {
+    let mut game = Game::new(position("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", &ThreeCheck));
+    play(&mut game, &["a1a8"]);
+    let session = Session {
+        game,
+        white: "Alice".to_string(),
+        black: "Bob".to_string(),
+        clock: None,
+    };
+    let text = session.to_string();
+    assert!(text.contains("\nvariant 3check\n"));
+
+    let reloaded: Session = text.parse().unwrap();
+    assert_eq!(reloaded.game.position().variant().name(), "Three-check");
+    assert_eq!(reloaded.game.position().checks(WHITE), 1);
+}
@@ -0,0 +1,101 @@
+use chess::Position;
+
+const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+/// Checks the incrementally updated key against the key of the same position read from FEN at
+/// every node of the move tree, and that unmaking each move restores the key
+fn assert_keys(position: &mut Position, depth: u32) {
+    let expected: Position = position.to_fen().parse().unwrap();
+    assert_eq!(position.key(), expected.key(), "{}", position.to_fen());
+    if depth == 0 {
+        return;
+    }
+
+    for chess_move in position.legal_moves() {
+        let key = position.key();
+        let undo = position.make_move(chess_move);
+        assert_keys(position, depth - 1);
+        position.unmake_move(chess_move, undo);
+        assert_eq!(position.key(), key);
+    }
+}
+
+#[test]
+fn incremental_keys_match() {
+    assert_keys(&mut Position::default(), 3);
+    assert_keys(&mut KIWIPETE.parse().unwrap(), 2);
+    // en passant, promotions and castling
+    assert_keys(
+        &mut "r3k2r/1P4P1/8/3pP3/8/8/1p4p1/R3K2R w KQkq d6 0 1"
+            .parse()
+            .unwrap(),
+        3,
+    );
+}
+
+#[test]
+fn keys_distinguish_positions() {
+    let key = |fen: &str| fen.parse::<Position>().unwrap().key();
+    let start = Position::default().key();
+
+    assert_ne!(
+        start,
+        key("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1")
+    );
+    assert_ne!(
+        start,
+        key("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w Kkq - 0 1")
+    );
+    // the move counters are not part of the position
+    assert_eq!(
+        start,
+        key("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 12 40")
+    );
+}
+
+/// Plays `moves`, given in UCI, from the starting position
+fn play(moves: &[&str]) -> Position {
+    let mut position = Position::default();
+    for uci in moves {
+        let chess_move = position.parse_uci(uci).unwrap();
+        position.make_move(chess_move);
+    }
+    position
+}
+
+/// The keys given in the Polyglot book format specification, which an incrementally updated key
+/// and a key calculated from FEN must both match
+#[test]
+fn keys_match_polyglot() {
+    let cases: [(&[&str], u64); 9] = [
+        (&[], 0x463b_9618_1691_fc9c),
+        (&["e2e4"], 0x823c_9b50_fd11_4196),
+        (&["e2e4", "d7d5"], 0x0756_b944_61c5_0fb0),
+        (&["e2e4", "d7d5", "e4e5"], 0x662f_afb9_65db_29d4),
+        // f6 can be taken en passant, so its file is part of the key
+        (&["e2e4", "d7d5", "e4e5", "f7f5"], 0x22a4_8b5a_8e47_ff78),
+        (
+            &["e2e4", "d7d5", "e4e5", "f7f5", "e1e2"],
+            0x652a_607c_a3f2_42c1,
+        ),
+        (
+            &["e2e4", "d7d5", "e4e5", "f7f5", "e1e2", "e8f7"],
+            0x00fd_d303_c946_bdd9,
+        ),
+        (
+            &["a2a4", "b7b5", "h2h4", "b5b4", "c2c4"],
+            0x3c81_23ea_7b06_7637,
+        ),
+        (
+            &["a2a4", "b7b5", "h2h4", "b5b4", "c2c4", "b4c3", "a1a3"],
+            0x5c3f_9b82_9b27_9560,
+        ),
+    ];
+
+    for (moves, key) in cases {
+        let position = play(moves);
+        assert_eq!(position.key(), key, "{}", position.to_fen());
+        let parsed: Position = position.to_fen().parse().unwrap();
+        assert_eq!(parsed.key(), key, "{}", position.to_fen());
+    }
+}